@@ -27,15 +27,15 @@ fn analyze_element(element: &mut Element, analysis: &mut Option<&mut ContextAnal
         match node {
             Node::Function { name, arguments: _ } => {
                 if let Some(analysis) = analysis {
-                    analysis.functions.insert(name.clone(), None);
+                    analysis.functions.insert(name.to_string(), None);
                 }
-                cache.functions.insert(name.clone());
+                cache.functions.insert(name.to_string());
             }
             Node::Variable(name) => {
                 if let Some(analysis) = analysis {
-                    analysis.variables.insert(name.clone(), None);
+                    analysis.variables.insert(name.to_string(), None);
                 }
-                cache.variables.insert(name.clone());
+                cache.variables.insert(name.to_string());
             }
             _ => (),
         }
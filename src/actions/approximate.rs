@@ -0,0 +1,1093 @@
+use std::cell::OnceCell;
+
+use num::{BigInt, BigRational, Integer, One, Signed, ToPrimitive, Zero};
+
+use crate::ast::{
+    product::Product, Constant, Element, Expression, Identifier, Node, NodeOrExpression, Sign,
+};
+
+use super::evaluate::{Bindings, EvalError};
+
+/// How [`Element::approximate`] computes its bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    /// Hardware floating point, with every operation rounded outwards.
+    F64,
+    /// Exact rational bounds which are at most `10^-digits` apart, relative to the value for
+    /// values above one.
+    Digits(u32),
+}
+
+/// Bounds which are guaranteed to contain the exact value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interval {
+    pub lower: BigRational,
+    pub upper: BigRational,
+}
+
+impl Interval {
+    pub fn midpoint(&self) -> BigRational {
+        (&self.lower + &self.upper) / BigRational::from_integer(2.into())
+    }
+
+    pub fn radius(&self) -> BigRational {
+        (&self.upper - &self.lower) / BigRational::from_integer(2.into())
+    }
+
+    pub fn contains(&self, value: &BigRational) -> bool {
+        self.lower <= *value && *value <= self.upper
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.midpoint().to_f64().unwrap_or(f64::NAN)
+    }
+}
+
+/// [`Precision::Digits`] doubles the number of bits until the bounds are close enough, up to
+/// this many.
+const MAX_BITS: u64 = 4096;
+/// Exponents above this are refused, the result would have too many digits.
+const MAX_EXPONENT: u32 = 100_000;
+/// The standard library doesn't promise correctly rounded transcendental functions, so their
+/// results are widened by this many units in the last place.
+const F64_ERROR_ULPS: u32 = 2;
+
+impl Element {
+    /// Bounds the value of the element, with variables replaced by their values in `bindings`.
+    ///
+    /// Unlike [`Element::evaluate`] it supports `sin`, `cos`, `tan`, `cot`, their inverses
+    /// `arcsin`, `arccos`, `arctan` and `arccot`, `exp`, `ln`, `log` (with an optional base as
+    /// the second argument), `sqrt` and the constants `pi` and `e`.
+    pub fn approximate(
+        &self,
+        bindings: &Bindings,
+        precision: Precision,
+    ) -> Result<Interval, EvalError> {
+        match precision {
+            Precision::F64 => {
+                let approximator = Approximator {
+                    bindings,
+                    engine: F64Engine,
+                };
+                let (lower, upper) = F64Engine.bounds(&approximator.element(self)?)?;
+
+                Ok(Interval { lower, upper })
+            }
+            Precision::Digits(digits) => {
+                let tolerance =
+                    BigRational::new(BigInt::one(), num::pow(BigInt::from(10), digits as usize));
+                let mut bits = u64::from(digits) * 10 / 3 + 16;
+
+                loop {
+                    let approximator = Approximator {
+                        bindings,
+                        engine: RationalEngine::new(bits),
+                    };
+                    let retry = bits < MAX_BITS;
+
+                    match approximator.element(self) {
+                        Ok((lower, upper)) => {
+                            let interval = Interval { lower, upper };
+                            let scale = interval.midpoint().abs().max(BigRational::one());
+                            if interval.radius() <= &tolerance * scale {
+                                return Ok(interval);
+                            }
+                            // the bounds aren't close enough even with the most bits
+                            if !retry {
+                                return Err(EvalError::Imprecise);
+                            }
+                        }
+                        // more bits might tell on which side of a discontinuity the value is
+                        Err(EvalError::Imprecise) if retry => {}
+                        Err(error) => return Err(error),
+                    }
+
+                    bits *= 2;
+                }
+            }
+        }
+    }
+}
+
+/// Interval arithmetic, every result contains all the values an operation can have for
+/// arguments inside of the argument intervals.
+trait Engine {
+    type Value: Clone;
+
+    fn number(&self, number: &BigRational) -> Self::Value;
+    fn interval(&self, lower: &BigRational, upper: &BigRational) -> Self::Value;
+    fn bounds(&self, value: &Self::Value) -> Result<(BigRational, BigRational), EvalError>;
+    fn pi(&self) -> Self::Value;
+
+    fn add(&self, lhs: &Self::Value, rhs: &Self::Value) -> Self::Value;
+    fn neg(&self, value: &Self::Value) -> Self::Value;
+    fn mul(&self, lhs: &Self::Value, rhs: &Self::Value) -> Self::Value;
+    /// Only called for values which don't contain zero.
+    fn recip(&self, value: &Self::Value) -> Self::Value;
+
+    /// Only called for non-negative values.
+    fn sqrt(&self, value: &Self::Value) -> Self::Value;
+    /// Only called for values within [`MAX_EXPONENT`] of zero.
+    fn exp(&self, value: &Self::Value) -> Self::Value;
+    /// Only called for positive values.
+    fn ln(&self, value: &Self::Value) -> Self::Value;
+    fn sin(&self, value: &Self::Value) -> Self::Value;
+    fn cos(&self, value: &Self::Value) -> Self::Value;
+    fn atan(&self, value: &Self::Value) -> Self::Value;
+}
+
+/// Walks an element with an [`Engine`], checking the domains of the operations it can't
+/// evaluate everywhere.
+struct Approximator<'a, E> {
+    bindings: &'a Bindings,
+    engine: E,
+}
+
+impl<E: Engine> Approximator<'_, E> {
+    fn element(&self, element: &Element) -> Result<E::Value, EvalError> {
+        let value = match &element.node_or_expression {
+            NodeOrExpression::Node(node) => self.node(node)?,
+            NodeOrExpression::Expression(expression) => self.expression(expression)?,
+        };
+
+        Ok(match element.sign {
+            Sign::Positive => value,
+            Sign::Negative => self.engine.neg(&value),
+        })
+    }
+
+    fn expression(&self, expression: &Expression) -> Result<E::Value, EvalError> {
+        let mut sum = self.engine.number(&BigRational::zero());
+        for product in &expression.products {
+            sum = self.engine.add(&sum, &self.product(product)?);
+        }
+
+        Ok(sum)
+    }
+
+    fn product(&self, product: &Product) -> Result<E::Value, EvalError> {
+        let mut value = self.engine.number(&BigRational::one());
+        for element in &product.numerator {
+            value = self.engine.mul(&value, &self.element(element)?);
+        }
+        for element in &product.denominator {
+            value = self
+                .engine
+                .mul(&value, &self.recip(&self.element(element)?)?);
+        }
+
+        Ok(value)
+    }
+
+    fn node(&self, node: &Node) -> Result<E::Value, EvalError> {
+        match node {
+            Node::Number(number) => Ok(self.engine.number(number)),
+            Node::Variable(name) => match self.bindings.get(name) {
+                Some(value) => Ok(self.engine.number(value)),
+                None => Err(EvalError::UnboundVariable(name.clone())),
+            },
+            Node::Constant(Constant::Pi) => Ok(self.engine.pi()),
+            Node::Constant(Constant::E) => {
+                Ok(self.engine.exp(&self.engine.number(&BigRational::one())))
+            }
+            Node::Constant(Constant::I | Constant::Infinity) => Err(EvalError::NotReal),
+            Node::Power { base, power } => self.power(base, power),
+            Node::Modulo { lhs, rhs } => match node.evaluate(self.bindings) {
+                Ok(value) => Ok(self.engine.number(&value)),
+                Err(EvalError::DivisionByZero) => Err(EvalError::DivisionByZero),
+                Err(_) => {
+                    // `lhs - n*rhs`, if the quotient is certainly between the same integers
+                    let lhs = self.element(lhs)?;
+                    let rhs = self.element(rhs)?;
+                    let quotient = self.engine.mul(&lhs, &self.recip(&rhs)?);
+
+                    let (lower, upper) = self.engine.bounds(&quotient)?;
+                    if lower.trunc() != upper.trunc() {
+                        return Err(EvalError::Imprecise);
+                    }
+
+                    let multiple = self.engine.mul(&self.engine.number(&lower.trunc()), &rhs);
+                    Ok(self.engine.add(&lhs, &self.engine.neg(&multiple)))
+                }
+            },
+            // only defined for exact integers
+            Node::Factorial { .. } => Ok(self.engine.number(&node.evaluate(self.bindings)?)),
+            Node::Function { name, arguments } => self.function(name, arguments),
+        }
+    }
+
+    fn power(&self, base: &Element, power: &Element) -> Result<E::Value, EvalError> {
+        let base = self.element(base)?;
+
+        // exact exponents keep powers of negative bases defined, like `(-2)^3` and `(-8)^(1/3)`
+        if let Ok(exponent) = power.evaluate(self.bindings) {
+            if exponent.is_integer() {
+                return self.integer_power(&base, &exponent);
+            }
+
+            let (_, upper) = self.engine.bounds(&base)?;
+            if exponent.denom().is_odd() && upper.is_negative() {
+                let magnitude =
+                    self.real_power(&self.engine.neg(&base), &self.engine.number(&exponent))?;
+                return Ok(if exponent.numer().is_odd() {
+                    self.engine.neg(&magnitude)
+                } else {
+                    magnitude
+                });
+            }
+        }
+
+        self.real_power(&base, &self.element(power)?)
+    }
+
+    fn integer_power(
+        &self,
+        base: &E::Value,
+        exponent: &BigRational,
+    ) -> Result<E::Value, EvalError> {
+        let degree = exponent
+            .to_integer()
+            .abs()
+            .to_u32()
+            .filter(|degree| *degree <= MAX_EXPONENT)
+            .ok_or_else(|| EvalError::TooLarge(exponent.clone()))?;
+
+        let (lower, upper) = self.engine.bounds(base)?;
+        let result = if !lower.is_negative() {
+            self.power_of_positive(base, degree)
+        } else if !upper.is_positive() {
+            let magnitude = self.power_of_positive(&self.engine.neg(base), degree);
+            if degree.is_odd() {
+                self.engine.neg(&magnitude)
+            } else {
+                magnitude
+            }
+        } else {
+            // the power of an interval around zero is between the powers of its ends
+            let power_of = |number: &BigRational| {
+                let power = self.power_of_positive(&self.engine.number(number), degree);
+                self.engine.bounds(&power).map(|(_, upper)| upper)
+            };
+            let (left, right) = (power_of(&-lower)?, power_of(&upper)?);
+
+            if degree.is_odd() {
+                self.engine.interval(&-left, &right)
+            } else {
+                self.engine.interval(&BigRational::zero(), &left.max(right))
+            }
+        };
+
+        if exponent.is_negative() {
+            self.recip(&result)
+        } else {
+            Ok(result)
+        }
+    }
+
+    /// Squares and multiplies, `base` must not be negative.
+    fn power_of_positive(&self, base: &E::Value, degree: u32) -> E::Value {
+        let mut result = self.engine.number(&BigRational::one());
+        for bit in (0..u32::BITS - degree.leading_zeros()).rev() {
+            result = self.engine.mul(&result, &result);
+            if degree >> bit & 1 == 1 {
+                result = self.engine.mul(&result, base);
+            }
+        }
+
+        result
+    }
+
+    /// `base^exponent = exp(exponent * ln(base))` for positive bases.
+    fn real_power(&self, base: &E::Value, exponent: &E::Value) -> Result<E::Value, EvalError> {
+        let (lower, upper) = self.engine.bounds(base)?;
+
+        if lower.is_zero() && upper.is_zero() {
+            let (exponent_lower, _) = self.engine.bounds(exponent)?;
+            return if exponent_lower.is_positive() {
+                Ok(self.engine.number(&BigRational::zero()))
+            } else {
+                Err(EvalError::Imprecise)
+            };
+        }
+        if upper.is_negative() {
+            return Err(EvalError::NotReal);
+        }
+        if !lower.is_positive() {
+            return Err(EvalError::Imprecise);
+        }
+
+        self.exp(&self.engine.mul(exponent, &self.engine.ln(base)))
+    }
+
+    fn function(&self, name: &Identifier, arguments: &[Element]) -> Result<E::Value, EvalError> {
+        let engine = &self.engine;
+        let arguments = arguments
+            .iter()
+            .map(|argument| self.element(argument))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let half_pi = || {
+            engine.mul(
+                &engine.pi(),
+                &engine.number(&BigRational::new(1.into(), 2.into())),
+            )
+        };
+
+        let value = match (name.as_plain(), arguments.as_slice()) {
+            (Some("sin"), [x]) => engine.sin(x),
+            (Some("cos"), [x]) => engine.cos(x),
+            (Some("tan"), [x]) => engine.mul(&engine.sin(x), &self.recip(&engine.cos(x))?),
+            (Some("cot"), [x]) => engine.mul(&engine.cos(x), &self.recip(&engine.sin(x))?),
+            (Some("arcsin"), [x]) => self.arcsin(name, x)?,
+            (Some("arccos"), [x]) => engine.add(&half_pi(), &engine.neg(&self.arcsin(name, x)?)),
+            (Some("arctan"), [x]) => engine.atan(x),
+            (Some("arccot"), [x]) => engine.add(&half_pi(), &engine.neg(&engine.atan(x))),
+            (Some("exp"), [x]) => self.exp(x)?,
+            (Some("ln" | "log"), [x]) => self.ln(name, x)?,
+            (Some("log"), [x, base]) => {
+                engine.mul(&self.ln(name, x)?, &self.recip(&self.ln(name, base)?)?)
+            }
+            (Some("sqrt"), [x]) => self.sqrt(name, x)?,
+            _ => return Err(EvalError::UnsupportedFunction(name.clone())),
+        };
+
+        Ok(value)
+    }
+
+    fn recip(&self, value: &E::Value) -> Result<E::Value, EvalError> {
+        let (lower, upper) = self.engine.bounds(value)?;
+
+        if lower.is_zero() && upper.is_zero() {
+            Err(EvalError::DivisionByZero)
+        } else if !lower.is_positive() && !upper.is_negative() {
+            Err(EvalError::Imprecise)
+        } else {
+            Ok(self.engine.recip(value))
+        }
+    }
+
+    fn exp(&self, value: &E::Value) -> Result<E::Value, EvalError> {
+        let (lower, upper) = self.engine.bounds(value)?;
+        let limit = BigRational::from_integer(MAX_EXPONENT.into());
+
+        match (lower.abs() > limit, upper.abs() > limit) {
+            (false, false) => Ok(self.engine.exp(value)),
+            (true, _) => Err(EvalError::TooLarge(lower)),
+            (_, true) => Err(EvalError::TooLarge(upper)),
+        }
+    }
+
+    fn ln(&self, name: &Identifier, value: &E::Value) -> Result<E::Value, EvalError> {
+        let (lower, upper) = self.engine.bounds(value)?;
+
+        if !upper.is_positive() {
+            Err(EvalError::OutOfDomain(name.clone()))
+        } else if !lower.is_positive() {
+            Err(EvalError::Imprecise)
+        } else {
+            Ok(self.engine.ln(value))
+        }
+    }
+
+    /// Arguments whose bounds contain zero might be negative, more bits might tell.
+    fn sqrt(&self, name: &Identifier, value: &E::Value) -> Result<E::Value, EvalError> {
+        let (lower, upper) = self.engine.bounds(value)?;
+
+        if upper.is_negative() {
+            Err(EvalError::OutOfDomain(name.clone()))
+        } else if lower.is_negative() {
+            Err(EvalError::Imprecise)
+        } else {
+            Ok(self.engine.sqrt(value))
+        }
+    }
+
+    /// `arcsin(x) = 2·atan(x / (1 + sqrt(1 - x²)))`, which is defined on all of `[-1, 1]`.
+    fn arcsin(&self, name: &Identifier, value: &E::Value) -> Result<E::Value, EvalError> {
+        let engine = &self.engine;
+        let one = BigRational::one();
+
+        let (lower, upper) = engine.bounds(value)?;
+        if lower > one || upper < -&one {
+            return Err(EvalError::OutOfDomain(name.clone()));
+        }
+        let value = engine.interval(&lower.max(-&one), &upper.min(one.clone()));
+
+        let square = self.integer_power(&value, &BigRational::from_integer(2.into()))?;
+        let root = self.sqrt(
+            name,
+            &engine.add(&engine.number(&one), &engine.neg(&square)),
+        )?;
+        let reduced = engine.mul(
+            &value,
+            &self.recip(&engine.add(&engine.number(&one), &root))?,
+        );
+
+        Ok(engine.mul(
+            &engine.number(&BigRational::from_integer(2.into())),
+            &engine.atan(&reduced),
+        ))
+    }
+}
+
+struct F64Engine;
+
+fn widen(lower: f64, upper: f64, ulps: u32) -> (f64, f64) {
+    (0..ulps).fold((lower, upper), |(lower, upper), _| {
+        (lower.next_down(), upper.next_up())
+    })
+}
+
+/// Moves a rounded `value` one step towards the exact result `value + error`.
+fn round_towards(value: f64, error: f64, up: bool) -> f64 {
+    if up && error > 0.0 {
+        value.next_up()
+    } else if !up && error < 0.0 {
+        value.next_down()
+    } else {
+        value
+    }
+}
+
+impl F64Engine {
+    /// Applies `function` to both ends of an interval, it has to be increasing.
+    fn increasing(&self, value: &(f64, f64), function: fn(f64) -> f64) -> (f64, f64) {
+        let (lower, _) = widen(function(value.0), function(value.0), F64_ERROR_ULPS);
+        let (_, upper) = widen(function(value.1), function(value.1), F64_ERROR_ULPS);
+        (lower, upper)
+    }
+
+    /// `sin` and `cos` change at most as fast as their argument.
+    fn periodic(&self, value: &(f64, f64), function: fn(f64) -> f64) -> (f64, f64) {
+        let middle = value.0 / 2.0 + value.1 / 2.0;
+        let radius = (value.1 - middle).max(middle - value.0).next_up();
+        let result = function(middle);
+
+        let (lower, upper) = widen(result - radius, result + radius, F64_ERROR_ULPS + 1);
+        (lower.max(-1.0), upper.min(1.0))
+    }
+}
+
+impl Engine for F64Engine {
+    type Value = (f64, f64);
+
+    fn number(&self, number: &BigRational) -> Self::Value {
+        let value = number.to_f64().unwrap_or(f64::NAN);
+        if BigRational::from_float(value).as_ref() == Some(number) {
+            (value, value)
+        } else {
+            widen(value, value, 1)
+        }
+    }
+
+    fn interval(&self, lower: &BigRational, upper: &BigRational) -> Self::Value {
+        (self.number(lower).0, self.number(upper).1)
+    }
+
+    fn bounds(&self, value: &Self::Value) -> Result<(BigRational, BigRational), EvalError> {
+        match (
+            BigRational::from_float(value.0),
+            BigRational::from_float(value.1),
+        ) {
+            (Some(lower), Some(upper)) => Ok((lower, upper)),
+            _ => Err(EvalError::Imprecise),
+        }
+    }
+
+    fn pi(&self) -> Self::Value {
+        widen(std::f64::consts::PI, std::f64::consts::PI, 1)
+    }
+
+    fn add(&self, lhs: &Self::Value, rhs: &Self::Value) -> Self::Value {
+        let sum = |lhs: f64, rhs: f64, up| {
+            // Knuth's two-sum, the rounding error of the sum is exact
+            let sum = lhs + rhs;
+            let rhs_part = sum - lhs;
+            let error = (lhs - (sum - rhs_part)) + (rhs - rhs_part);
+            round_towards(sum, error, up)
+        };
+
+        (sum(lhs.0, rhs.0, false), sum(lhs.1, rhs.1, true))
+    }
+
+    fn neg(&self, value: &Self::Value) -> Self::Value {
+        (-value.1, -value.0)
+    }
+
+    fn mul(&self, lhs: &Self::Value, rhs: &Self::Value) -> Self::Value {
+        let products = [
+            (lhs.0, rhs.0),
+            (lhs.0, rhs.1),
+            (lhs.1, rhs.0),
+            (lhs.1, rhs.1),
+        ]
+        .map(|(lhs, rhs)| {
+            // a fused multiply-add gives the exact rounding error of the product
+            let product = lhs * rhs;
+            let error = lhs.mul_add(rhs, -product);
+            (
+                round_towards(product, error, false),
+                round_towards(product, error, true),
+            )
+        });
+        let lower = products
+            .iter()
+            .map(|(lower, _)| *lower)
+            .fold(f64::INFINITY, f64::min);
+        let upper = products
+            .iter()
+            .map(|(_, upper)| *upper)
+            .fold(f64::NEG_INFINITY, f64::max);
+
+        (lower, upper)
+    }
+
+    fn recip(&self, value: &Self::Value) -> Self::Value {
+        ((1.0 / value.1).next_down(), (1.0 / value.0).next_up())
+    }
+
+    fn sqrt(&self, value: &Self::Value) -> Self::Value {
+        (
+            value.0.sqrt().next_down().max(0.0),
+            value.1.sqrt().next_up(),
+        )
+    }
+
+    fn exp(&self, value: &Self::Value) -> Self::Value {
+        let (lower, upper) = self.increasing(value, f64::exp);
+        (lower.max(0.0), upper)
+    }
+
+    fn ln(&self, value: &Self::Value) -> Self::Value {
+        self.increasing(value, f64::ln)
+    }
+
+    fn sin(&self, value: &Self::Value) -> Self::Value {
+        self.periodic(value, f64::sin)
+    }
+
+    fn cos(&self, value: &Self::Value) -> Self::Value {
+        self.periodic(value, f64::cos)
+    }
+
+    fn atan(&self, value: &Self::Value) -> Self::Value {
+        self.increasing(value, f64::atan)
+    }
+}
+
+type Bounds = (BigRational, BigRational);
+
+/// Exact rational bounds, rounded outwards to `bits` significant bits after every operation so
+/// the numbers stay small. Transcendental functions are summed as fixed point series.
+struct RationalEngine {
+    bits: u64,
+    pi: OnceCell<Bounds>,
+}
+
+fn power_of_two(exponent: i64) -> BigRational {
+    let power = BigInt::one() << exponent.unsigned_abs();
+    if exponent >= 0 {
+        BigRational::from_integer(power)
+    } else {
+        BigRational::new(BigInt::one(), power)
+    }
+}
+
+/// `floor(value * 2^width)`, a fixed point number with `width` bits after the point.
+fn to_fixed(value: &BigRational, width: u64) -> BigInt {
+    (value * power_of_two(width as i64)).floor().to_integer()
+}
+
+/// The bounds of the fixed point number `value` with an error of at most `error` units.
+fn from_fixed(value: BigInt, error: u64, width: u64) -> Bounds {
+    let scale = power_of_two(-(width as i64));
+    (
+        BigRational::from_integer(&value - error) * &scale,
+        BigRational::from_integer(value + error) * scale,
+    )
+}
+
+/// The series of `sin(x)` or `cos(x)` in fixed point, for `|x| <= 1`. Returns the sum and its
+/// error in units of the last place.
+fn sin_cos_series(x: &BigInt, width: u64, cosine: bool) -> (BigInt, u64) {
+    let square = (x * x) >> width;
+    let (mut term, mut k) = if cosine {
+        (BigInt::one() << width, 0u64)
+    } else {
+        (x.clone(), 1u64)
+    };
+    let mut sum = term.clone();
+    let mut steps = 0;
+
+    while !term.is_zero() {
+        term = -((term * &square) >> width) / ((k + 1) * (k + 2));
+        sum += &term;
+        k += 2;
+        steps += 1;
+    }
+
+    // every term is off by a few units at most, the tail is smaller than the last term
+    (sum, 8 * steps + 8)
+}
+
+/// The series of `atan(x)` or `atanh(x)` in fixed point, for `|x| <= 1/2`.
+fn arctan_series(x: &BigInt, width: u64, hyperbolic: bool) -> (BigInt, u64) {
+    let square = (x * x) >> width;
+    let mut power = x.clone();
+    let mut sum = BigInt::zero();
+    let mut k = 0u64;
+
+    while !power.is_zero() {
+        let term = &power / (2 * k + 1);
+        if hyperbolic || k.is_even() {
+            sum += term;
+        } else {
+            sum -= term;
+        }
+        power = (power * &square) >> width;
+        k += 1;
+    }
+
+    (sum, 4 * k + 8)
+}
+
+/// The series of `exp(x)` in fixed point, for `|x| <= 1/2`.
+fn exp_series(x: &BigInt, width: u64) -> (BigInt, u64) {
+    let mut term = BigInt::one() << width;
+    let mut sum = term.clone();
+    let mut k = 1u64;
+
+    while !term.is_zero() {
+        term = ((term * x) >> width) / k;
+        sum += &term;
+        k += 1;
+    }
+
+    (sum, 4 * k + 8)
+}
+
+impl RationalEngine {
+    fn new(bits: u64) -> Self {
+        RationalEngine {
+            bits,
+            pi: OnceCell::new(),
+        }
+    }
+
+    /// The width of the fixed point numbers in series, with guard bits for their errors.
+    fn width(&self) -> u64 {
+        self.bits + 32
+    }
+
+    fn round(&self, value: &BigRational, up: bool) -> BigRational {
+        if value.is_zero() {
+            return BigRational::zero();
+        }
+
+        let magnitude = value.numer().bits() as i64 - value.denom().bits() as i64;
+        let scale = power_of_two(self.bits as i64 - magnitude);
+        let scaled = value * &scale;
+        let rounded = if up { scaled.ceil() } else { scaled.floor() };
+
+        rounded / scale
+    }
+
+    fn rounded(&self, lower: BigRational, upper: BigRational) -> Bounds {
+        (self.round(&lower, false), self.round(&upper, true))
+    }
+
+    /// Machin's formula, `π = 16·atan(1/5) - 4·atan(1/239)`.
+    fn pi_bounds(&self) -> &Bounds {
+        self.pi.get_or_init(|| {
+            let width = self.width();
+            let series = |denominator: i64| {
+                let x = to_fixed(&BigRational::new(1.into(), denominator.into()), width);
+                arctan_series(&x, width, false)
+            };
+            let ((fifth, fifth_error), (other, other_error)) = (series(5), series(239));
+
+            let (lower, upper) = from_fixed(
+                fifth * 16 - other * 4,
+                16 * (fifth_error + 1) + 4 * (other_error + 1),
+                width,
+            );
+            self.rounded(lower, upper)
+        })
+    }
+
+    /// `sin(x)` or `cos(x)` of a single number, reduced by a multiple of `π/2` to the quarter
+    /// period around zero.
+    fn sin_cos_point(&self, x: &BigRational, cosine: bool) -> Bounds {
+        let (pi_lower, pi_upper) = self.pi_bounds();
+        let two = BigRational::from_integer(2.into());
+
+        let half_pi = (pi_lower + pi_upper) / BigRational::from_integer(4.into());
+        let quarters = BigRational::from_integer((x / half_pi).round().to_integer());
+
+        // π is only known within its bounds, so the reduced argument is too
+        let first = x - &quarters * pi_lower / &two;
+        let second = x - &quarters * pi_upper / &two;
+        let (lower, upper) = if first <= second {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        let radius = (&upper - &lower) / &two;
+        if radius > BigRational::one() {
+            return (-BigRational::one(), BigRational::one());
+        }
+
+        let width = self.width();
+        let middle = to_fixed(&((lower + upper) / two), width);
+        let quadrant = (quarters.to_integer() + i32::from(cosine)).mod_floor(&4.into());
+
+        let (value, error) = sin_cos_series(&middle, width, quadrant.is_odd());
+        let value = if quadrant > BigInt::one() {
+            -value
+        } else {
+            value
+        };
+
+        let (lower, upper) = from_fixed(value, error + 1, width);
+        (lower - &radius, upper + radius)
+    }
+
+    /// `sin` and `cos` change at most as fast as their argument.
+    fn periodic(&self, value: &Bounds, cosine: bool) -> Bounds {
+        let two = BigRational::from_integer(2.into());
+        let one = BigRational::one();
+
+        let middle = (&value.0 + &value.1) / &two;
+        let radius = (&value.1 - &value.0) / two;
+        let (lower, upper) = self.sin_cos_point(&middle, cosine);
+
+        self.rounded((lower - &radius).max(-&one), (upper + radius).min(one))
+    }
+
+    /// `sqrt(p/q) = sqrt(p·q)/q`, from the integer square root of `p·q` scaled by an even power
+    /// of two.
+    fn sqrt_bound(&self, value: &BigRational, up: bool) -> BigRational {
+        if value.is_zero() {
+            return BigRational::zero();
+        }
+
+        let product = value.numer() * value.denom();
+        let shift = (2 * self.bits).saturating_sub(product.bits()) + 2;
+        let shift = shift + shift % 2;
+
+        let root = (product << shift).sqrt();
+        let root = if up { root + 1 } else { root };
+
+        self.round(&BigRational::new(root, value.denom() << (shift / 2)), up)
+    }
+
+    /// `exp(x) = exp(x/2^s)^(2^s)`, with `x/2^s` small enough for the series.
+    fn exp_point(&self, x: &BigRational) -> Bounds {
+        let magnitude = x.numer().bits() as i64 - x.denom().bits() as i64;
+        let halvings = (magnitude + 2).max(0) as u64;
+
+        // every squaring doubles the relative error
+        let engine = RationalEngine::new(self.bits + halvings + 8);
+        let width = engine.width();
+
+        let reduced = to_fixed(&(x * power_of_two(-(halvings as i64))), width);
+        let (value, error) = exp_series(&reduced, width);
+
+        let mut result = from_fixed(value, error + 2, width);
+        for _ in 0..halvings {
+            result = engine.mul(&result, &result);
+        }
+
+        self.rounded(result.0, result.1)
+    }
+
+    /// `x = 2^k·y` with `y` close to one, so `ln(x) = k·ln(2) + 2·atanh((y - 1)/(y + 1))`.
+    fn ln_point(&self, x: &BigRational) -> Bounds {
+        let k = x.numer().bits() as i64 - x.denom().bits() as i64;
+        let y = x * power_of_two(-k);
+        let t = (&y - BigRational::one()) / (&y + BigRational::one());
+
+        // the error of ln(2) is multiplied by k
+        let width = self.width() + u64::from(u64::BITS - k.unsigned_abs().leading_zeros());
+        let (half_ln_two, ln_two_error) = arctan_series(
+            &to_fixed(&BigRational::new(1.into(), 3.into()), width),
+            width,
+            true,
+        );
+        let (value, error) = arctan_series(&to_fixed(&t, width), width, true);
+
+        let (lower, upper) = from_fixed(
+            half_ln_two * (2 * k) + value * 2,
+            2 * k.unsigned_abs() * (ln_two_error + 2) + 2 * (error + 2),
+            width,
+        );
+        self.rounded(lower, upper)
+    }
+
+    fn atan_point(&self, x: &BigRational) -> Bounds {
+        let one = BigRational::one();
+        let two = BigRational::from_integer(2.into());
+
+        if x.is_negative() {
+            let (lower, upper) = self.atan_point(&-x);
+            return (-upper, -lower);
+        }
+
+        if *x > one {
+            // atan(x) = π/2 - atan(1/x)
+            let (lower, upper) = self.atan_point(&x.recip());
+            let (pi_lower, pi_upper) = self.pi_bounds();
+            return self.rounded(pi_lower / &two - upper, pi_upper / &two - lower);
+        }
+
+        if *x > BigRational::new(1.into(), 2.into()) {
+            // atan(x) = 2·atan(x / (1 + sqrt(1 + x²)))
+            let root = self.sqrt(&self.number(&(&one + x * x)));
+            let reduced = self.mul(
+                &self.number(x),
+                &self.recip(&self.add(&self.number(&one), &root)),
+            );
+            let (lower, _) = self.atan_point(&reduced.0);
+            let (_, upper) = self.atan_point(&reduced.1);
+            return self.rounded(lower * &two, upper * two);
+        }
+
+        let width = self.width();
+        let (value, error) = arctan_series(&to_fixed(x, width), width, false);
+        let (lower, upper) = from_fixed(value, error + 1, width);
+        self.rounded(lower, upper)
+    }
+}
+
+impl Engine for RationalEngine {
+    type Value = Bounds;
+
+    fn number(&self, number: &BigRational) -> Self::Value {
+        self.rounded(number.clone(), number.clone())
+    }
+
+    fn interval(&self, lower: &BigRational, upper: &BigRational) -> Self::Value {
+        self.rounded(lower.clone(), upper.clone())
+    }
+
+    fn bounds(&self, value: &Self::Value) -> Result<(BigRational, BigRational), EvalError> {
+        Ok(value.clone())
+    }
+
+    fn pi(&self) -> Self::Value {
+        self.pi_bounds().clone()
+    }
+
+    fn add(&self, lhs: &Self::Value, rhs: &Self::Value) -> Self::Value {
+        self.rounded(&lhs.0 + &rhs.0, &lhs.1 + &rhs.1)
+    }
+
+    fn neg(&self, value: &Self::Value) -> Self::Value {
+        (-&value.1, -&value.0)
+    }
+
+    fn mul(&self, lhs: &Self::Value, rhs: &Self::Value) -> Self::Value {
+        let products = [
+            &lhs.0 * &rhs.0,
+            &lhs.0 * &rhs.1,
+            &lhs.1 * &rhs.0,
+            &lhs.1 * &rhs.1,
+        ];
+        let lower = products.iter().min().unwrap().clone();
+        let upper = products.iter().max().unwrap().clone();
+
+        self.rounded(lower, upper)
+    }
+
+    fn recip(&self, value: &Self::Value) -> Self::Value {
+        self.rounded(value.1.recip(), value.0.recip())
+    }
+
+    fn sqrt(&self, value: &Self::Value) -> Self::Value {
+        (
+            self.sqrt_bound(&value.0, false),
+            self.sqrt_bound(&value.1, true),
+        )
+    }
+
+    fn exp(&self, value: &Self::Value) -> Self::Value {
+        (self.exp_point(&value.0).0, self.exp_point(&value.1).1)
+    }
+
+    fn ln(&self, value: &Self::Value) -> Self::Value {
+        (self.ln_point(&value.0).0, self.ln_point(&value.1).1)
+    }
+
+    fn sin(&self, value: &Self::Value) -> Self::Value {
+        self.periodic(value, false)
+    }
+
+    fn cos(&self, value: &Self::Value) -> Self::Value {
+        self.periodic(value, true)
+    }
+
+    fn atan(&self, value: &Self::Value) -> Self::Value {
+        (self.atan_point(&value.0).0, self.atan_point(&value.1).1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::parse;
+
+    fn approximate(input: &str, precision: Precision) -> Result<Interval, EvalError> {
+        let bindings = Bindings::from([(Identifier::new("x"), BigRational::one())]);
+
+        parse(input).approximate(&bindings, precision)
+    }
+
+    /// Parses a decimal like `-0.75`.
+    fn decimal(input: &str) -> BigRational {
+        let (integer, fraction) = input.split_once('.').unwrap_or((input, ""));
+        let digits: BigInt = format!("{integer}{fraction}").parse().unwrap();
+
+        BigRational::new(digits, num::pow(BigInt::from(10), fraction.len()))
+    }
+
+    // computed with mpmath at 50 digits
+    const CASES: [(&str, &str); 16] = [
+        (
+            "sin(x/2*(3+5))",
+            "-0.75680249530792825137263909451182909413591288733647",
+        ),
+        ("pi", "3.1415926535897932384626433832795028841971693993751"),
+        ("e", "2.7182818284590452353602874713526624977572470937"),
+        (
+            "ln(10)",
+            "2.3025850929940456840179914546843642076011014886288",
+        ),
+        (
+            "arcsin(1/3)",
+            "0.33983690945412193709639251339176406638824469033246",
+        ),
+        (
+            "arccos(-1/2)",
+            "2.0943951023931954923084289221863352561314462662501",
+        ),
+        (
+            "arccot(2)",
+            "0.46364760900080611621425623146121440202853705428612",
+        ),
+        (
+            "arctan(x)",
+            "0.78539816339744830961566084581987572104929234984378",
+        ),
+        (
+            "tan(1)",
+            "1.5574077246549022305069748074583601730872507723815",
+        ),
+        (
+            "cot(1/2)",
+            "1.8304877217124519192680194389688166237581079480161",
+        ),
+        (
+            "exp(100)",
+            "26881171418161354484126255515800135873611118.773742",
+        ),
+        (
+            "log(2, 10)",
+            "0.30102999566398119521373889472449302676818988146211",
+        ),
+        (
+            "2^(1/3)",
+            "1.2599210498948731647672106072782283505702514647015",
+        ),
+        (
+            "sqrt(2)",
+            "1.4142135623730950488016887242096980785696718753769",
+        ),
+        (
+            "sin(10^20)",
+            "-0.6452512852657808442058117113125230074069041966869",
+        ),
+        ("(-8)^(1/3) + 7 % 3 + 3!", "5"),
+    ];
+
+    fn check(precision: Precision, digits: i32) {
+        for (input, expected) in CASES {
+            let interval = approximate(input, precision).unwrap();
+            let expected = decimal(expected);
+
+            let scale = expected.abs().max(BigRational::one());
+            let tolerance =
+                BigRational::new(BigInt::one(), num::pow(BigInt::from(10), digits as usize))
+                    * &scale;
+            // the expected values are rounded to 50 digits
+            let rounding = BigRational::new(BigInt::one(), num::pow(BigInt::from(10), 45)) * scale;
+
+            assert!(
+                interval.lower <= &expected + &rounding && &expected - &rounding <= interval.upper,
+                "{input}: {} is outside of [{}, {}]",
+                expected.to_f64().unwrap(),
+                interval.lower.to_f64().unwrap(),
+                interval.upper.to_f64().unwrap()
+            );
+            assert!(interval.radius() <= tolerance, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_digits() {
+        check(Precision::Digits(40), 40);
+    }
+
+    #[test]
+    fn test_f64() {
+        check(Precision::F64, 12);
+        assert!(
+            (approximate("sin(x/2*(3+5))", Precision::F64)
+                .unwrap()
+                .to_f64()
+                - 4f64.sin())
+            .abs()
+                < 1e-15
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let cases = [
+            ("ln(-1)", EvalError::OutOfDomain(Identifier::new("ln"))),
+            (
+                "arcsin(2)",
+                EvalError::OutOfDomain(Identifier::new("arcsin")),
+            ),
+            ("sqrt(-x)", EvalError::OutOfDomain(Identifier::new("sqrt"))),
+            ("1/(x - 1)", EvalError::DivisionByZero),
+            ("(-2)^(1/2)", EvalError::NotReal),
+            ("tan(pi/2)", EvalError::Imprecise),
+            ("sqrt(sin(pi))", EvalError::Imprecise),
+            (
+                "exp(10^6)",
+                EvalError::TooLarge(BigRational::from_integer(1_000_000.into())),
+            ),
+            (
+                "sin(x/2*(3+f(5)))",
+                EvalError::UnsupportedFunction(Identifier::new("f")),
+            ),
+            ("y + 1", EvalError::UnboundVariable(Identifier::new("y"))),
+        ];
+
+        for precision in [Precision::F64, Precision::Digits(10)] {
+            for (input, expected) in &cases {
+                assert_eq!(
+                    approximate(input, precision).as_ref(),
+                    Err(expected),
+                    "{input}"
+                );
+            }
+        }
+
+        // reducing `10^2000` by multiples of `pi` needs more than `MAX_BITS` bits
+        assert_eq!(
+            approximate("sin(10^2000)", Precision::Digits(10)),
+            Err(EvalError::Imprecise)
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use crate::ast::{
+    polynomial::PolynomialError, rational_function::RationalFunction, Element, NodeOrExpression,
+};
+
+impl Element {
+    /// Puts a sum of fractions of polynomials over their least common denominator, like
+    /// `1/x + 1/y = (x + y)/(x*y)`. The numerator and the denominator are expanded.
+    pub fn together(&self) -> Result<Element, PolynomialError> {
+        Ok(RationalFunction::from_element(self)?.to_element())
+    }
+
+    /// Reduces a fraction of polynomials to lowest terms by dividing the numerator and the
+    /// denominator by their greatest common divisor, `(x^2 - 1)/(x - 1) = x + 1`.
+    pub fn cancel(&self) -> Result<Element, PolynomialError> {
+        Ok(RationalFunction::from_element(self)?.cancel().to_element())
+    }
+
+    /// Whether a product of the element divides by something.
+    pub(crate) fn has_denominator(&self) -> bool {
+        match &self.node_or_expression {
+            NodeOrExpression::Expression(expression) => expression
+                .products
+                .iter()
+                .any(|product| !product.denominator.is_empty()),
+            NodeOrExpression::Node(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::parse;
+
+    #[test]
+    fn test_together_and_cancel() {
+        let cases = [
+            ("(x^2 - 1)/(x - 1)", "(x^2 - 1)/(x - 1)", "x + 1"),
+            ("1/x + 1/y", "(x + y)/(x*y)", "(x + y)/(x*y)"),
+            ("x/(x + 1) + 1/(x + 1)", "(x + 1)/(x + 1)", "1"),
+            (
+                "(x*y + y)/(x^2 + 2x + 1)",
+                "(x*y + y)/(x^2 + 2x + 1)",
+                "y/(x + 1)",
+            ),
+        ];
+
+        for (input, together, cancel) in cases {
+            let element = parse(input);
+            assert_eq!(element.together().unwrap().to_string(), together, "{input}");
+            assert_eq!(element.cancel().unwrap().to_string(), cancel, "{input}");
+        }
+
+        assert!(parse("sin(x)/x").cancel().is_err());
+    }
+}
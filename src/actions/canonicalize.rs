@@ -0,0 +1,392 @@
+use std::cmp::Ordering;
+
+use num::{BigRational, One, Zero};
+
+use crate::ast::{product::Product, Element, Expression, Node, NodeOrExpression, Sign};
+
+/// The sorted numerator and denominator factors of a [`Term`].
+pub(crate) type Key = (Vec<Element>, Vec<Element>);
+
+/// A product split into its rational coefficient and the factors that aren't numbers, with the
+/// signs of the factors moved into the coefficient.
+pub(crate) struct Term {
+    pub coefficient: BigRational,
+    pub numerator: Vec<Element>,
+    pub denominator: Vec<Element>,
+}
+
+impl Term {
+    pub fn new(product: &Product) -> Term {
+        let mut term = Term {
+            coefficient: BigRational::one(),
+            numerator: vec![],
+            denominator: vec![],
+        };
+        term.add_product(product, false);
+
+        term
+    }
+
+    /// Multiplies the term with `product`, or divides it if `inverted` is set.
+    fn add_product(&mut self, product: &Product, inverted: bool) {
+        for (side, in_denominator) in [(&product.numerator, false), (&product.denominator, true)] {
+            let in_denominator = in_denominator != inverted;
+
+            for factor in side {
+                if factor.sign == Sign::Negative {
+                    self.coefficient = -self.coefficient.clone();
+                }
+
+                match &factor.node_or_expression {
+                    NodeOrExpression::Node(Node::Number(number)) if !in_denominator => {
+                        self.coefficient *= number;
+                    }
+                    NodeOrExpression::Node(Node::Number(number)) if !number.is_zero() => {
+                        self.coefficient /= number;
+                    }
+                    // negated products like the `-(b*a)` in `a*b - b*a` aren't flattened
+                    NodeOrExpression::Expression(expression) if expression.products.len() == 1 => {
+                        self.add_product(&expression.products[0], in_denominator);
+                    }
+                    _ => {
+                        let mut factor = factor.clone();
+                        factor.sign = Sign::Positive;
+                        if in_denominator {
+                            self.denominator.push(factor);
+                        } else {
+                            self.numerator.push(factor);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// The factors in canonical order, equal for products that only differ in the order of
+    /// their factors like `a*b` and `b*a`.
+    pub fn key(&self) -> Key {
+        let sorted = |side: &Vec<Element>| {
+            let mut side = side.iter().map(Element::without_cache).collect::<Vec<_>>();
+            side.sort_by(Element::canonical_cmp);
+            side
+        };
+
+        (sorted(&self.numerator), sorted(&self.denominator))
+    }
+
+    /// Writes the term as a product, with a coefficient of `-1` as the sign of the first factor.
+    pub fn into_product(self) -> Product {
+        let mut numerator = self.numerator;
+        if let Some(first) = numerator
+            .first_mut()
+            .filter(|_| (-&self.coefficient).is_one())
+        {
+            first.invert_sign();
+        } else if numerator.is_empty() || !self.coefficient.is_one() {
+            numerator.insert(0, Element::number(self.coefficient));
+        }
+
+        Product::new(numerator, self.denominator)
+    }
+
+    /// Writes the term as a product with the coefficient first, unless it's `1`.
+    fn into_canonical_product(self) -> Product {
+        let mut numerator = self.numerator;
+        if numerator.is_empty() || !self.coefficient.is_one() {
+            numerator.insert(0, Element::number(self.coefficient));
+        }
+
+        Product::new(numerator, self.denominator)
+    }
+}
+
+/// Lexicographic comparison of two lists of elements.
+fn cmp_elements(lhs: &[Element], rhs: &[Element]) -> Ordering {
+    lhs.iter()
+        .zip(rhs)
+        .map(|(lhs, rhs)| lhs.canonical_cmp(rhs))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or_else(|| lhs.len().cmp(&rhs.len()))
+}
+
+/// Compares the factors of two products before their coefficients, so that `x` and `2x` end up
+/// next to each other and terms without factors come first.
+fn cmp_products(lhs: &Product, rhs: &Product) -> Ordering {
+    let split = |product: &Product| match product.numerator.split_first() {
+        Some((first, rest))
+            if matches!(
+                first.node_or_expression,
+                NodeOrExpression::Node(Node::Number(_))
+            ) =>
+        {
+            (Some(first.clone()), rest.to_vec())
+        }
+        _ => (None, product.numerator.clone()),
+    };
+    let (lhs_coefficient, lhs_factors) = split(lhs);
+    let (rhs_coefficient, rhs_factors) = split(rhs);
+
+    cmp_elements(&lhs_factors, &rhs_factors)
+        .then_with(|| cmp_elements(&lhs.denominator, &rhs.denominator))
+        .then_with(|| {
+            let one = Element::number(BigRational::one());
+            lhs_coefficient
+                .as_ref()
+                .unwrap_or(&one)
+                .canonical_cmp(rhs_coefficient.as_ref().unwrap_or(&one))
+        })
+}
+
+/// The position of the kind of an element in the canonical order, numbers come first.
+fn rank(element: &Element) -> u8 {
+    match &element.node_or_expression {
+        NodeOrExpression::Node(Node::Number(_)) => 0,
+        NodeOrExpression::Node(Node::Constant(_)) => 1,
+        NodeOrExpression::Node(Node::Variable(_)) => 2,
+        NodeOrExpression::Node(Node::Function { .. }) => 3,
+        NodeOrExpression::Node(Node::Power { .. }) => 4,
+        NodeOrExpression::Node(Node::Modulo { .. }) => 5,
+        NodeOrExpression::Node(Node::Factorial { .. }) => 6,
+        NodeOrExpression::Expression(_) => 7,
+    }
+}
+
+impl Element {
+    /// The base and the exponent of a power, other elements are their own base.
+    fn base_and_exponent(&self) -> (&Element, Option<&Element>) {
+        match &self.node_or_expression {
+            NodeOrExpression::Node(Node::Power { base, power }) => (base, Some(power)),
+            _ => (self, None),
+        }
+    }
+
+    /// A total order of elements that follows their meaning instead of their layout: numbers
+    /// by value, then constants, variables by name and functions, where powers are ordered
+    /// next to their base, `x < x^2 < y`.
+    pub fn canonical_cmp(&self, other: &Element) -> Ordering {
+        let (lhs_base, lhs_exponent) = self.base_and_exponent();
+        let (rhs_base, rhs_exponent) = other.base_and_exponent();
+
+        let ordering = match (lhs_exponent, rhs_exponent) {
+            (None, None) => rank(self).cmp(&rank(other)).then_with(|| {
+                match (&self.node_or_expression, &other.node_or_expression) {
+                    (NodeOrExpression::Node(lhs), NodeOrExpression::Node(rhs)) => {
+                        cmp_nodes(lhs, rhs)
+                    }
+                    (NodeOrExpression::Expression(lhs), NodeOrExpression::Expression(rhs)) => lhs
+                        .products
+                        .iter()
+                        .zip(&rhs.products)
+                        .map(|(lhs, rhs)| cmp_products(lhs, rhs))
+                        .find(|ordering| ordering.is_ne())
+                        .unwrap_or_else(|| lhs.products.len().cmp(&rhs.products.len())),
+                    _ => Ordering::Equal,
+                }
+            }),
+            _ => {
+                let one = Element::number(BigRational::one());
+                lhs_base.canonical_cmp(rhs_base).then_with(|| {
+                    lhs_exponent
+                        .unwrap_or(&one)
+                        .canonical_cmp(rhs_exponent.unwrap_or(&one))
+                })
+            }
+        };
+
+        ordering.then(self.sign.cmp(&other.sign))
+    }
+
+    /// A unique normal form of the element, so that elements which only differ in the order of
+    /// their terms and factors, or in where their signs are written, become equal:
+    ///
+    /// - factors and terms are sorted by [`Element::canonical_cmp`]
+    /// - numbers and signs are collected into one coefficient at the start of a product, which
+    ///   is left out if it's `1`
+    /// - sums nested in sums are merged, like terms are added up and terms with a zero
+    ///   coefficient are dropped
+    /// - the caches are removed
+    pub fn canonicalize(&self) -> Element {
+        self.without_cache()
+            .apply_to_every_element_into(&mut canonicalize_element, false, None)
+    }
+
+    /// The [`Element::structural_hash`] of the canonical form, equal for elements like `a*b + c`
+    /// and `c + b*a`.
+    pub fn canonical_hash(&self) -> u64 {
+        self.canonicalize().structural_hash()
+    }
+}
+
+fn cmp_nodes(lhs: &Node, rhs: &Node) -> Ordering {
+    match (lhs, rhs) {
+        (Node::Number(lhs), Node::Number(rhs)) => lhs.cmp(rhs),
+        (Node::Constant(lhs), Node::Constant(rhs)) => lhs.cmp(rhs),
+        (Node::Variable(lhs), Node::Variable(rhs)) => lhs.cmp(rhs),
+        (
+            Node::Function {
+                name: lhs_name,
+                arguments: lhs_arguments,
+            },
+            Node::Function {
+                name: rhs_name,
+                arguments: rhs_arguments,
+            },
+        ) => lhs_name
+            .cmp(rhs_name)
+            .then_with(|| cmp_elements(lhs_arguments, rhs_arguments)),
+        (
+            Node::Power {
+                base: lhs_base,
+                power: lhs_power,
+            },
+            Node::Power {
+                base: rhs_base,
+                power: rhs_power,
+            },
+        )
+        | (
+            Node::Modulo {
+                lhs: lhs_base,
+                rhs: lhs_power,
+            },
+            Node::Modulo {
+                lhs: rhs_base,
+                rhs: rhs_power,
+            },
+        ) => lhs_base
+            .canonical_cmp(rhs_base)
+            .then_with(|| lhs_power.canonical_cmp(rhs_power)),
+        (Node::Factorial { child: lhs }, Node::Factorial { child: rhs }) => lhs.canonical_cmp(rhs),
+        _ => Ordering::Equal,
+    }
+}
+
+/// Canonicalizes `element` itself, its children are canonical already.
+fn canonicalize_element(element: Element) -> Element {
+    let expression = match element.node_or_expression {
+        NodeOrExpression::Node(Node::Number(value)) => {
+            return Element::number(match element.sign {
+                Sign::Positive => value,
+                Sign::Negative => -value,
+            });
+        }
+        NodeOrExpression::Node(node) if element.sign == Sign::Positive => {
+            return Element::new(Sign::Positive, NodeOrExpression::Node(node));
+        }
+        // `-x` is written as `-1*x`
+        NodeOrExpression::Node(node) => Expression::new(vec![Product::new(
+            vec![Element::new(Sign::Positive, NodeOrExpression::Node(node))],
+            vec![],
+        )]),
+        NodeOrExpression::Expression(expression) => expression,
+    };
+
+    let mut terms = vec![];
+    for product in &expression.products {
+        let mut term = Term::new(product);
+        if element.sign == Sign::Negative {
+            term.coefficient = -term.coefficient;
+        }
+
+        // a sum as the only factor is merged into this sum, `(a + b) + c`
+        let nested = match (term.numerator.as_slice(), term.denominator.is_empty()) {
+            ([factor], true) => match &factor.node_or_expression {
+                NodeOrExpression::Expression(nested) => Some(nested.clone()),
+                NodeOrExpression::Node(_) => None,
+            },
+            _ => None,
+        };
+        match nested {
+            Some(nested) => terms.extend(nested.products.iter().map(|product| {
+                let mut nested_term = Term::new(product);
+                nested_term.coefficient *= &term.coefficient;
+                nested_term
+            })),
+            None => terms.push(term),
+        }
+    }
+
+    // like terms are added up, `x + x` is `2x`
+    let mut groups: Vec<(Key, Term)> = vec![];
+    for term in terms {
+        let key = term.key();
+        match groups.iter_mut().find(|(group_key, _)| *group_key == key) {
+            Some((_, sum)) => sum.coefficient += term.coefficient,
+            None => groups.push((key, term)),
+        }
+    }
+
+    let mut products = groups
+        .into_iter()
+        .map(|(_, term)| term)
+        .filter(|term| !term.coefficient.is_zero())
+        .map(|mut term| {
+            term.numerator.sort_by(Element::canonical_cmp);
+            term.denominator.sort_by(Element::canonical_cmp);
+            term.into_canonical_product()
+        })
+        .collect::<Vec<_>>();
+    products.sort_by(cmp_products);
+
+    match products.as_mut_slice() {
+        [] => Element::number(BigRational::zero()),
+        [product] if product.numerator.len() == 1 && product.denominator.is_empty() => {
+            product.numerator.remove(0)
+        }
+        _ => Element::new(
+            Sign::Positive,
+            NodeOrExpression::Expression(Expression::new(products)),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::parse;
+
+    fn canonicalize(input: &str) -> Element {
+        parse(input).canonicalize()
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        let cases = [
+            ("b*a", "a*b"),
+            ("y + x + 1", "1 + x + y"),
+            ("x^2 + x + 3x^3", "x + x^2 + 3*x^3"),
+            ("-(x - y)", "-1x + y"),
+            ("-x", "-1x"),
+            ("--x", "x"),
+            ("2*x*3/4", "3/2*x"),
+            ("(a + b) + (c + a)", "2a + b + c"),
+            ("x - x", "0"),
+            ("x*y - 3y*x + 1", "1 + -2x*y"),
+            ("0*x + y", "y"),
+            ("sin(y + x)", "sin(x + y)"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(canonicalize(input).to_string(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_equal_forms() {
+        let cases = [
+            ("a*b - c", "-c + b*a"),
+            ("x/(2y)", "1/2*x/y"),
+            ("-(a*b)", "(-b)*a"),
+            ("(x + 1)^2*y", "y*(1 + x)^2"),
+            ("x + x", "2x"),
+            ("x - x", "0"),
+            ("a + (b - a)", "b"),
+        ];
+
+        for (lhs, rhs) in cases {
+            let lhs = canonicalize(lhs);
+            assert_eq!(lhs, canonicalize(rhs), "{lhs} {rhs}");
+            assert_eq!(lhs.canonicalize(), lhs, "{lhs}");
+        }
+    }
+}
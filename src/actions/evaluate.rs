@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+
+use num::{BigInt, BigRational, One, Signed, ToPrimitive, Zero};
+use thiserror::Error;
+
+use crate::ast::{
+    product::Product, Constant, Element, Expression, Identifier, Node, NodeOrExpression, Sign,
+};
+
+/// Values of the variables used by [`Element::evaluate`].
+pub type Bindings = HashMap<Identifier, BigRational>;
+
+/// Exponents and factorials above this are refused instead of running out of memory.
+const MAX_INTEGER: u32 = 100_000;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum EvalError {
+    #[error("Division by zero")]
+    DivisionByZero,
+    #[error("The factorial of {0} isn't defined, only of non-negative integers")]
+    InvalidFactorial(BigRational),
+    #[error("The result is irrational")]
+    Irrational,
+    #[error("The result isn't a real number")]
+    NotReal,
+    #[error("The variable {0} has no value")]
+    UnboundVariable(Identifier),
+    #[error("The function {0} can't be evaluated")]
+    UnsupportedFunction(Identifier),
+    #[error("The number {0} is too large")]
+    TooLarge(BigRational),
+    #[error("The argument of {0} is outside of its domain")]
+    OutOfDomain(Identifier),
+    #[error("The result can't be bounded precisely enough")]
+    Imprecise,
+}
+
+impl Element {
+    /// Computes the exact value of the element, with variables replaced by their values in
+    /// `bindings`.
+    ///
+    /// Powers are evaluated if their result is rational, like `4^(-1/2)`, the same goes for
+    /// `sqrt`, `cbrt` and `abs`.
+    pub fn evaluate(&self, bindings: &Bindings) -> Result<BigRational, EvalError> {
+        let value = match &self.node_or_expression {
+            NodeOrExpression::Node(node) => node.evaluate(bindings)?,
+            NodeOrExpression::Expression(expression) => expression.evaluate(bindings)?,
+        };
+
+        Ok(match self.sign {
+            Sign::Positive => value,
+            Sign::Negative => -value,
+        })
+    }
+}
+
+impl Expression {
+    pub fn evaluate(&self, bindings: &Bindings) -> Result<BigRational, EvalError> {
+        self.products
+            .iter()
+            .try_fold(BigRational::zero(), |sum, product| {
+                Ok(sum + product.evaluate(bindings)?)
+            })
+    }
+}
+
+impl Product {
+    pub fn evaluate(&self, bindings: &Bindings) -> Result<BigRational, EvalError> {
+        let mut value = BigRational::one();
+
+        for element in &self.numerator {
+            value *= element.evaluate(bindings)?;
+        }
+
+        for element in &self.denominator {
+            let divisor = element.evaluate(bindings)?;
+            if divisor.is_zero() {
+                return Err(EvalError::DivisionByZero);
+            }
+            value /= divisor;
+        }
+
+        Ok(value)
+    }
+}
+
+impl Node {
+    pub fn evaluate(&self, bindings: &Bindings) -> Result<BigRational, EvalError> {
+        match self {
+            Node::Number(number) => Ok(number.clone()),
+            Node::Variable(name) => bindings
+                .get(name)
+                .cloned()
+                .ok_or_else(|| EvalError::UnboundVariable(name.clone())),
+            Node::Constant(Constant::Pi | Constant::E) => Err(EvalError::Irrational),
+            Node::Constant(Constant::I | Constant::Infinity) => Err(EvalError::NotReal),
+            Node::Power { base, power } => {
+                evaluate_power(&base.evaluate(bindings)?, &power.evaluate(bindings)?)
+            }
+            Node::Modulo { lhs, rhs } => {
+                let rhs = rhs.evaluate(bindings)?;
+                if rhs.is_zero() {
+                    return Err(EvalError::DivisionByZero);
+                }
+                Ok(lhs.evaluate(bindings)? % rhs)
+            }
+            Node::Factorial { child } => evaluate_factorial(&child.evaluate(bindings)?),
+            Node::Function { name, arguments } => {
+                let arguments = arguments
+                    .iter()
+                    .map(|argument| argument.evaluate(bindings))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                match (name.as_plain(), arguments.as_slice()) {
+                    (Some("sqrt"), [argument]) => root(argument, 2),
+                    (Some("cbrt"), [argument]) => root(argument, 3),
+                    (Some("abs"), [argument]) => Ok(argument.abs()),
+                    _ => Err(EvalError::UnsupportedFunction(name.clone())),
+                }
+            }
+        }
+    }
+}
+
+/// `base^(p/q)` is the `q`-th root of `base^p`, so it's only rational if that root is exact.
+fn evaluate_power(base: &BigRational, power: &BigRational) -> Result<BigRational, EvalError> {
+    if base.is_zero() {
+        return if power.is_negative() {
+            Err(EvalError::DivisionByZero)
+        } else if power.is_zero() {
+            Ok(BigRational::one())
+        } else {
+            Ok(BigRational::zero())
+        };
+    }
+
+    let exponent = small_integer(&BigRational::from_integer(power.numer().abs()))?;
+    let degree = small_integer(&BigRational::from_integer(power.denom().clone()))?;
+
+    let value = root(&num::pow(base.clone(), exponent as usize), degree)?;
+
+    Ok(if power.is_negative() {
+        value.recip()
+    } else {
+        value
+    })
+}
+
+fn evaluate_factorial(number: &BigRational) -> Result<BigRational, EvalError> {
+    if !number.is_integer() || number.is_negative() {
+        return Err(EvalError::InvalidFactorial(number.clone()));
+    }
+
+    let product =
+        (1..=small_integer(number)?).fold(BigInt::one(), |product, factor| product * factor);
+
+    Ok(BigRational::from_integer(product))
+}
+
+/// The exact `degree`-th root of `number`.
+pub(crate) fn root(number: &BigRational, degree: u32) -> Result<BigRational, EvalError> {
+    if number.is_negative() && degree.is_multiple_of(2) {
+        return Err(EvalError::NotReal);
+    }
+
+    let exact_root = |integer: &BigInt| {
+        let root = integer.nth_root(degree);
+        if num::pow(root.clone(), degree as usize) == *integer {
+            Ok(root)
+        } else {
+            Err(EvalError::Irrational)
+        }
+    };
+
+    Ok(BigRational::new(
+        exact_root(number.numer())?,
+        exact_root(number.denom())?,
+    ))
+}
+
+fn small_integer(number: &BigRational) -> Result<u32, EvalError> {
+    number
+        .to_integer()
+        .to_u32()
+        .filter(|integer| *integer <= MAX_INTEGER)
+        .ok_or_else(|| EvalError::TooLarge(number.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::parse;
+
+    fn evaluate(input: &str, bindings: &Bindings) -> Result<BigRational, EvalError> {
+        parse(input).evaluate(bindings)
+    }
+
+    fn rational(numerator: i64, denominator: i64) -> BigRational {
+        BigRational::new(numerator.into(), denominator.into())
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let cases = [
+            ("1-((-2-3)*(-4-5))/((-6-7)*(-8-9))", rational(176, 221)),
+            ("2^10 - 3*2^-2", rational(4093, 4)),
+            ("(-8)^(1/3) + 4^(3/2)", rational(6, 1)),
+            ("(9/4)^(-1/2)", rational(2, 3)),
+            ("0^0 + 0^3", rational(1, 1)),
+            ("7 % 3 + 5!/3!", rational(21, 1)),
+            ("-(2 + 3)^2", rational(-25, 1)),
+            ("sqrt(16/9) + cbrt(-27) + abs(-1/2)", rational(-7, 6)),
+            ("0!", rational(1, 1)),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(evaluate(input, &Bindings::new()), Ok(expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_bindings() {
+        let bindings = Bindings::from([
+            (Identifier::new("x"), rational(1, 2)),
+            (Identifier::new("v").with_subscript("0"), rational(-3, 1)),
+        ]);
+
+        assert_eq!(evaluate("4x^2 - v_0/x", &bindings), Ok(rational(7, 1)));
+        assert_eq!(
+            evaluate("x + y", &bindings),
+            Err(EvalError::UnboundVariable(Identifier::new("y")))
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let cases = [
+            ("1/(2 - 2)", EvalError::DivisionByZero),
+            ("0^-1", EvalError::DivisionByZero),
+            ("3 % 0", EvalError::DivisionByZero),
+            ("(1/2)!", EvalError::InvalidFactorial(rational(1, 2))),
+            ("(-1)!", EvalError::InvalidFactorial(rational(-1, 1))),
+            ("2^(1/2)", EvalError::Irrational),
+            ("sqrt(2)", EvalError::Irrational),
+            ("(-4)^(1/2)", EvalError::NotReal),
+            ("2pi", EvalError::Irrational),
+            ("e^2", EvalError::Irrational),
+            ("i + 1", EvalError::NotReal),
+            (
+                "sin(0)",
+                EvalError::UnsupportedFunction(Identifier::new("sin")),
+            ),
+            ("2^1000000", EvalError::TooLarge(rational(1000000, 1))),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(evaluate(input, &Bindings::new()), Err(expected), "{input}");
+        }
+    }
+}
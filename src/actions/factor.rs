@@ -0,0 +1,74 @@
+use num::{BigRational, One};
+
+use crate::ast::{
+    polynomial::{Polynomial, PolynomialError},
+    product::Product,
+    Element, Node, NodeOrExpression, Sign,
+};
+
+impl Element {
+    /// Factors a polynomial in one variable into irreducible factors over the rationals, like
+    /// `2x^3 - 2x = 2x(x - 1)(x + 1)`. Repeated factors are written as powers.
+    pub fn factor(&self) -> Result<Product, PolynomialError> {
+        let factorization = Polynomial::from_element(self)?.factor()?;
+        let mut numerator = factorization
+            .factors
+            .iter()
+            .map(|(factor, multiplicity)| match multiplicity {
+                1 => factor.to_element(),
+                _ => Element::new(
+                    Sign::Positive,
+                    NodeOrExpression::Node(Node::Power {
+                        base: Box::new(factor.to_element()),
+                        power: Box::new(Element::number(BigRational::from_integer(
+                            (*multiplicity).into(),
+                        ))),
+                    }),
+                ),
+            })
+            .collect::<Vec<_>>();
+
+        let content = factorization.content;
+        match numerator.first_mut() {
+            Some(first) if (-&content).is_one() => first.invert_sign(),
+            Some(_) if content.is_one() => {}
+            _ => numerator.insert(0, Element::number(content)),
+        }
+
+        Ok(Product::new(numerator, vec![]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::parse;
+
+    #[test]
+    fn test_factor() {
+        let cases = [
+            ("x^2 - 1", "(x - 1)(x + 1)"),
+            ("2x^3 - 2x", "2x*(x - 1)(x + 1)"),
+            ("-x^2 + 4", "-(x - 2)(x + 2)"),
+            ("6x^2 + 5x + 1", "(2x + 1)*(3x + 1)"),
+            ("1/2x^2 - 1/2", "1/2*(x - 1)(x + 1)"),
+            ("x^3 - 3x^2 + 3x - 1", "(x - 1)^3"),
+            ("x^4 + 1", "x^4 + 1"),
+            ("7", "7"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(
+                parse(input).factor().unwrap().to_string(),
+                expected,
+                "{input}"
+            );
+        }
+
+        assert!(matches!(
+            parse("x^2 - y^2").factor(),
+            Err(PolynomialError::NotUnivariate(_))
+        ));
+        assert!(parse("sin(x)").factor().is_err());
+    }
+}
@@ -3,7 +3,9 @@ use std::{
     fmt::{Debug, Display},
 };
 
-use crate::ast::{product::Product, Element, Equation, Expression, Node, NodeOrExpression};
+use crate::ast::{
+    product::Product, Element, Equation, Expression, Identifier, Node, NodeOrExpression,
+};
 
 pub trait IsSame {
     fn is_same(lhs: &Self, rhs: &Self, names: &mut IsSameNames) -> bool;
@@ -11,7 +13,7 @@ pub trait IsSame {
 
 #[derive(Debug)]
 pub struct IsSameNames {
-    pub variables: HashMap<String, Vec<String>>,
+    pub variables: HashMap<Identifier, Vec<Identifier>>,
 }
 
 impl IsSameNames {
@@ -24,7 +26,7 @@ impl IsSameNames {
     pub fn check(&self) -> bool {
         let mut result = true;
 
-        let mut name_set: HashSet<String> = HashSet::new();
+        let mut name_set: HashSet<Identifier> = HashSet::new();
 
         for name_options in self.variables.values() {
             if name_options.len() == 1 {
@@ -152,6 +154,8 @@ impl IsSame for Node {
                 if let Node::Variable(right_name) = rhs {
                     // info!("Variable l: {}, r: {}", left_name, right_name);
                     match names.variables.get_mut(left_name) {
+                        // a variable which appears twice maps to the same name twice
+                        Some(name) if name.contains(right_name) => {}
                         Some(name) => {
                             name.push(right_name.clone());
                         }
@@ -165,6 +169,9 @@ impl IsSame for Node {
 
                 true
             }
+            Node::Constant(left_constant) => {
+                matches!(rhs, Node::Constant(right_constant) if left_constant == right_constant)
+            }
             Node::Power {
                 base: left_base,
                 power: left_power,
@@ -233,3 +240,33 @@ impl IsSame for Product {
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::parse;
+
+    #[test]
+    fn test_repeated_variables() {
+        let mut names = IsSameNames::new();
+        assert!(Element::is_same(
+            &parse("sin(x) + x"),
+            &parse("sin(y) + y"),
+            &mut names
+        ));
+        // `x` stands for `y` once, however often it appears
+        assert_eq!(
+            names.variables[&Identifier::new("x")],
+            [Identifier::new("y")]
+        );
+        assert!(names.check());
+
+        let mut names = IsSameNames::new();
+        assert!(Element::is_same(
+            &parse("sin(x) + x"),
+            &parse("sin(y) + z"),
+            &mut names
+        ));
+        assert!(!names.check());
+    }
+}
@@ -1,6 +1,12 @@
 pub mod analyze;
 pub mod apply_to_every_element;
+pub mod approximate;
 pub mod bind;
+pub mod cancel;
+pub mod canonicalize;
+pub mod evaluate;
 pub mod expand;
+pub mod factor;
 pub mod is_same;
+pub mod roots;
 pub mod solve;
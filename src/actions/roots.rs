@@ -0,0 +1,459 @@
+use num::{BigInt, BigRational, One, Signed, ToPrimitive, Zero};
+use thiserror::Error;
+
+use crate::{
+    ast::{
+        complex::{gaussian_sqrt, GaussianRational},
+        polynomial::{Monomial, Polynomial, PolynomialError},
+        product::Product,
+        Element, Equation, Expression, Identifier, Node, NodeOrExpression, Sign,
+    },
+    strategies::fold_powers::split_radicand,
+};
+
+use super::{approximate::Interval, evaluate::Bindings};
+
+/// Polynomials of a higher degree aren't expanded.
+const MAX_DEGREE: usize = 64;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum RootsError {
+    #[error("The equation isn't a polynomial in {0} with Gaussian rational coefficients")]
+    NotPolynomial(Identifier),
+    #[error("Polynomial equations of degree {0} can't be solved yet")]
+    UnsupportedDegree(usize),
+    #[error("The equation holds for every value")]
+    Identity,
+    #[error("The equation has no solution")]
+    NoSolution,
+    #[error("{0}")]
+    Polynomial(PolynomialError),
+}
+
+/// Coefficients of a polynomial in one variable, starting with the constant one.
+type Coefficients = Vec<GaussianRational>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Root {
+    Exact(Element),
+    /// A real root which can't be written exactly, the interval contains no other root.
+    Approximate(Interval),
+}
+
+/// The roots found by [`Element::roots`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Roots {
+    pub found: Vec<Root>,
+    /// The number of non-real roots which aren't found, of factors of degree three or more.
+    pub missing_non_real: usize,
+}
+
+impl Equation {
+    /// Solves `lhs = rhs` for `variable` if it's a polynomial equation, see [`Element::roots`].
+    pub fn roots(&self, variable: &Identifier, digits: u32) -> Result<Roots, RootsError> {
+        match self.equation_sides.as_slice() {
+            [lhs, rhs] => Element::simple_sub(lhs.clone(), rhs.clone()).roots(variable, digits),
+            _ => Err(RootsError::NotPolynomial(variable.clone())),
+        }
+    }
+}
+
+impl Element {
+    /// Solves `self = 0` for `variable` if it's a polynomial with coefficients made of numbers
+    /// and `i`. The roots can be complex, `x^2 + 1` has the roots `i` and `-i`.
+    ///
+    /// Roots are written as Gaussian rationals if they are exact, otherwise they contain the
+    /// square root of the discriminant, like `-1/2 + 1/2*sqrt(5)`. Polynomials of a higher degree
+    /// need rational coefficients, they are factored and the linear and quadratic factors are
+    /// solved exactly. Only the real roots of the other factors are found, as intervals at most
+    /// `10^-digits` wide, their non-real roots are counted in [`Roots::missing_non_real`].
+    pub fn roots(&self, variable: &Identifier, digits: u32) -> Result<Roots, RootsError> {
+        let coefficients = polynomial(self, variable)?;
+
+        match coefficients.as_slice() {
+            [] => Err(RootsError::Identity),
+            [_] => Err(RootsError::NoSolution),
+            _ => match exact_roots(&coefficients) {
+                Some(roots) => Ok(Roots {
+                    found: roots.into_iter().map(Root::Exact).collect(),
+                    missing_non_real: 0,
+                }),
+                None => factored_roots(&coefficients, variable, digits),
+            },
+        }
+    }
+
+    /// The degree of the element as a polynomial in `variable`, if it is one.
+    pub(crate) fn degree_in(&self, variable: &Identifier) -> Option<usize> {
+        polynomial(self, variable)
+            .ok()
+            .map(|coefficients| coefficients.len().saturating_sub(1))
+    }
+}
+
+/// The roots of polynomials of degree one or two.
+fn exact_roots(coefficients: &Coefficients) -> Option<Vec<Element>> {
+    match coefficients.as_slice() {
+        [constant, linear] => Some(vec![Element::from_gaussian(
+            &(-constant.clone() / linear.clone()),
+        )]),
+        [constant, linear, quadratic] => Some(quadratic_roots(constant, linear, quadratic)),
+        _ => None,
+    }
+}
+
+/// Solves the factors of a polynomial with rational coefficients one by one, a root of a
+/// repeated factor is only returned once.
+fn factored_roots(
+    coefficients: &Coefficients,
+    variable: &Identifier,
+    digits: u32,
+) -> Result<Roots, RootsError> {
+    let degree = coefficients.len() - 1;
+    let polynomial = coefficients
+        .iter()
+        .enumerate()
+        .try_fold(Polynomial::zero(), |sum, (exponent, coefficient)| {
+            let term = Polynomial::term(
+                Monomial::from([(variable.clone(), exponent as u32)]),
+                coefficient.re.clone(),
+            );
+            coefficient.im.is_zero().then(|| &sum + &term)
+        })
+        .ok_or(RootsError::UnsupportedDegree(degree))?;
+
+    let factorization = polynomial.factor().map_err(RootsError::Polynomial)?;
+    let tolerance = BigRational::new(BigInt::one(), num::pow(BigInt::from(10), digits as usize));
+
+    let mut roots = Roots::default();
+    for (factor, _) in &factorization.factors {
+        let coefficients = self::polynomial(&factor.to_element(), variable)?;
+
+        match exact_roots(&coefficients) {
+            Some(exact) => roots.found.extend(exact.into_iter().map(Root::Exact)),
+            None => {
+                let real = factor
+                    .real_roots(&tolerance)
+                    .map_err(RootsError::Polynomial)?;
+                roots.missing_non_real += coefficients.len() - 1 - real.len();
+                roots.found.extend(real.into_iter().map(Root::Approximate));
+            }
+        }
+    }
+
+    Ok(roots)
+}
+
+/// `(-b ± sqrt(b² - 4ac)) / 2a`
+fn quadratic_roots(
+    c: &GaussianRational,
+    b: &GaussianRational,
+    a: &GaussianRational,
+) -> Vec<Element> {
+    let two_a = a * GaussianRational::from(BigRational::from_integer(2.into()));
+    let four = GaussianRational::from(BigRational::from_integer(4.into()));
+    let discriminant = b * b - four * a * c;
+    let center = -b.clone() / two_a.clone();
+
+    if discriminant.is_zero() {
+        return vec![Element::from_gaussian(&center)];
+    }
+
+    if let Some(root) = gaussian_sqrt(&discriminant) {
+        let offset = root / two_a;
+        return vec![
+            Element::from_gaussian(&(center.clone() + offset.clone())),
+            Element::from_gaussian(&(center - offset)),
+        ];
+    }
+
+    let sqrt = |argument: Element| {
+        Element::new(
+            Sign::Positive,
+            NodeOrExpression::Node(Node::Function {
+                name: Identifier::new("sqrt"),
+                arguments: vec![argument],
+            }),
+        )
+    };
+    // the square roots of negative numbers are written with `i`
+    let negative = discriminant.im.is_zero() && discriminant.re.is_negative();
+    let radicand = if negative {
+        -discriminant
+    } else {
+        discriminant
+    };
+    // perfect squares are pulled out of the square root, `sqrt(8) = 2*sqrt(2)`
+    let (outside, radicand) = if radicand.im.is_zero() {
+        let (outside, inside) = split_radicand(&radicand.re, 2);
+        (
+            GaussianRational::from(outside),
+            GaussianRational::from(inside),
+        )
+    } else {
+        (GaussianRational::one(), radicand)
+    };
+
+    let offset = |factor: GaussianRational| {
+        let mut factors = vec![];
+        let mut root = sqrt(Element::from_gaussian(&radicand));
+        // a factor of `-1` is written as the sign of the root
+        if (-factor.clone()).is_one() {
+            root.sign = Sign::Negative;
+        } else if !factor.is_one() {
+            factors.push(Element::from_gaussian(&factor));
+        }
+        factors.push(root);
+        if negative {
+            factors.push(Element::from_gaussian(&GaussianRational::i()));
+        }
+
+        Element::new(
+            Sign::Positive,
+            NodeOrExpression::Expression(Expression::new(vec![Product::new(factors, vec![])])),
+        )
+    };
+    let factor = outside / two_a;
+
+    if center.is_zero() {
+        vec![offset(factor.clone()), offset(-factor)]
+    } else {
+        vec![
+            Element::simple_add(Element::from_gaussian(&center), offset(factor.clone())),
+            Element::simple_sub(Element::from_gaussian(&center), offset(factor)),
+        ]
+    }
+}
+
+/// The coefficients of `element` as a polynomial in `variable`, without trailing zeros. The
+/// degree is checked after every multiplication, so nested powers can't expand past
+/// [`MAX_DEGREE`].
+fn polynomial(element: &Element, variable: &Identifier) -> Result<Coefficients, RootsError> {
+    let coefficients = match &element.node_or_expression {
+        NodeOrExpression::Node(node) => node_polynomial(node, variable)?,
+        NodeOrExpression::Expression(expression) => expression_polynomial(expression, variable)?,
+    };
+
+    Ok(match element.sign {
+        Sign::Positive => coefficients,
+        Sign::Negative => coefficients.into_iter().map(|value| -value).collect(),
+    })
+}
+
+fn node_polynomial(node: &Node, variable: &Identifier) -> Result<Coefficients, RootsError> {
+    let not_polynomial = || RootsError::NotPolynomial(variable.clone());
+
+    match node {
+        Node::Variable(name) if name == variable => {
+            Ok(vec![GaussianRational::zero(), GaussianRational::one()])
+        }
+        Node::Power { base, power } if node.as_gaussian().is_none() => {
+            let base = polynomial(base, variable)?;
+            let degree = power
+                .evaluate(&Bindings::new())
+                .ok()
+                .filter(|degree| degree.is_integer())
+                .and_then(|degree| degree.to_integer().to_usize())
+                .ok_or_else(not_polynomial)?;
+            if degree > MAX_DEGREE {
+                return Err(RootsError::UnsupportedDegree(degree));
+            }
+
+            (0..degree).try_fold(vec![GaussianRational::one()], |power, _| {
+                multiply(&power, &base)
+            })
+        }
+        _ => Ok(trim(vec![node.as_gaussian().ok_or_else(not_polynomial)?])),
+    }
+}
+
+fn expression_polynomial(
+    expression: &Expression,
+    variable: &Identifier,
+) -> Result<Coefficients, RootsError> {
+    let mut sum = vec![];
+
+    for product in &expression.products {
+        let product = product_polynomial(product, variable)?;
+        if product.len() > sum.len() {
+            sum.resize(product.len(), GaussianRational::zero());
+        }
+        for (total, coefficient) in sum.iter_mut().zip(product) {
+            *total += coefficient;
+        }
+    }
+
+    Ok(trim(sum))
+}
+
+/// Only numbers can be divided by, the result would be a rational function otherwise.
+fn product_polynomial(
+    product: &Product,
+    variable: &Identifier,
+) -> Result<Coefficients, RootsError> {
+    let mut result = vec![GaussianRational::one()];
+
+    for element in &product.numerator {
+        result = multiply(&result, &polynomial(element, variable)?)?;
+    }
+
+    for element in &product.denominator {
+        let divisor = element
+            .as_gaussian()
+            .filter(|divisor| !divisor.is_zero())
+            .ok_or_else(|| RootsError::NotPolynomial(variable.clone()))?;
+        result = result
+            .into_iter()
+            .map(|coefficient| coefficient / divisor.clone())
+            .collect();
+    }
+
+    Ok(result)
+}
+
+/// The product of two polynomials, if its degree is at most [`MAX_DEGREE`].
+fn multiply(lhs: &Coefficients, rhs: &Coefficients) -> Result<Coefficients, RootsError> {
+    if lhs.is_empty() || rhs.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let degree = lhs.len() + rhs.len() - 2;
+    if degree > MAX_DEGREE {
+        return Err(RootsError::UnsupportedDegree(degree));
+    }
+
+    let mut result = vec![GaussianRational::zero(); degree + 1];
+    for (i, left) in lhs.iter().enumerate() {
+        for (j, right) in rhs.iter().enumerate() {
+            result[i + j] += left * right;
+        }
+    }
+
+    Ok(trim(result))
+}
+
+fn trim(mut coefficients: Coefficients) -> Coefficients {
+    while coefficients.last().is_some_and(Zero::is_zero) {
+        coefficients.pop();
+    }
+
+    coefficients
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::equation::NoContextEquation;
+
+    fn roots(input: &str) -> Result<Vec<String>, RootsError> {
+        let equation = NoContextEquation::try_from(input).unwrap();
+        let [lhs, rhs] = [&equation.sides[0].element, &equation.sides[1].element];
+
+        let roots =
+            Element::simple_sub(lhs.clone(), rhs.clone()).roots(&Identifier::new("x"), 20)?;
+        assert_eq!(roots.missing_non_real, 0, "{input}");
+        Ok(roots
+            .found
+            .iter()
+            .map(|root| match root {
+                Root::Exact(root) => root.to_string(),
+                Root::Approximate(interval) => panic!("{input} {interval:?}"),
+            })
+            .collect())
+    }
+
+    #[test]
+    fn test_roots() {
+        let cases = [
+            ("x^2 + 1 = 0", vec!["i", "-i"]),
+            ("2x + 3 = 0", vec!["-3/2"]),
+            ("x^2 = 4", vec!["2", "-2"]),
+            ("(x - 1)^2 = 0", vec!["1"]),
+            ("x^2 + 2x + 5 = 0", vec!["-1 + 2i", "-1 - 2i"]),
+            ("x^2 = 2i", vec!["1 + i", "-1 - i"]),
+            ("i*x = 1", vec!["-i"]),
+            (
+                "x^2 + x - 1 = 0",
+                vec!["-1/2 + 1/2*sqrt(5)", "-1/2 - 1/2*sqrt(5)"],
+            ),
+            ("x^2 + 2 = 0", vec!["sqrt(2)*i", "-sqrt(2)*i"]),
+            (
+                "x^3 = 1",
+                vec!["1", "-1/2 + 1/2*sqrt(3)*i", "-1/2 - 1/2*sqrt(3)*i"],
+            ),
+            (
+                "x^4 = 4",
+                vec!["sqrt(2)", "-sqrt(2)", "sqrt(2)*i", "-sqrt(2)*i"],
+            ),
+            ("x^2 = 12", vec!["2*sqrt(3)", "-2*sqrt(3)"]),
+            ("x^2 - 2x - 7 = 0", vec!["1 + 2*sqrt(2)", "1 - 2*sqrt(2)"]),
+            ("2x^2 = 3", vec!["1/2*sqrt(6)", "-1/2*sqrt(6)"]),
+            ("x^3 - 2x^2 - x + 2 = 0", vec!["2", "1", "-1"]),
+            (
+                "(x - 1)^2*(x^2 + x + 1) = 0",
+                vec!["1", "-1/2 + 1/2*sqrt(3)*i", "-1/2 - 1/2*sqrt(3)*i"],
+            ),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(
+                roots(input),
+                Ok(expected.iter().map(|root| root.to_string()).collect()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_approximate_roots() {
+        let equation = NoContextEquation::try_from("x^3 - x - 1 = 0").unwrap();
+        let element = &equation.sides[0].element;
+
+        let roots = element.roots(&Identifier::new("x"), 12).unwrap();
+        let [Root::Approximate(interval)] = roots.found.as_slice() else {
+            panic!("{roots:?}");
+        };
+        assert_eq!(roots.missing_non_real, 2);
+        assert!(
+            &interval.upper - &interval.lower <= BigRational::new(1.into(), 10u64.pow(12).into())
+        );
+
+        let residual = |value: &BigRational| value * value * value - value - BigRational::one();
+        assert!(!residual(&interval.lower).is_positive());
+        assert!(!residual(&interval.upper).is_negative());
+        assert_eq!((interval.to_f64() * 1e6).round(), 1_324_718.0);
+
+        // the quadratic factor is solved exactly, `x^3 - 2` has non-real roots
+        let equation = NoContextEquation::try_from("(x^3 - 2)*(x^2 + 1) = 0").unwrap();
+        let roots = equation.sides[0]
+            .element
+            .roots(&Identifier::new("x"), 6)
+            .unwrap();
+        assert_eq!(roots.found.len(), 3);
+        assert!(matches!(roots.found[0], Root::Exact(_)));
+        assert!(matches!(roots.found[2], Root::Approximate(_)));
+        assert_eq!(roots.missing_non_real, 2);
+    }
+
+    #[test]
+    fn test_errors() {
+        let x = Identifier::new("x");
+        let cases = [
+            ("x = x", RootsError::Identity),
+            ("x + 1 = x", RootsError::NoSolution),
+            ("x^3 = i", RootsError::UnsupportedDegree(3)),
+            ("x^65 = 2", RootsError::UnsupportedDegree(65)),
+            ("(x^64)^64 = 2", RootsError::UnsupportedDegree(128)),
+            ("((x^64)^64)^64 = 2", RootsError::UnsupportedDegree(128)),
+            ("x^40*(x + 1)^40 = 2", RootsError::UnsupportedDegree(80)),
+            ("x^(-1) = 1", RootsError::NotPolynomial(x.clone())),
+            ("x*y = 1", RootsError::NotPolynomial(x.clone())),
+            ("1/x = 1", RootsError::NotPolynomial(x.clone())),
+            ("sin(x) = 0", RootsError::NotPolynomial(x)),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(roots(input), Err(expected), "{input}");
+        }
+    }
+}
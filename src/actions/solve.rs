@@ -3,33 +3,44 @@ use tracing::debug;
 use uuid::Uuid;
 
 use crate::{
-    actions::is_same::{IsSame, IsSameNames},
+    actions::{
+        is_same::{IsSame, IsSameNames},
+        roots::{Root, RootsError},
+    },
     graph::graph::EquationGraph,
     output::equation_to_rpn::ReversePolishNotation,
 };
 
-use crate::ast::{app::App, Equation};
+use crate::ast::{
+    app::App, arena::ElementArena, Element, Equation, Identifier, Node, NodeOrExpression, Sign,
+};
 
 const STRATEGIES: [&'static str; 1] = ["apply_inverse"];
+/// `flatten` and `simplify` are applied until the equation stops changing, but at most this
+/// often.
+const MAX_SIMPLIFY_ROUNDS: usize = 32;
 
 impl App {
-    pub fn solve(&mut self, context_uuid: Uuid) {
+    /// Solves every equation of the context, roots which can't be written exactly are
+    /// approximated to `digits` digits.
+    pub fn solve(&mut self, context_uuid: Uuid, digits: u32) {
         // println!("Context {}", self.uuid);
         let mut context = self
             .remove_context(context_uuid)
             .expect("Context not found");
 
         for (_, equation) in &mut context.equations {
-            self.solve_equation(equation);
+            self.solve_equation(equation, digits);
         }
 
         self.contexts.insert(context_uuid, context);
         // println!("Analysis: {:#?}", analysis);
     }
 
-    pub fn solve_equation(&mut self, equation: &mut Equation) {
+    /// Builds the graph of the equations derived from `equation`, the leaves are solutions.
+    pub fn solve_equation(&mut self, equation: &mut Equation, digits: u32) -> EquationGraph {
         let (mut graph, center_index) = EquationGraph::new(equation.clone());
-        self.process_graph_node(center_index, &mut graph);
+        self.process_graph_node(center_index, &mut graph, digits);
 
         /* let dot_format = Dot::with_config(&graph.graph, &[Config::EdgeNoLabel]);
         debug!("{dot_format:?}");
@@ -40,47 +51,72 @@ impl App {
         debug!("{graph_json:?}");
         debug!("{graph_json:#?}");
         debug!("{graph_json}"); */
+
+        graph
     }
 
     pub fn process_graph_node(
         &mut self,
         node_index: NodeIndex,
         graph: &mut EquationGraph,
+        digits: u32,
     ) -> Vec<NodeIndex> {
         let mut original_eq = graph.graph[node_index].clone();
         // debug!("{}", original_eq.rpn());
 
         let mut indices = vec![];
+        let mut leaves = vec![];
 
         for strategy in STRATEGIES {
             for side in &mut original_eq.equation_sides {
                 side.analyze(None);
             }
 
-            let mut previous_eq = original_eq.clone();
-            loop {
+            // the sides are interned, so they are compared by their handles without cloning
+            let mut arena = ElementArena::new();
+            let mut intern = |equation: &Equation| {
+                equation
+                    .equation_sides
+                    .iter()
+                    .map(|side| arena.intern(side))
+                    .collect::<Vec<_>>()
+            };
+            // simplifying may cancel factors, where the equation was undefined before
+            let mut simplify_constraints = vec![];
+            let mut previous_ids = intern(&original_eq);
+            for _ in 0..MAX_SIMPLIFY_ROUNDS {
                 for strategy in ["flatten", "simplify"] {
-                    original_eq.apply_strategy(self, strategy);
+                    let constraints = original_eq.apply_strategy(self, strategy);
+                    simplify_constraints.extend(constraints);
                     // debug!("{}", original_eq.rpn());
                 }
 
-                let mut names = IsSameNames::new();
-                let is_same = IsSame::is_same(&previous_eq, &original_eq, &mut names);
-                if is_same && names.check() {
+                let current_ids = intern(&original_eq);
+                if current_ids == previous_ids {
                     debug!("{}", original_eq.rpn());
                     break;
                 }
 
-                previous_eq = original_eq.clone();
+                previous_ids = current_ids;
             }
 
             for side in &mut original_eq.equation_sides {
                 side.analyze(None);
             }
 
+            if let Some(solutions) = polynomial_solutions(&original_eq, digits) {
+                for (solution, constraints) in solutions {
+                    let constraints = [simplify_constraints.clone(), constraints].concat();
+                    let (index, _) = graph.add_path(solution, constraints, node_index);
+                    leaves.push(index);
+                }
+                continue;
+            }
+
             let mut cloned_eq = original_eq.clone();
 
-            let constraints = cloned_eq.apply_strategy(self, strategy);
+            let mut constraints = simplify_constraints;
+            constraints.extend(cloned_eq.apply_strategy(self, strategy));
             // debug!("{}", cloned_eq.rpn());
 
             let (node_index, _) = graph.add_path(cloned_eq.clone(), constraints, node_index);
@@ -94,9 +130,9 @@ impl App {
             }
         }
 
-        let mut new_indices = vec![];
+        let mut new_indices = leaves;
         for index in indices {
-            let leaves = self.process_graph_node(index, graph);
+            let leaves = self.process_graph_node(index, graph, digits);
             new_indices.extend(leaves);
         }
 
@@ -104,3 +140,175 @@ impl App {
         new_indices
     }
 }
+
+/// Solves an equation in one unknown by the roots of a polynomial if `apply_inverse` can't
+/// isolate the unknown, because it's of degree two or more or on both sides. Every root becomes
+/// an equation `x = root`.
+///
+/// Approximate roots become `x = midpoint` with the constraint `lower <= x <= upper`, which
+/// marks them as approximate. If non-real roots aren't found, the equation itself is kept as
+/// a solution too, with their number as the constraint.
+fn polynomial_solutions(equation: &Equation, digits: u32) -> Option<Vec<(Equation, Vec<String>)>> {
+    let [lhs, rhs] = equation.equation_sides.as_slice() else {
+        return None;
+    };
+    let variables = |side: &Element| {
+        side.cache
+            .as_ref()
+            .map(|cache| cache.variables.clone())
+            .unwrap_or_default()
+    };
+    let (lhs_variables, rhs_variables) = (variables(lhs), variables(rhs));
+
+    let unknowns = lhs_variables.union(&rhs_variables).collect::<Vec<_>>();
+    let [unknown] = unknowns.as_slice() else {
+        return None;
+    };
+    let unknown = Identifier::parse(unknown);
+
+    let degree = Element::simple_sub(lhs.clone(), rhs.clone()).degree_in(&unknown)?;
+    let on_both_sides = !lhs_variables.is_empty() && !rhs_variables.is_empty();
+    if degree < 2 && !on_both_sides {
+        return None;
+    }
+
+    let variable = Element::new(
+        Sign::Positive,
+        NodeOrExpression::Node(Node::Variable(unknown.clone())),
+    );
+    let roots = match equation.roots(&unknown, digits) {
+        Ok(roots) => roots,
+        Err(RootsError::NoSolution) => return Some(vec![]),
+        Err(_) => return None,
+    };
+
+    let mut solutions = roots
+        .found
+        .into_iter()
+        .map(|root| {
+            let (root, constraints) = match root {
+                Root::Exact(root) => (root, vec![]),
+                Root::Approximate(interval) => (
+                    Element::number(interval.midpoint()),
+                    vec![format!(
+                        "{} <= {unknown} <= {}",
+                        interval.lower, interval.upper
+                    )],
+                ),
+            };
+            let solution = Equation {
+                equation_sides: vec![variable.clone(), root],
+                ..equation.clone()
+            };
+
+            (solution, constraints)
+        })
+        .collect::<Vec<_>>();
+
+    if roots.missing_non_real > 0 {
+        solutions.push((
+            equation.clone(),
+            vec![format!(
+                "{} non-real roots aren't found",
+                roots.missing_non_real
+            )],
+        ));
+    }
+
+    Some(solutions)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::ast::context::Context;
+
+    /// The solutions of `input` with their constraints, sorted.
+    fn leaves_with_constraints(input: &str, digits: u32) -> Vec<(String, Vec<String>)> {
+        let app = App::new().unwrap();
+        let ctx_uuid = app.borrow_mut().add_context(Context::new(Rc::clone(&app)));
+        let uuid = App::try_add_equation(Rc::clone(&app), ctx_uuid, input).unwrap();
+        let mut equation = app
+            .borrow()
+            .get_context(ctx_uuid)
+            .and_then(|context| context.get_equation(uuid))
+            .unwrap()
+            .clone();
+
+        let graph = app.borrow_mut().solve_equation(&mut equation, digits);
+        graph
+            .leaves()
+            .into_iter()
+            .map(|(leaf, constraints)| (leaf.to_string(), constraints))
+            .sorted()
+            .collect()
+    }
+
+    fn leaves(input: &str) -> Vec<String> {
+        leaves_with_constraints(input, 20)
+            .into_iter()
+            .map(|(leaf, _)| leaf)
+            .collect()
+    }
+
+    #[test]
+    fn test_solve() {
+        let cases = [
+            ("x^2 + x = 2", vec!["x = -2", "x = 1"]),
+            ("x^2 = 2x", vec!["x = 0", "x = 2"]),
+            ("2x = x + 1", vec!["x = 1"]),
+            ("x^2 + 1 = x^2", vec![]),
+            ("x + 1 = 0", vec!["x = -1"]),
+            ("(x + y)*2 = 1", vec!["x + y = 1/2"]),
+            ("sin(x) = x", vec!["sin(x) = x"]),
+            ("1/x = 2", vec!["1/x = 2"]),
+            ("x*(x - 1)*(x + 2) = 0", vec!["x = -2", "x = 0", "x = 1"]),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(leaves(input), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_cancelled_factors() {
+        let cases = [
+            ("(x^2 - 1)/(x - 1) = 2", ("x = 1", vec!["x - 1 != 0"])),
+            ("(x^2 - 4)/(x - 2) = 4", ("x = 2", vec!["x - 2 != 0"])),
+        ];
+
+        for (input, (leaf, constraints)) in cases {
+            assert_eq!(
+                leaves_with_constraints(input, 20),
+                [(
+                    leaf.to_string(),
+                    constraints.iter().map(|c| c.to_string()).collect()
+                )],
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_approximate_solutions() {
+        let leaves = leaves_with_constraints("x^3 = 2", 2);
+
+        assert_eq!(
+            leaves,
+            [
+                (
+                    "x = 1293/1024".to_string(),
+                    vec!["645/512 <= x <= 81/64".to_string()]
+                ),
+                (
+                    "x^3 = 2".to_string(),
+                    vec!["2 non-real roots aren't found".to_string()]
+                ),
+            ]
+        );
+    }
+}
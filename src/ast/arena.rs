@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+
+use num::BigRational;
+
+use super::{
+    product::Product, Constant, Element, Expression, Identifier, Node, NodeOrExpression, Sign,
+};
+
+/// Handle of an element interned in an [`ElementArena`]. Two handles from the same arena are
+/// equal exactly if the elements are structurally equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ElementId(usize);
+
+/// An element whose children are stored in the arena, so every subtree is only stored once.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Interned {
+    Number(BigRational),
+    Variable(Identifier),
+    Constant(Constant),
+    Power(ElementId, ElementId),
+    Modulo(ElementId, ElementId),
+    Factorial(ElementId),
+    Function(Identifier, Vec<ElementId>),
+    /// The numerators and denominators of the products.
+    Expression(Vec<(Vec<ElementId>, Vec<ElementId>)>),
+}
+
+/// Stores elements with hash-consing: identical subtrees are interned once and shared, so they
+/// can be compared by their [`ElementId`] instead of walking the trees.
+///
+/// Caches aren't stored. Elements that only become equal after reordering their terms should be
+/// canonicalized before they are interned, see [`Element::canonicalize`].
+#[derive(Debug, Default)]
+pub struct ElementArena {
+    elements: Vec<(Sign, Interned)>,
+    ids: HashMap<(Sign, Interned), ElementId>,
+}
+
+impl ElementArena {
+    pub fn new() -> ElementArena {
+        ElementArena::default()
+    }
+
+    /// The number of distinct subtrees in the arena.
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Adds `element` and all its subtrees to the arena, reusing the ones stored already.
+    pub fn intern(&mut self, element: &Element) -> ElementId {
+        let mut intern_all = |elements: &Vec<Element>| {
+            elements
+                .iter()
+                .map(|element| self.intern(element))
+                .collect::<Vec<_>>()
+        };
+
+        let interned = match &element.node_or_expression {
+            NodeOrExpression::Node(node) => match node {
+                Node::Number(number) => Interned::Number(number.clone()),
+                Node::Variable(name) => Interned::Variable(name.clone()),
+                Node::Constant(constant) => Interned::Constant(*constant),
+                Node::Power { base, power } => {
+                    Interned::Power(self.intern(base), self.intern(power))
+                }
+                Node::Modulo { lhs, rhs } => Interned::Modulo(self.intern(lhs), self.intern(rhs)),
+                Node::Factorial { child } => Interned::Factorial(self.intern(child)),
+                Node::Function { name, arguments } => {
+                    Interned::Function(name.clone(), intern_all(arguments))
+                }
+            },
+            NodeOrExpression::Expression(expression) => Interned::Expression(
+                expression
+                    .products
+                    .iter()
+                    .map(|product| {
+                        (
+                            intern_all(&product.numerator),
+                            intern_all(&product.denominator),
+                        )
+                    })
+                    .collect(),
+            ),
+        };
+
+        let key = (element.sign, interned);
+        if let Some(id) = self.ids.get(&key) {
+            return *id;
+        }
+
+        let id = ElementId(self.elements.len());
+        self.elements.push(key.clone());
+        self.ids.insert(key, id);
+        id
+    }
+
+    /// Builds the element with the handle `id` back as a tree.
+    ///
+    /// # Panics
+    ///
+    /// If `id` doesn't belong to this arena.
+    pub fn get(&self, id: ElementId) -> Element {
+        let resolve_all = |ids: &Vec<ElementId>| ids.iter().map(|id| self.get(*id)).collect();
+        let boxed = |id: &ElementId| Box::new(self.get(*id));
+
+        let (sign, interned) = &self.elements[id.0];
+        let node_or_expression = match interned {
+            Interned::Number(number) => NodeOrExpression::Node(Node::Number(number.clone())),
+            Interned::Variable(name) => NodeOrExpression::Node(Node::Variable(name.clone())),
+            Interned::Constant(constant) => NodeOrExpression::Node(Node::Constant(*constant)),
+            Interned::Power(base, power) => NodeOrExpression::Node(Node::Power {
+                base: boxed(base),
+                power: boxed(power),
+            }),
+            Interned::Modulo(lhs, rhs) => NodeOrExpression::Node(Node::Modulo {
+                lhs: boxed(lhs),
+                rhs: boxed(rhs),
+            }),
+            Interned::Factorial(child) => NodeOrExpression::Node(Node::Factorial {
+                child: boxed(child),
+            }),
+            Interned::Function(name, arguments) => NodeOrExpression::Node(Node::Function {
+                name: name.clone(),
+                arguments: resolve_all(arguments),
+            }),
+            Interned::Expression(products) => NodeOrExpression::Expression(Expression::new(
+                products
+                    .iter()
+                    .map(|(numerator, denominator)| {
+                        Product::new(resolve_all(numerator), resolve_all(denominator))
+                    })
+                    .collect(),
+            )),
+        };
+
+        Element::new(*sign, node_or_expression)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::parse;
+
+    #[test]
+    fn test_intern() {
+        let mut arena = ElementArena::new();
+
+        let lhs = arena.intern(&parse("sin(x + 1)^2 + cos(x + 1)"));
+        let size = arena.len();
+        let rhs = arena.intern(&parse("sin(x + 1)^2 + cos(x + 1)"));
+        assert_eq!(lhs, rhs);
+        assert_eq!(arena.len(), size);
+
+        // `x`, `1`, `x + 1`, `sin(x + 1)`, `2`, the power, `cos(x + 1)` and the sum, `x + 1` is
+        // only stored once
+        assert_eq!(size, 8);
+
+        let other = arena.intern(&parse("sin(x + 1)^2 - cos(x + 1)"));
+        assert_ne!(lhs, other);
+
+        let mut element = parse("a*b/(c - 2)");
+        element.analyze(None);
+        let id = arena.intern(&element);
+        assert_eq!(arena.get(id), element.without_cache());
+        assert_eq!(arena.intern(&arena.get(id)), id);
+    }
+
+    #[test]
+    fn test_canonical() {
+        let mut arena = ElementArena::new();
+
+        let lhs = arena.intern(&parse("a*b + c").canonicalize());
+        let rhs = arena.intern(&parse("c + b*a").canonicalize());
+        assert_eq!(lhs, rhs);
+        assert_eq!(
+            parse("a*b + c").canonical_hash(),
+            parse("c + b*a").canonical_hash()
+        );
+        assert_ne!(
+            parse("a*b + c").structural_hash(),
+            parse("c + b*a").structural_hash()
+        );
+    }
+}
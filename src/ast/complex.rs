@@ -0,0 +1,195 @@
+use num::{BigRational, Complex, One, Signed, ToPrimitive, Zero};
+
+use crate::actions::evaluate::{root, Bindings};
+
+use super::{product::Product, Constant, Element, Expression, Node, NodeOrExpression, Sign};
+
+/// An exact complex number `re + im·i` with rational parts.
+pub type GaussianRational = Complex<BigRational>;
+
+/// Integer powers like `(1 + i)^n` are only folded up to this exponent.
+const MAX_EXPONENT: u32 = 1000;
+
+impl Element {
+    /// The value of the element if it only consists of numbers and `i`, like `3 + 4i` or
+    /// `(1 - i)^2/2`.
+    pub fn as_gaussian(&self) -> Option<GaussianRational> {
+        let value = match &self.node_or_expression {
+            NodeOrExpression::Node(node) => node.as_gaussian()?,
+            NodeOrExpression::Expression(expression) => expression.as_gaussian()?,
+        };
+
+        Some(match self.sign {
+            Sign::Positive => value,
+            Sign::Negative => -value,
+        })
+    }
+
+    /// Writes `number` as `a`, `b·i` or `a + b·i`, the way the parser reads `3 + 4i`.
+    pub fn from_gaussian(number: &GaussianRational) -> Element {
+        let imaginary = |factor: BigRational| {
+            let i = |sign| Element::new(sign, NodeOrExpression::Node(Node::Constant(Constant::I)));
+            if factor.is_one() {
+                i(Sign::Positive)
+            } else if (-&factor).is_one() {
+                i(Sign::Negative)
+            } else {
+                Element::simple_mul(Element::number(factor), i(Sign::Positive))
+            }
+        };
+
+        match (
+            number.re.is_zero(),
+            number.im.is_zero(),
+            number.im.is_negative(),
+        ) {
+            (_, true, _) => Element::number(number.re.clone()),
+            (true, false, _) => imaginary(number.im.clone()),
+            (false, false, false) => Element::simple_add(
+                Element::number(number.re.clone()),
+                imaginary(number.im.clone()),
+            ),
+            (false, false, true) => Element::simple_sub(
+                Element::number(number.re.clone()),
+                imaginary(number.im.abs()),
+            ),
+        }
+    }
+}
+
+impl Expression {
+    pub fn as_gaussian(&self) -> Option<GaussianRational> {
+        self.products
+            .iter()
+            .try_fold(GaussianRational::zero(), |sum, product| {
+                Some(sum + product.as_gaussian()?)
+            })
+    }
+}
+
+impl Product {
+    pub fn as_gaussian(&self) -> Option<GaussianRational> {
+        let mut value = GaussianRational::one();
+
+        for element in &self.numerator {
+            value *= element.as_gaussian()?;
+        }
+
+        for element in &self.denominator {
+            let divisor = element.as_gaussian()?;
+            if divisor.is_zero() {
+                return None;
+            }
+            value /= divisor;
+        }
+
+        Some(value)
+    }
+}
+
+impl Node {
+    pub fn as_gaussian(&self) -> Option<GaussianRational> {
+        match self {
+            Node::Number(number) => Some(Complex::new(number.clone(), BigRational::zero())),
+            Node::Constant(Constant::I) => {
+                Some(Complex::new(BigRational::zero(), BigRational::one()))
+            }
+            Node::Power { base, power } => {
+                let base = base.as_gaussian()?;
+                let exponent = power.evaluate(&Bindings::new()).ok()?;
+                if !exponent.is_integer() {
+                    return None;
+                }
+
+                let magnitude = exponent
+                    .abs()
+                    .to_integer()
+                    .to_u32()
+                    .filter(|magnitude| *magnitude <= MAX_EXPONENT)?;
+                let value = num::pow(base, magnitude as usize);
+
+                if !exponent.is_negative() {
+                    Some(value)
+                } else if value.is_zero() {
+                    None
+                } else {
+                    Some(GaussianRational::one() / value)
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The exact principal square root of `number`, if both of its parts are rational.
+///
+/// With `|z|` the absolute value, `sqrt(a + b·i) = sqrt((|z| + a)/2) ± sqrt((|z| - a)/2)·i`
+/// where the sign is the one of `b`.
+pub fn gaussian_sqrt(number: &GaussianRational) -> Option<GaussianRational> {
+    let two = BigRational::from_integer(2.into());
+
+    let modulus = root(&number.norm_sqr(), 2).ok()?;
+    let re = root(&((&modulus + &number.re) / &two), 2).ok()?;
+    let im = root(&((&modulus - &number.re) / two), 2).ok()?;
+
+    Some(Complex::new(
+        re,
+        if number.im.is_negative() { -im } else { im },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::parse;
+
+    fn gaussian(re: i64, im: i64) -> GaussianRational {
+        Complex::new(
+            BigRational::from_integer(re.into()),
+            BigRational::from_integer(im.into()),
+        )
+    }
+
+    #[test]
+    fn test_as_gaussian() {
+        let cases = [
+            ("3+4i", Some(gaussian(3, 4))),
+            ("2i", Some(gaussian(0, 2))),
+            ("-i", Some(gaussian(0, -1))),
+            ("(1 + i)^2", Some(gaussian(0, 2))),
+            ("(3 + 4i)/(1 + 2i)", Some(gaussian(11, -2) / gaussian(5, 0))),
+            ("i^-1", Some(gaussian(0, -1))),
+            ("x + i", None),
+            ("1/(i - i)", None),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(parse(input).as_gaussian(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_from_gaussian() {
+        let cases = [
+            (gaussian(3, 4), "3 + 4i"),
+            (gaussian(3, -4), "3 - 4i"),
+            (gaussian(0, 1), "i"),
+            (gaussian(0, -2), "-2i"),
+            (gaussian(-5, 0), "-5"),
+        ];
+
+        for (number, expected) in cases {
+            let element = Element::from_gaussian(&number);
+            assert_eq!(element.to_string(), expected);
+            assert_eq!(element.as_gaussian(), Some(number));
+        }
+    }
+
+    #[test]
+    fn test_gaussian_sqrt() {
+        assert_eq!(gaussian_sqrt(&gaussian(-1, 0)), Some(gaussian(0, 1)));
+        assert_eq!(gaussian_sqrt(&gaussian(3, 4)), Some(gaussian(2, 1)));
+        assert_eq!(gaussian_sqrt(&gaussian(-3, -4)), Some(gaussian(1, -2)));
+        assert_eq!(gaussian_sqrt(&gaussian(2, 0)), None);
+    }
+}
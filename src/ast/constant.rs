@@ -0,0 +1,77 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use super::identifier::Identifier;
+
+/// Reserved names which stand for a fixed value instead of an unknown, they are never solved
+/// for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Constant {
+    Pi,
+    E,
+    /// The imaginary unit, `i^2 = -1`.
+    I,
+    Infinity,
+}
+
+impl Constant {
+    pub const ALL: [Constant; 4] = [Constant::Pi, Constant::E, Constant::I, Constant::Infinity];
+
+    /// The name it's written as in plain text.
+    pub fn name(self) -> &'static str {
+        match self {
+            Constant::Pi => "pi",
+            Constant::E => "e",
+            Constant::I => "i",
+            Constant::Infinity => "infinity",
+        }
+    }
+
+    /// The symbol it's written as in Unicode text, e.g. `π`.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Constant::Pi => "π",
+            Constant::E => "e",
+            Constant::I => "i",
+            Constant::Infinity => "∞",
+        }
+    }
+
+    /// The constant an identifier refers to, names with a subscript or primes like `e_1` are
+    /// still variables.
+    pub fn from_identifier(identifier: &Identifier) -> Option<Constant> {
+        let name = identifier.as_plain()?;
+        Constant::ALL
+            .into_iter()
+            .find(|constant| constant.name() == name)
+    }
+}
+
+impl Display for Constant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_identifier() {
+        assert_eq!(
+            Constant::from_identifier(&Identifier::new("pi")),
+            Some(Constant::Pi)
+        );
+        assert_eq!(
+            Constant::from_identifier(&Identifier::new("infinity")),
+            Some(Constant::Infinity)
+        );
+        assert_eq!(
+            Constant::from_identifier(&Identifier::new("e").with_subscript("1")),
+            None
+        );
+        assert_eq!(Constant::from_identifier(&Identifier::new("x")), None);
+    }
+}
@@ -4,7 +4,10 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use uuid::Uuid;
 
-use crate::tokenizer::parser::ParseError;
+use crate::tokenizer::{
+    parser::ParseError,
+    span::{render_diagnostic, Span},
+};
 
 use super::{app::App, token_to_element::TokensToEquationError, Equation};
 
@@ -48,6 +51,23 @@ pub enum CreateEquationError {
     TokensToEquationError(TokensToEquationError),
 }
 
+impl CreateEquationError {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            CreateEquationError::ParseError(error) => Some(error.span()),
+            CreateEquationError::TokensToEquationError(error) => error.span(),
+        }
+    }
+
+    /// Renders the error with the offending part of `input` underlined, if its location is known.
+    pub fn render(&self, input: &str) -> String {
+        match self.span() {
+            Some(span) => render_diagnostic(input, span, &self.to_string()),
+            None => format!("error: {self}"),
+        }
+    }
+}
+
 impl Context {
     pub fn new(app: Rc<RefCell<App>>) -> Context {
         Context {
@@ -1,7 +1,12 @@
 use serde::{Deserialize, Serialize};
 
 use super::{product::Product, Expression, Node};
-use std::{cmp::Ordering, collections::HashSet, ops::Mul};
+use std::{
+    cmp::Ordering,
+    collections::{hash_map::DefaultHasher, HashSet},
+    hash::{Hash, Hasher},
+    ops::Mul,
+};
 
 pub(crate) trait ShouldBeParenthesized {
     fn should_be_parenthesized(&self) -> bool;
@@ -11,7 +16,9 @@ pub(crate) trait IsTimesVisible {
     fn is_times_visible(&self, last: &Element) -> bool;
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
 pub enum Sign {
     #[default]
     Positive,
@@ -30,7 +37,7 @@ impl Mul for Sign {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub enum NodeOrExpression {
     Node(Node),
     Expression(Expression),
@@ -78,6 +85,14 @@ pub struct Element {
     pub cache: Option<ElementCache>,
 }
 
+/// Only the structure is hashed, the cache is derived from it.
+impl Hash for Element {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.sign.hash(state);
+        self.node_or_expression.hash(state);
+    }
+}
+
 impl Element {
     pub fn new(sign: Sign, node_or_expression: NodeOrExpression) -> Self {
         Self {
@@ -87,6 +102,11 @@ impl Element {
         }
     }
 
+    /// A positive element holding `value`, which can be negative itself.
+    pub fn number(value: num::BigRational) -> Self {
+        Element::new(Sign::Positive, NodeOrExpression::Node(Node::Number(value)))
+    }
+
     // assume analyzed
     pub fn is_number(&self) -> bool {
         if self.cache.is_none() {
@@ -120,6 +140,21 @@ impl Element {
         }
     }
 
+    /// A hash of the structure of the element, equal for elements which only differ in their
+    /// caches. See [`Element::canonical_hash`] for one that ignores the order of terms too.
+    pub fn structural_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// A copy with every cache removed, so that equal subtrees compare equal.
+    pub fn without_cache(&self) -> Element {
+        let mut element = self.clone();
+        element.apply_to_every_element_mut(&mut |element| element.cache = None, false, None);
+        element
+    }
+
     pub fn invert_sign(&mut self) {
         match self.sign {
             Sign::Positive => self.sign = Sign::Negative,
@@ -3,9 +3,14 @@ use std::{cell::RefCell, rc::Rc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::tokenizer::{parser::TokenizedString, token::Operation};
+use crate::tokenizer::{latex::Latex, parser::TokenizedString, rpn::Rpn, token::Operation};
 
-use super::{app::App, context::CreateEquationError, Element};
+use super::{
+    app::App,
+    context::CreateEquationError,
+    token_to_element::{rpn_to_ast, TokensToEquationError},
+    Element,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EquationCache {}
@@ -80,3 +85,37 @@ impl TryFrom<&str> for NoContextEquation {
         Ok(ast)
     }
 }
+
+impl TryFrom<Latex<'_>> for NoContextEquation {
+    type Error = CreateEquationError;
+
+    fn try_from(value: Latex<'_>) -> Result<Self, Self::Error> {
+        let tokens = TokenizedString::try_from(value).map_err(CreateEquationError::ParseError)?;
+
+        NoContextEquation::try_from(tokens).map_err(CreateEquationError::TokensToEquationError)
+    }
+}
+
+impl TryFrom<Rpn<'_>> for NoContextEquation {
+    type Error = CreateEquationError;
+
+    /// The tokens are already in postfix order, so every side goes straight to the AST.
+    fn try_from(value: Rpn<'_>) -> Result<Self, Self::Error> {
+        let sides = value
+            .tokenize()
+            .map_err(CreateEquationError::ParseError)?
+            .into_iter()
+            .map(|(tokens, operation)| {
+                let element = rpn_to_ast(&tokens).map_err(|error| {
+                    CreateEquationError::TokensToEquationError(
+                        TokensToEquationError::AbstractSyntaxTreeError(error),
+                    )
+                })?;
+
+                Ok(EquationSide::new(element, operation))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(NoContextEquation { sides })
+    }
+}
@@ -6,7 +6,7 @@ use super::{
     Element,
 };
 
-#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub struct Expression {
     pub products: Vec<Product>,
 }
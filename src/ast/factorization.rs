@@ -0,0 +1,795 @@
+use itertools::Itertools;
+use num::{BigInt, BigRational, Integer, One, Signed, ToPrimitive, Zero};
+
+use super::{
+    polynomial::{Monomial, Polynomial, PolynomialError},
+    Identifier,
+};
+
+/// Rational roots are only searched if the first and the last coefficient are at most this,
+/// larger ones are left to the modular factorization.
+const MAX_ROOT_DIVISOR: u64 = 1_000_000;
+
+/// A polynomial with integer coefficients, starting with the constant one.
+type Dense = Vec<BigInt>;
+
+/// A polynomial with coefficients modulo a prime, starting with the constant one.
+type Modular = Vec<u64>;
+
+/// A polynomial written as `content * factor_1^multiplicity_1 * factor_2^multiplicity_2 ...`
+/// where the factors are irreducible over the rationals, have integer coefficients without a
+/// common divisor and a positive leading coefficient.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Factorization {
+    pub content: BigRational,
+    pub factors: Vec<(Polynomial, u32)>,
+}
+
+impl Polynomial {
+    /// Factors a polynomial in one variable into irreducible factors over the rationals.
+    ///
+    /// The content is taken out first and the rest is split by multiplicity with a square-free
+    /// decomposition. Linear factors are found with the rational root test and the remaining
+    /// ones by factoring modulo a prime, lifting the factors with Hensel's lemma and recombining
+    /// them (Berlekamp-Zassenhaus).
+    pub fn factor(&self) -> Result<Factorization, PolynomialError> {
+        let variables = self.variables();
+        let variable = match variables.iter().collect::<Vec<_>>().as_slice() {
+            [] => {
+                return Ok(Factorization {
+                    content: self.as_constant().unwrap_or_default(),
+                    factors: vec![],
+                })
+            }
+            [variable] => (*variable).clone(),
+            _ => return Err(PolynomialError::NotUnivariate(self.to_string())),
+        };
+
+        let mut factors = vec![];
+        for (square_free, multiplicity) in square_free_decomposition(self, &variable) {
+            for factor in factor_square_free(to_dense(&square_free, &variable)) {
+                factors.push((factor, multiplicity));
+            }
+        }
+        factors.sort_by(|(lhs, lhs_multiplicity), (rhs, rhs_multiplicity)| {
+            let terms = |factor: &Dense| {
+                factor
+                    .iter()
+                    .filter(|coefficient| !coefficient.is_zero())
+                    .count()
+            };
+            lhs.len()
+                .cmp(&rhs.len())
+                .then(terms(lhs).cmp(&terms(rhs)))
+                .then_with(|| lhs.iter().rev().cmp(rhs.iter().rev()))
+                .then(lhs_multiplicity.cmp(rhs_multiplicity))
+        });
+
+        let factors = factors
+            .into_iter()
+            .map(|(factor, multiplicity)| (from_dense(&factor, &variable), multiplicity))
+            .collect::<Vec<_>>();
+        let content = factors.iter().fold(
+            self.leading_coefficient(),
+            |content, (factor, multiplicity)| {
+                content / num::pow(factor.leading_coefficient(), *multiplicity as usize)
+            },
+        );
+
+        Ok(Factorization { content, factors })
+    }
+}
+
+/// Splits `polynomial` into the products of its factors with the same multiplicity with Yun's
+/// algorithm, the parts are square-free and coprime.
+fn square_free_decomposition(
+    polynomial: &Polynomial,
+    variable: &Identifier,
+) -> Vec<(Polynomial, u32)> {
+    let exact = |lhs: &Polynomial, rhs: &Polynomial| lhs.div_rem(rhs).unwrap().0;
+
+    let derivative = polynomial.derivative(variable);
+    let divisor = polynomial.gcd(&derivative);
+    let mut rest = exact(polynomial, &divisor);
+    let mut difference = &exact(&derivative, &divisor) - &rest.derivative(variable);
+
+    let mut parts = vec![];
+    let mut multiplicity = 1;
+    while rest.degree().unwrap_or(0) > 0 {
+        let part = rest.gcd(&difference);
+        rest = exact(&rest, &part);
+        difference = &exact(&difference, &part) - &rest.derivative(variable);
+
+        if part.degree().unwrap_or(0) > 0 {
+            parts.push((part, multiplicity));
+        }
+        multiplicity += 1;
+    }
+
+    parts
+}
+
+/// The primitive integer polynomial with a positive leading coefficient which is a rational
+/// multiple of `polynomial`.
+fn to_dense(polynomial: &Polynomial, variable: &Identifier) -> Dense {
+    let coefficients = polynomial.coefficients_in(variable);
+    let degree = coefficients.keys().max().copied().unwrap_or(0);
+
+    let mut rationals = vec![BigRational::zero(); degree as usize + 1];
+    for (exponent, coefficient) in coefficients {
+        rationals[exponent as usize] = coefficient.as_constant().unwrap_or_default();
+    }
+
+    let denominator = rationals
+        .iter()
+        .fold(BigInt::one(), |lcm, rational| lcm.lcm(rational.denom()));
+    primitive(
+        rationals
+            .iter()
+            .map(|rational| {
+                (rational * BigRational::from_integer(denominator.clone())).to_integer()
+            })
+            .collect(),
+    )
+}
+
+fn from_dense(dense: &Dense, variable: &Identifier) -> Polynomial {
+    dense
+        .iter()
+        .enumerate()
+        .fold(Polynomial::zero(), |sum, (exponent, coefficient)| {
+            let term = Polynomial::term(
+                Monomial::from([(variable.clone(), exponent as u32)]),
+                BigRational::from_integer(coefficient.clone()),
+            );
+            &sum + &term
+        })
+}
+
+/// Factors a square-free primitive polynomial with a positive leading coefficient.
+fn factor_square_free(mut polynomial: Dense) -> Vec<Dense> {
+    let mut factors = vec![];
+
+    if polynomial.len() > 2 && polynomial[0].is_zero() {
+        factors.push(vec![BigInt::zero(), BigInt::one()]);
+        polynomial.remove(0);
+    }
+
+    factors.extend(rational_root_factors(&mut polynomial));
+    if polynomial.len() > 1 {
+        factors.extend(zassenhaus(polynomial));
+    }
+
+    factors
+}
+
+/// Divides out the linear factors `q*x - p` for the rational roots `p/q`, which have to divide
+/// the last and the first coefficient.
+fn rational_root_factors(polynomial: &mut Dense) -> Vec<Dense> {
+    let mut factors = vec![];
+    let bounded = |coefficient: &BigInt| {
+        coefficient
+            .abs()
+            .to_u64()
+            .filter(|coefficient| *coefficient <= MAX_ROOT_DIVISOR)
+    };
+    let (Some(constant), Some(leading)) = (
+        polynomial.first().and_then(bounded),
+        polynomial.last().and_then(bounded),
+    ) else {
+        return factors;
+    };
+
+    for denominator in divisors(leading) {
+        for numerator in divisors(constant) {
+            for numerator in [-BigInt::from(numerator), BigInt::from(numerator)] {
+                if polynomial.len() <= 2 || !numerator.gcd(&denominator.into()).is_one() {
+                    continue;
+                }
+
+                let factor = vec![-numerator, BigInt::from(denominator)];
+                if let Some(quotient) = divide_exact(polynomial, &factor) {
+                    factors.push(factor);
+                    *polynomial = quotient;
+                }
+            }
+        }
+    }
+
+    // what is left is linear or constant
+    if polynomial.len() == 2 {
+        factors.push(std::mem::replace(polynomial, vec![BigInt::one()]));
+    }
+
+    factors
+}
+
+fn divisors(number: u64) -> Vec<u64> {
+    (1..)
+        .take_while(|divisor| divisor * divisor <= number)
+        .filter(|divisor| number.is_multiple_of(*divisor))
+        .flat_map(|divisor| [divisor, number / divisor])
+        .sorted()
+        .dedup()
+        .collect()
+}
+
+/// Factors a square-free primitive polynomial of degree two or more.
+fn zassenhaus(polynomial: Dense) -> Vec<Dense> {
+    let degree = polynomial.len() - 1;
+    let leading = polynomial[degree].clone();
+
+    let prime = (3..)
+        .step_by(2)
+        .filter(|candidate| is_prime(*candidate))
+        .find(|prime| {
+            let modular = to_modular(&polynomial, *prime);
+            modular.len() == polynomial.len()
+                && modular_gcd(&modular, &modular_derivative(&modular, *prime), *prime).len() == 1
+        })
+        .expect("a square-free polynomial is square-free modulo almost every prime");
+
+    let modular = to_modular(&polynomial, prime);
+    let monic = modular_scale(&modular, modular_inverse(modular[degree], prime), prime);
+    let modular_factors = berlekamp(&monic, prime);
+    if modular_factors.len() == 1 {
+        return vec![polynomial];
+    }
+
+    // the coefficients of a factor times the leading coefficient are below this
+    let maximum = polynomial.iter().map(BigInt::abs).max().unwrap_or_default();
+    let bound = leading.abs() * num::pow(BigInt::from(2), degree) * (degree + 1) * maximum;
+    let mut exponent = 1;
+    let mut modulus = BigInt::from(prime);
+    while modulus <= &bound * 2 {
+        modulus *= prime;
+        exponent += 1;
+    }
+
+    let lifted = hensel_lift(&polynomial, &modular_factors, prime, exponent);
+    recombine(polynomial, lifted, &modulus)
+}
+
+fn is_prime(number: u64) -> bool {
+    number >= 2
+        && (2..)
+            .take_while(|divisor| divisor * divisor <= number)
+            .all(|divisor| !number.is_multiple_of(divisor))
+}
+
+/// Finds the true factors among the products of the lifted modular factors, starting with the
+/// smallest subsets.
+fn recombine(mut polynomial: Dense, mut lifted: Vec<Dense>, modulus: &BigInt) -> Vec<Dense> {
+    let mut factors = vec![];
+    let mut size = 1;
+
+    while 2 * size <= lifted.len() {
+        let found = (0..lifted.len()).combinations(size).find_map(|subset| {
+            let leading = vec![polynomial.last().unwrap().clone()];
+            let product = subset.iter().fold(leading, |product, index| {
+                reduce(&multiply(&product, &lifted[*index]), modulus)
+            });
+            let candidate = primitive(symmetric(product, modulus));
+
+            divide_exact(&polynomial, &candidate).map(|quotient| (subset, candidate, quotient))
+        });
+
+        match found {
+            Some((subset, candidate, quotient)) => {
+                factors.push(candidate);
+                polynomial = quotient;
+                lifted = lifted
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(index, _)| !subset.contains(index))
+                    .map(|(_, factor)| factor)
+                    .collect();
+            }
+            None => size += 1,
+        }
+    }
+
+    if polynomial.len() > 1 {
+        factors.push(primitive(polynomial));
+    }
+
+    factors
+}
+
+/// Lifts `polynomial = leading*factor_1*factor_2...` modulo `prime` to a factorization into
+/// monic factors modulo `prime^exponent`.
+fn hensel_lift(polynomial: &Dense, factors: &[Modular], prime: u64, exponent: u32) -> Vec<Dense> {
+    let modulus = num::pow(BigInt::from(prime), exponent as usize);
+
+    let [first, rest @ ..] = factors else {
+        return vec![];
+    };
+    if rest.is_empty() {
+        let leading = polynomial.last().unwrap();
+        let inverse = leading.extended_gcd(&modulus).x;
+        return vec![reduce(&multiply(polynomial, &[inverse]), &modulus)];
+    }
+
+    let leading = to_modular(&polynomial[polynomial.len() - 1..].to_vec(), prime);
+    let others = rest.iter().fold(leading, |product, factor| {
+        modular_mul(&product, factor, prime)
+    });
+    let (factor, cofactor) = lift_pair(polynomial, first, &others, prime, exponent);
+
+    let mut lifted = vec![factor];
+    lifted.extend(hensel_lift(&cofactor, rest, prime, exponent));
+    lifted
+}
+
+/// Lifts `polynomial = factor*cofactor` modulo `prime`, with a monic `factor` coprime to the
+/// cofactor, to a factorization modulo `prime^exponent` one power at a time.
+fn lift_pair(
+    polynomial: &Dense,
+    factor: &Modular,
+    cofactor: &Modular,
+    prime: u64,
+    exponent: u32,
+) -> (Dense, Dense) {
+    let (s, t) = modular_gcdex(factor, cofactor, prime);
+    let (mut factor, mut cofactor) = (from_modular(factor), from_modular(cofactor));
+    let mut modulus = BigInt::from(prime);
+
+    for _ in 1..exponent {
+        let error = subtract(polynomial, &multiply(&factor, &cofactor));
+        let error = to_modular(
+            &error
+                .iter()
+                .map(|coefficient| coefficient / &modulus)
+                .collect(),
+            prime,
+        );
+
+        // `s*factor + t*cofactor = 1` splits the error into `tau*factor + sigma*cofactor`
+        let (quotient, tau) = modular_divrem(
+            &modular_mul(&t, &error, prime),
+            &to_modular(&factor, prime),
+            prime,
+        );
+        let sigma = modular_add(
+            &modular_mul(&s, &error, prime),
+            &modular_mul(&quotient, &to_modular(&cofactor, prime), prime),
+            prime,
+        );
+
+        factor = add(&factor, &multiply(&from_modular(&tau), &[modulus.clone()]));
+        cofactor = add(
+            &cofactor,
+            &multiply(&from_modular(&sigma), &[modulus.clone()]),
+        );
+        modulus *= prime;
+    }
+
+    (reduce(&factor, &modulus), reduce(&cofactor, &modulus))
+}
+
+/// The monic irreducible factors of a monic square-free polynomial modulo `prime`, with
+/// Berlekamp's algorithm.
+fn berlekamp(polynomial: &Modular, prime: u64) -> Vec<Modular> {
+    let degree = polynomial.len() - 1;
+
+    // the rows are `x^(prime*i)` modulo the polynomial
+    let x_prime = modular_powmod(&vec![0, 1], prime, polynomial, prime);
+    let mut rows = vec![];
+    let mut row = vec![1];
+    for _ in 0..degree {
+        let mut padded = row.clone();
+        padded.resize(degree, 0);
+        rows.push(padded);
+        row = modular_divrem(&modular_mul(&row, &x_prime, prime), polynomial, prime).1;
+    }
+
+    // the polynomials `v` with `v^prime = v` form the kernel of the transposed `Q - I`
+    let matrix = (0..degree)
+        .map(|column| {
+            (0..degree)
+                .map(|index| (rows[index][column] + prime - u64::from(index == column)) % prime)
+                .collect()
+        })
+        .collect();
+    let basis = kernel(matrix, prime);
+
+    let mut factors = vec![polynomial.clone()];
+    for vector in &basis {
+        let vector = trim(vector.clone());
+        if vector.len() <= 1 {
+            continue;
+        }
+
+        for shift in 0..prime {
+            if factors.len() == basis.len() {
+                return factors;
+            }
+
+            let shifted = modular_sub(&vector, &vec![shift], prime);
+            factors = factors
+                .into_iter()
+                .flat_map(|factor| {
+                    let divisor = modular_gcd(&factor, &shifted, prime);
+                    if factor.len() <= 2 || divisor.len() <= 1 || divisor.len() == factor.len() {
+                        vec![factor]
+                    } else {
+                        let quotient = modular_divrem(&factor, &divisor, prime).0;
+                        vec![divisor, quotient]
+                    }
+                })
+                .collect();
+        }
+    }
+
+    factors
+}
+
+/// A basis of the solutions of `matrix*v = 0` modulo `prime`.
+fn kernel(mut matrix: Vec<Vec<u64>>, prime: u64) -> Vec<Vec<u64>> {
+    let size = matrix.len();
+    let mut pivots = vec![];
+
+    for column in 0..size {
+        let row = pivots.len();
+        let Some(pivot) = (row..size).find(|index| matrix[*index][column] != 0) else {
+            continue;
+        };
+        matrix.swap(row, pivot);
+
+        let inverse = modular_inverse(matrix[row][column], prime);
+        for value in &mut matrix[row] {
+            *value = *value * inverse % prime;
+        }
+        for other in 0..size {
+            let factor = matrix[other][column];
+            if other == row || factor == 0 {
+                continue;
+            }
+            let pivot_row = matrix[row].clone();
+            for (value, pivot_value) in matrix[other].iter_mut().zip(pivot_row) {
+                let subtrahend = factor * pivot_value % prime;
+                *value = (*value + prime - subtrahend) % prime;
+            }
+        }
+        pivots.push(column);
+    }
+
+    (0..size)
+        .filter(|column| !pivots.contains(column))
+        .map(|free| {
+            let mut vector = vec![0; size];
+            vector[free] = 1;
+            for (row, pivot) in pivots.iter().enumerate() {
+                vector[*pivot] = (prime - matrix[row][free]) % prime;
+            }
+            vector
+        })
+        .collect()
+}
+
+fn trim<T: Zero>(mut polynomial: Vec<T>) -> Vec<T> {
+    while polynomial.last().is_some_and(Zero::is_zero) {
+        polynomial.pop();
+    }
+    polynomial
+}
+
+/// Divides out the greatest common divisor of the coefficients and makes the leading one
+/// positive.
+fn primitive(polynomial: Dense) -> Dense {
+    let polynomial = trim(polynomial);
+    let mut divisor = polynomial
+        .iter()
+        .fold(BigInt::zero(), |divisor, coefficient| {
+            divisor.gcd(coefficient)
+        });
+    if divisor.is_zero() {
+        return polynomial;
+    }
+    if polynomial.last().is_some_and(Signed::is_negative) {
+        divisor = -divisor;
+    }
+
+    polynomial
+        .into_iter()
+        .map(|coefficient| coefficient / &divisor)
+        .collect()
+}
+
+fn add(lhs: &Dense, rhs: &Dense) -> Dense {
+    let mut sum = vec![BigInt::zero(); lhs.len().max(rhs.len())];
+    for (index, coefficient) in lhs.iter().enumerate() {
+        sum[index] += coefficient;
+    }
+    for (index, coefficient) in rhs.iter().enumerate() {
+        sum[index] += coefficient;
+    }
+    trim(sum)
+}
+
+fn subtract(lhs: &Dense, rhs: &Dense) -> Dense {
+    add(lhs, &rhs.iter().map(|coefficient| -coefficient).collect())
+}
+
+fn multiply(lhs: &[BigInt], rhs: &[BigInt]) -> Dense {
+    if lhs.is_empty() || rhs.is_empty() {
+        return vec![];
+    }
+
+    let mut product = vec![BigInt::zero(); lhs.len() + rhs.len() - 1];
+    for (i, left) in lhs.iter().enumerate() {
+        for (j, right) in rhs.iter().enumerate() {
+            product[i + j] += left * right;
+        }
+    }
+    trim(product)
+}
+
+/// `dividend/divisor` if the division over the integers leaves no remainder.
+fn divide_exact(dividend: &Dense, divisor: &Dense) -> Option<Dense> {
+    let divisor_degree = divisor.len().checked_sub(1)?;
+    if dividend.len() < divisor.len() {
+        return None;
+    }
+
+    let mut rest = dividend.clone();
+    let mut quotient = vec![BigInt::zero(); dividend.len() - divisor_degree];
+    for index in (0..quotient.len()).rev() {
+        let (coefficient, remainder) =
+            rest[index + divisor_degree].div_rem(&divisor[divisor_degree]);
+        if !remainder.is_zero() {
+            return None;
+        }
+        for (offset, value) in divisor.iter().enumerate() {
+            rest[index + offset] -= &coefficient * value;
+        }
+        quotient[index] = coefficient;
+    }
+
+    rest.iter().all(Zero::is_zero).then_some(quotient)
+}
+
+/// The coefficients modulo `modulus` in `[0, modulus)`.
+fn reduce(polynomial: &Dense, modulus: &BigInt) -> Dense {
+    trim(
+        polynomial
+            .iter()
+            .map(|coefficient| coefficient.mod_floor(modulus))
+            .collect(),
+    )
+}
+
+/// The coefficients modulo `modulus` in `(-modulus/2, modulus/2]`.
+fn symmetric(polynomial: Dense, modulus: &BigInt) -> Dense {
+    let half = modulus / 2;
+    polynomial
+        .into_iter()
+        .map(|coefficient| {
+            if coefficient > half {
+                coefficient - modulus
+            } else {
+                coefficient
+            }
+        })
+        .collect()
+}
+
+fn to_modular(polynomial: &Dense, prime: u64) -> Modular {
+    let modulus = BigInt::from(prime);
+    trim(
+        polynomial
+            .iter()
+            .map(|coefficient| coefficient.mod_floor(&modulus).to_u64().unwrap())
+            .collect(),
+    )
+}
+
+fn from_modular(polynomial: &Modular) -> Dense {
+    polynomial
+        .iter()
+        .map(|coefficient| BigInt::from(*coefficient))
+        .collect()
+}
+
+fn modular_add(lhs: &Modular, rhs: &Modular, prime: u64) -> Modular {
+    let mut sum = vec![0; lhs.len().max(rhs.len())];
+    for (index, coefficient) in lhs.iter().enumerate() {
+        sum[index] = coefficient % prime;
+    }
+    for (index, coefficient) in rhs.iter().enumerate() {
+        sum[index] = (sum[index] + coefficient) % prime;
+    }
+    trim(sum)
+}
+
+fn modular_sub(lhs: &Modular, rhs: &Modular, prime: u64) -> Modular {
+    let negated = rhs
+        .iter()
+        .map(|coefficient| (prime - coefficient % prime) % prime)
+        .collect();
+    modular_add(lhs, &negated, prime)
+}
+
+fn modular_mul(lhs: &Modular, rhs: &Modular, prime: u64) -> Modular {
+    if lhs.is_empty() || rhs.is_empty() {
+        return vec![];
+    }
+
+    let mut product = vec![0; lhs.len() + rhs.len() - 1];
+    for (i, left) in lhs.iter().enumerate() {
+        for (j, right) in rhs.iter().enumerate() {
+            product[i + j] = (product[i + j] + left * right) % prime;
+        }
+    }
+    trim(product)
+}
+
+fn modular_scale(polynomial: &Modular, factor: u64, prime: u64) -> Modular {
+    modular_mul(polynomial, &vec![factor], prime)
+}
+
+fn modular_inverse(number: u64, prime: u64) -> u64 {
+    let (mut result, mut base, mut exponent) = (1, number % prime, prime - 2);
+    while exponent > 0 {
+        if exponent % 2 == 1 {
+            result = result * base % prime;
+        }
+        base = base * base % prime;
+        exponent /= 2;
+    }
+    result
+}
+
+/// The quotient and the remainder of the division by a non-zero `divisor`.
+fn modular_divrem(dividend: &Modular, divisor: &Modular, prime: u64) -> (Modular, Modular) {
+    let divisor_degree = divisor.len() - 1;
+    let inverse = modular_inverse(divisor[divisor_degree], prime);
+
+    let mut rest = dividend.clone();
+    if rest.len() < divisor.len() {
+        return (vec![], rest);
+    }
+    let mut quotient = vec![0; rest.len() - divisor_degree];
+    for index in (0..quotient.len()).rev() {
+        let coefficient = rest[index + divisor_degree] * inverse % prime;
+        for (offset, value) in divisor.iter().enumerate() {
+            let subtrahend = coefficient * value % prime;
+            rest[index + offset] = (rest[index + offset] + prime - subtrahend) % prime;
+        }
+        quotient[index] = coefficient;
+    }
+
+    (trim(quotient), trim(rest))
+}
+
+/// The monic greatest common divisor.
+fn modular_gcd(lhs: &Modular, rhs: &Modular, prime: u64) -> Modular {
+    let (mut lhs, mut rhs) = (lhs.clone(), rhs.clone());
+    while !rhs.is_empty() {
+        let remainder = modular_divrem(&lhs, &rhs, prime).1;
+        lhs = rhs;
+        rhs = remainder;
+    }
+
+    match lhs.last() {
+        Some(leading) => modular_scale(&lhs, modular_inverse(*leading, prime), prime),
+        None => lhs,
+    }
+}
+
+/// `s` and `t` with `s*lhs + t*rhs = 1` for coprime polynomials.
+fn modular_gcdex(lhs: &Modular, rhs: &Modular, prime: u64) -> (Modular, Modular) {
+    let (mut r0, mut r1) = (lhs.clone(), rhs.clone());
+    let (mut s0, mut s1) = (vec![1], vec![]);
+    let (mut t0, mut t1) = (vec![], vec![1]);
+
+    while !r1.is_empty() {
+        let (quotient, remainder) = modular_divrem(&r0, &r1, prime);
+        r0 = std::mem::replace(&mut r1, remainder);
+        let s = modular_sub(&s0, &modular_mul(&quotient, &s1, prime), prime);
+        s0 = std::mem::replace(&mut s1, s);
+        let t = modular_sub(&t0, &modular_mul(&quotient, &t1, prime), prime);
+        t0 = std::mem::replace(&mut t1, t);
+    }
+
+    let inverse = modular_inverse(r0[0], prime);
+    (
+        modular_scale(&s0, inverse, prime),
+        modular_scale(&t0, inverse, prime),
+    )
+}
+
+fn modular_derivative(polynomial: &Modular, prime: u64) -> Modular {
+    trim(
+        polynomial
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(exponent, coefficient)| exponent as u64 % prime * coefficient % prime)
+            .collect(),
+    )
+}
+
+/// `base^exponent` modulo `modulus` and `prime`.
+fn modular_powmod(base: &Modular, mut exponent: u64, modulus: &Modular, prime: u64) -> Modular {
+    let mut result = vec![1];
+    let mut base = modular_divrem(base, modulus, prime).1;
+
+    while exponent > 0 {
+        if exponent % 2 == 1 {
+            result = modular_divrem(&modular_mul(&result, &base, prime), modulus, prime).1;
+        }
+        base = modular_divrem(&modular_mul(&base, &base, prime), modulus, prime).1;
+        exponent /= 2;
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::polynomial;
+
+    #[test]
+    fn test_factor() {
+        let cases = [
+            ("x^2 - 1", "1", vec![("x - 1", 1), ("x + 1", 1)]),
+            ("2x^2 - 2", "2", vec![("x - 1", 1), ("x + 1", 1)]),
+            ("1/2x^2 - 1/2", "1/2", vec![("x - 1", 1), ("x + 1", 1)]),
+            ("-x^2 + 4", "-1", vec![("x - 2", 1), ("x + 2", 1)]),
+            ("(x - 1)^3*(2x + 3)", "1", vec![("x - 1", 3), ("2x + 3", 1)]),
+            (
+                "x^5 - x",
+                "1",
+                vec![("x", 1), ("x - 1", 1), ("x + 1", 1), ("x^2 + 1", 1)],
+            ),
+            ("x^4 + 1", "1", vec![("x^4 + 1", 1)]),
+            ("x^4 - 10x^2 + 1", "1", vec![("x^4 - 10x^2 + 1", 1)]),
+            (
+                "x^6 - 1",
+                "1",
+                vec![
+                    ("x - 1", 1),
+                    ("x + 1", 1),
+                    ("x^2 - x + 1", 1),
+                    ("x^2 + x + 1", 1),
+                ],
+            ),
+            (
+                "(x^4 + x + 1)*(x^4 - 2x^3 + 3)^2",
+                "1",
+                vec![("x^4 - 2x^3 + 3", 2), ("x^4 + x + 1", 1)],
+            ),
+            (
+                "(x^2 - 2)*(x^2 - 3)*(x^3 - 5)",
+                "1",
+                vec![("x^2 - 3", 1), ("x^2 - 2", 1), ("x^3 - 5", 1)],
+            ),
+            ("6", "6", vec![]),
+        ];
+
+        for (input, content, factors) in cases {
+            let factorization = polynomial(input).factor().unwrap();
+            let expected = Factorization {
+                content: polynomial(content).as_constant().unwrap(),
+                factors: factors
+                    .into_iter()
+                    .map(|(factor, multiplicity)| (polynomial(factor), multiplicity))
+                    .collect(),
+            };
+            assert_eq!(factorization, expected, "{input}");
+
+            let product = factorization.factors.iter().fold(
+                Polynomial::constant(factorization.content.clone()),
+                |product, (factor, multiplicity)| &product * &factor.pow(*multiplicity),
+            );
+            assert_eq!(product, polynomial(input), "{input}");
+        }
+
+        assert!(matches!(
+            polynomial("x*y - 1").factor(),
+            Err(PolynomialError::NotUnivariate(_))
+        ));
+    }
+}
@@ -0,0 +1,138 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// Name of a variable or a function, split into its parts.
+///
+/// `v_0` has the base `v` and the subscript `0`, `x_{max}` the subscript `max` and `f''` two
+/// primes. Identifiers which only differ in the subscript or primes are different names.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+pub struct Identifier {
+    pub base: String,
+    pub subscript: Option<String>,
+    pub primes: usize,
+}
+
+impl Identifier {
+    pub fn new(base: impl Into<String>) -> Self {
+        Identifier {
+            base: base.into(),
+            subscript: None,
+            primes: 0,
+        }
+    }
+
+    pub fn with_subscript(mut self, subscript: impl Into<String>) -> Self {
+        self.subscript = Some(subscript.into());
+        self
+    }
+
+    pub fn with_primes(mut self, primes: usize) -> Self {
+        self.primes = primes;
+        self
+    }
+
+    /// Splits an identifier as written in the input, e.g. `x_{max}''`.
+    ///
+    /// Leading underscores belong to the base, so `_a_b` has the base `_a` and the subscript `b`.
+    pub fn parse(name: &str) -> Self {
+        let without_primes = name.trim_end_matches('\'');
+        let primes = name.len() - without_primes.len();
+
+        let leading_underscores =
+            without_primes.len() - without_primes.trim_start_matches('_').len();
+        let separator = without_primes[leading_underscores..]
+            .find('_')
+            .map(|index| index + leading_underscores);
+
+        let (base, subscript) = match separator {
+            Some(index) => {
+                let subscript = &without_primes[index + 1..];
+                let subscript = subscript
+                    .strip_prefix('{')
+                    .and_then(|subscript| subscript.strip_suffix('}'))
+                    .unwrap_or(subscript);
+
+                if subscript.is_empty() {
+                    (without_primes, None)
+                } else {
+                    (&without_primes[..index], Some(subscript.to_string()))
+                }
+            }
+            None => (without_primes, None),
+        };
+
+        Identifier {
+            base: base.to_string(),
+            subscript,
+            primes,
+        }
+    }
+
+    /// The name if it has no subscript or primes, used to look up built in functions.
+    pub fn as_plain(&self) -> Option<&str> {
+        if self.subscript.is_none() && self.primes == 0 {
+            Some(&self.base)
+        } else {
+            None
+        }
+    }
+}
+
+impl From<&str> for Identifier {
+    fn from(name: &str) -> Self {
+        Identifier::parse(name)
+    }
+}
+
+impl Display for Identifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.base)?;
+
+        if let Some(subscript) = &self.subscript {
+            if subscript.chars().count() == 1 {
+                write!(f, "_{subscript}")?;
+            } else {
+                write!(f, "_{{{subscript}}}")?;
+            }
+        }
+
+        write!(f, "{}", "'".repeat(self.primes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        let cases = [
+            ("x", Identifier::new("x"), "x"),
+            ("v_0", Identifier::new("v").with_subscript("0"), "v_0"),
+            (
+                "x_{max}",
+                Identifier::new("x").with_subscript("max"),
+                "x_{max}",
+            ),
+            (
+                "x_max",
+                Identifier::new("x").with_subscript("max"),
+                "x_{max}",
+            ),
+            ("f'", Identifier::new("f").with_primes(1), "f'"),
+            (
+                "y_1''",
+                Identifier::new("y").with_subscript("1").with_primes(2),
+                "y_1''",
+            ),
+            ("_abc", Identifier::new("_abc"), "_abc"),
+            ("_a_b", Identifier::new("_a").with_subscript("b"), "_a_b"),
+        ];
+
+        for (input, identifier, output) in cases {
+            assert_eq!(Identifier::parse(input), identifier);
+            assert_eq!(identifier.to_string(), output);
+        }
+    }
+}
@@ -0,0 +1,523 @@
+//! The JSON format used to exchange equations and contexts, see [`App::export_json`] and
+//! [`App::import_json`].
+//!
+//! ```json
+//! {
+//!   "version": 2,
+//!   "contexts": [{
+//!     "uuid": "6f0c4a7e-5f4e-4d1c-9a53-2b1f0d1f4c11",
+//!     "equations": [{
+//!       "uuid": "0b8e1c52-9c3e-4a4f-8f0e-8f3b5d6a7c21",
+//!       "sides": [
+//!         { "type": "variable", "name": "x_1" },
+//!         { "type": "sum", "products": [
+//!           { "numerator": [
+//!             { "type": "number", "value": "1/2" },
+//!             { "type": "constant", "name": "pi" }
+//!           ] },
+//!           { "numerator": [{ "type": "variable", "name": "y", "negative": true }] }
+//!         ] }
+//!       ]
+//!     }]
+//!   }]
+//! }
+//! ```
+//!
+//! - numbers are exact, written as `"p/q"` or `"p"`
+//! - identifiers are written the way they are parsed, e.g. `"x_{max}"` or `"f'"`
+//! - constants are written with their plain name, `"pi"`, `"e"`, `"i"` or `"infinity"`
+//! - `negative` is left out for positive elements and `denominator` when it's empty
+//! - caches aren't part of the format, they are recomputed after an import
+//!
+//! Every change to the format increases [`JSON_VERSION`].
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc, str::FromStr};
+
+use num::BigRational;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::{
+    app::App,
+    context::{Context, ElementDefinition},
+    product::Product,
+    Constant, Element, Equation, Expression, Identifier, Node, NodeOrExpression, Sign,
+};
+
+/// The version written by [`App::export_json`]. [`App::import_json`] also accepts version 1,
+/// which had no constants, its variables named like a [`Constant`] are read as one.
+pub const JSON_VERSION: u32 = 2;
+
+#[derive(Debug, Error)]
+pub enum ImportJsonError {
+    #[error("Invalid JSON: {0}")]
+    Json(serde_json::Error),
+    #[error("Unsupported version {0}, expected 1 to {JSON_VERSION}")]
+    UnsupportedVersion(u32),
+    #[error("Invalid number \"{0}\"")]
+    InvalidNumber(String),
+    #[error("Unknown constant \"{0}\"")]
+    UnknownConstant(String),
+    #[error("A context with the uuid {0} already exists")]
+    DuplicateContext(Uuid),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppJson {
+    pub version: u32,
+    pub contexts: Vec<ContextJson>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContextJson {
+    pub uuid: Uuid,
+    pub equations: Vec<EquationJson>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub definitions: HashMap<String, ElementDefinition>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EquationJson {
+    pub uuid: Uuid,
+    pub sides: Vec<ElementJson>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ElementJson {
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub negative: bool,
+    #[serde(flatten)]
+    pub value: ValueJson,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ValueJson {
+    Number {
+        value: String,
+    },
+    Variable {
+        name: String,
+    },
+    Constant {
+        name: String,
+    },
+    Power {
+        base: Box<ElementJson>,
+        power: Box<ElementJson>,
+    },
+    Modulo {
+        lhs: Box<ElementJson>,
+        rhs: Box<ElementJson>,
+    },
+    Factorial {
+        child: Box<ElementJson>,
+    },
+    Function {
+        name: String,
+        arguments: Vec<ElementJson>,
+    },
+    Sum {
+        products: Vec<ProductJson>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProductJson {
+    pub numerator: Vec<ElementJson>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denominator: Vec<ElementJson>,
+}
+
+impl App {
+    /// Writes every context with its equations in the [format](self) described above, sorted by
+    /// their uuids so the output is stable.
+    pub fn export_json(&self) -> String {
+        let mut contexts = self
+            .contexts
+            .values()
+            .map(ContextJson::from)
+            .collect::<Vec<_>>();
+        contexts.sort_by_key(|context| context.uuid);
+
+        let json = AppJson {
+            version: JSON_VERSION,
+            contexts,
+        };
+
+        serde_json::to_string_pretty(&json).expect("the JSON format only has string keys")
+    }
+
+    /// Adds the contexts in `json` to `app`, keeping their uuids, and returns the uuids.
+    ///
+    /// Nothing is added if any part of `json` is invalid.
+    pub fn import_json(app: Rc<RefCell<App>>, json: &str) -> Result<Vec<Uuid>, ImportJsonError> {
+        let json: AppJson = serde_json::from_str(json).map_err(ImportJsonError::Json)?;
+        if !(1..=JSON_VERSION).contains(&json.version) {
+            return Err(ImportJsonError::UnsupportedVersion(json.version));
+        }
+
+        let mut contexts = vec![];
+        for context_json in json.contexts {
+            let uuid = context_json.uuid;
+            let duplicate = app.borrow().contexts.contains_key(&uuid)
+                || contexts
+                    .iter()
+                    .any(|context: &Context| context.uuid == uuid);
+            if duplicate {
+                return Err(ImportJsonError::DuplicateContext(uuid));
+            }
+
+            let mut context = Context::new(Rc::clone(&app));
+            context.uuid = uuid;
+            context.definitions = context_json.definitions;
+
+            for equation_json in context_json.equations {
+                let mut sides = equation_json
+                    .sides
+                    .into_iter()
+                    .map(Element::try_from)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if json.version == 1 {
+                    sides = sides
+                        .into_iter()
+                        .map(|side| {
+                            side.apply_to_every_element_into(
+                                &mut constant_from_variable,
+                                false,
+                                None,
+                            )
+                        })
+                        .collect();
+                }
+
+                let equation = Equation::new(sides, Rc::clone(&app), uuid);
+                context.equations.insert(equation_json.uuid, equation);
+            }
+
+            contexts.push(context);
+        }
+
+        let uuids = contexts.iter().map(|context| context.uuid).collect();
+
+        let mut borrowed_app = app.borrow_mut();
+        for context in contexts {
+            borrowed_app.contexts.insert(context.uuid, context);
+        }
+
+        Ok(uuids)
+    }
+}
+
+impl From<&Context> for ContextJson {
+    fn from(context: &Context) -> Self {
+        let mut equations = context
+            .equations
+            .iter()
+            .map(|(uuid, equation)| EquationJson {
+                uuid: *uuid,
+                sides: equation
+                    .equation_sides
+                    .iter()
+                    .map(ElementJson::from)
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        equations.sort_by_key(|equation| equation.uuid);
+
+        ContextJson {
+            uuid: context.uuid,
+            equations,
+            definitions: context.definitions.clone(),
+        }
+    }
+}
+
+impl From<&Element> for ElementJson {
+    fn from(element: &Element) -> Self {
+        let boxed = |element: &Element| Box::new(ElementJson::from(element));
+
+        let value = match &element.node_or_expression {
+            NodeOrExpression::Node(node) => match node {
+                Node::Number(number) => ValueJson::Number {
+                    value: number.to_string(),
+                },
+                Node::Variable(name) => ValueJson::Variable {
+                    name: name.to_string(),
+                },
+                Node::Constant(constant) => ValueJson::Constant {
+                    name: constant.name().to_string(),
+                },
+                Node::Power { base, power } => ValueJson::Power {
+                    base: boxed(base),
+                    power: boxed(power),
+                },
+                Node::Modulo { lhs, rhs } => ValueJson::Modulo {
+                    lhs: boxed(lhs),
+                    rhs: boxed(rhs),
+                },
+                Node::Factorial { child } => ValueJson::Factorial {
+                    child: boxed(child),
+                },
+                Node::Function { name, arguments } => ValueJson::Function {
+                    name: name.to_string(),
+                    arguments: arguments.iter().map(ElementJson::from).collect(),
+                },
+            },
+            NodeOrExpression::Expression(expression) => ValueJson::Sum {
+                products: expression.products.iter().map(ProductJson::from).collect(),
+            },
+        };
+
+        ElementJson {
+            negative: element.sign == Sign::Negative,
+            value,
+        }
+    }
+}
+
+impl From<&Product> for ProductJson {
+    fn from(product: &Product) -> Self {
+        ProductJson {
+            numerator: product.numerator.iter().map(ElementJson::from).collect(),
+            denominator: product.denominator.iter().map(ElementJson::from).collect(),
+        }
+    }
+}
+
+impl TryFrom<ElementJson> for Element {
+    type Error = ImportJsonError;
+
+    fn try_from(json: ElementJson) -> Result<Self, Self::Error> {
+        let boxed = |json: Box<ElementJson>| Element::try_from(*json).map(Box::new);
+        let elements = |json: Vec<ElementJson>| {
+            json.into_iter()
+                .map(Element::try_from)
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        let node_or_expression = match json.value {
+            ValueJson::Number { value } => NodeOrExpression::Node(Node::Number(
+                BigRational::from_str(&value).map_err(|_| ImportJsonError::InvalidNumber(value))?,
+            )),
+            ValueJson::Variable { name } => {
+                NodeOrExpression::Node(Node::Variable(Identifier::parse(&name)))
+            }
+            ValueJson::Constant { name } => NodeOrExpression::Node(Node::Constant(
+                Constant::from_identifier(&Identifier::new(name.as_str()))
+                    .ok_or(ImportJsonError::UnknownConstant(name))?,
+            )),
+            ValueJson::Power { base, power } => NodeOrExpression::Node(Node::Power {
+                base: boxed(base)?,
+                power: boxed(power)?,
+            }),
+            ValueJson::Modulo { lhs, rhs } => NodeOrExpression::Node(Node::Modulo {
+                lhs: boxed(lhs)?,
+                rhs: boxed(rhs)?,
+            }),
+            ValueJson::Factorial { child } => NodeOrExpression::Node(Node::Factorial {
+                child: boxed(child)?,
+            }),
+            ValueJson::Function { name, arguments } => NodeOrExpression::Node(Node::Function {
+                name: Identifier::parse(&name),
+                arguments: elements(arguments)?,
+            }),
+            ValueJson::Sum { products } => NodeOrExpression::Expression(Expression::new(
+                products
+                    .into_iter()
+                    .map(|product| {
+                        Ok(Product::new(
+                            elements(product.numerator)?,
+                            elements(product.denominator)?,
+                        ))
+                    })
+                    .collect::<Result<_, ImportJsonError>>()?,
+            )),
+        };
+
+        let sign = if json.negative {
+            Sign::Negative
+        } else {
+            Sign::Positive
+        };
+
+        Ok(Element::new(sign, node_or_expression))
+    }
+}
+
+/// Version 1 had no constants, a variable named like a [`Constant`] is read as one.
+fn constant_from_variable(element: Element) -> Element {
+    match &element.node_or_expression {
+        NodeOrExpression::Node(Node::Variable(name)) => match Constant::from_identifier(name) {
+            Some(constant) => Element::new(
+                element.sign,
+                NodeOrExpression::Node(Node::Constant(constant)),
+            ),
+            None => element,
+        },
+        _ => element,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::is_same::{IsSame, IsSameNames};
+
+    fn app_with(equations: &[&str]) -> (Rc<RefCell<App>>, Uuid) {
+        let app = App::new().unwrap();
+        let context = Context::new(Rc::clone(&app));
+        let ctx_uuid = app.borrow_mut().add_context(context);
+
+        for equation in equations {
+            App::try_add_equation(Rc::clone(&app), ctx_uuid, *equation).unwrap();
+        }
+
+        (app, ctx_uuid)
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (app, ctx_uuid) = app_with(&[
+            "x_1 = 1/2 - y + 2pi",
+            "f'(n!) = (a % 3)^-2 + e^i",
+            "-(a + b)/c = 0",
+        ]);
+        let json = app.borrow().export_json();
+
+        let imported = App::new().unwrap();
+        let uuids = App::import_json(Rc::clone(&imported), &json).unwrap();
+        assert_eq!(uuids, vec![ctx_uuid]);
+        assert_eq!(imported.borrow().export_json(), json);
+
+        let borrowed_imported = imported.borrow();
+        let context = borrowed_imported.get_context(ctx_uuid).unwrap();
+        for (uuid, equation) in &app.borrow().get_context(ctx_uuid).unwrap().equations {
+            let imported_equation = context.get_equation(*uuid).unwrap();
+            assert_eq!(imported_equation.context, ctx_uuid);
+            assert!(Rc::ptr_eq(&imported_equation.app, &imported));
+
+            for (left, right) in equation
+                .equation_sides
+                .iter()
+                .zip(&imported_equation.equation_sides)
+            {
+                assert!(Element::is_same(left, right, &mut IsSameNames::new()));
+                assert_eq!(left.to_string(), right.to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let (app, _) = app_with(&["x_{max} = 2 - y"]);
+        let json: serde_json::Value = serde_json::from_str(&app.borrow().export_json()).unwrap();
+
+        assert_eq!(json["version"], 2);
+        let sides = &json["contexts"][0]["equations"][0]["sides"];
+        assert_eq!(
+            sides[0],
+            serde_json::json!({ "type": "variable", "name": "x_{max}" })
+        );
+        assert_eq!(
+            sides[1],
+            serde_json::json!({
+                "type": "sum",
+                "products": [
+                    { "numerator": [{ "type": "number", "value": "2" }] },
+                    { "numerator": [{ "type": "variable", "name": "y", "negative": true }] }
+                ]
+            })
+        );
+
+        let half = Element::number(BigRational::new((-1).into(), 2.into()));
+        assert_eq!(
+            serde_json::to_value(ElementJson::from(&half)).unwrap(),
+            serde_json::json!({ "type": "number", "value": "-1/2" })
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let context = |sides: &str| {
+            format!(
+                r#"{{"version": 1, "contexts": [{{"uuid": "{}", "equations": [{{"uuid": "{}", "sides": {sides}}}]}}]}}"#,
+                Uuid::nil(),
+                Uuid::nil()
+            )
+        };
+
+        let app = App::new().unwrap();
+        let cases = [
+            ("{", "Invalid JSON"),
+            (r#"{"version": 3, "contexts": []}"#, "Unsupported version 3"),
+            (
+                &context(r#"[{"type": "number", "value": "1/0"}]"#),
+                "Invalid number \"1/0\"",
+            ),
+            (
+                &context(r#"[{"type": "number", "value": "x"}]"#),
+                "Invalid number \"x\"",
+            ),
+            (
+                &context(r#"[{"type": "constant", "name": "tau"}]"#),
+                "Unknown constant \"tau\"",
+            ),
+            (&context(r#"[{"type": "vector"}]"#), "Invalid JSON"),
+        ];
+
+        for (json, expected) in cases {
+            let error = App::import_json(Rc::clone(&app), json).unwrap_err();
+            assert!(error.to_string().starts_with(expected), "{json}: {error}");
+        }
+        assert!(app.borrow().contexts.is_empty());
+
+        let valid = context("[]");
+        App::import_json(Rc::clone(&app), &valid).unwrap();
+        assert!(matches!(
+            App::import_json(Rc::clone(&app), &valid),
+            Err(ImportJsonError::DuplicateContext(_))
+        ));
+    }
+
+    #[test]
+    fn test_constant_variables() {
+        let json = |version: u32| {
+            format!(
+                r#"{{"version": {version}, "contexts": [{{"uuid": "{}", "equations": [{{"uuid": "{}", "sides": [{{"type": "variable", "name": "pi"}}, {{"type": "constant", "name": "e"}}]}}]}}]}}"#,
+                Uuid::nil(),
+                Uuid::nil()
+            )
+        };
+        let cases = [
+            (1, Node::Constant(Constant::Pi)),
+            (2, Node::Variable(Identifier::new("pi"))),
+        ];
+
+        for (version, expected) in cases {
+            let app = App::new().unwrap();
+            App::import_json(Rc::clone(&app), &json(version)).unwrap();
+
+            let borrowed_app = app.borrow();
+            let sides = &borrowed_app
+                .get_context(Uuid::nil())
+                .and_then(|context| context.get_equation(Uuid::nil()))
+                .unwrap()
+                .equation_sides;
+            assert_eq!(
+                sides[0].node_or_expression,
+                NodeOrExpression::Node(expected),
+                "version {version}"
+            );
+            assert_eq!(
+                sides[1].node_or_expression,
+                NodeOrExpression::Node(Node::Constant(Constant::E))
+            );
+        }
+    }
+}
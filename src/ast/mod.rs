@@ -1,15 +1,26 @@
 pub mod app;
+pub mod arena;
+pub mod complex;
+pub mod constant;
 pub mod context;
 pub mod element;
 pub mod equation;
 pub mod expression;
+pub mod factorization;
+pub mod identifier;
+pub mod json;
 pub mod node;
+pub mod polynomial;
 pub mod product;
+pub mod rational_function;
+pub mod real_roots;
 pub mod token_to_element;
 
 pub use {
+    constant::Constant,
     element::{Element, NodeOrExpression, Sign},
     equation::Equation,
     expression::Expression,
+    identifier::Identifier,
     node::Node,
 };
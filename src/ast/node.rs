@@ -1,14 +1,17 @@
 use serde::{Deserialize, Serialize};
 
 use super::{
+    constant::Constant,
     element::{IsTimesVisible, NodeOrExpression, ShouldBeParenthesized},
+    identifier::Identifier,
     Element,
 };
 
-#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub enum Node {
     Number(num::BigRational),
-    Variable(String),
+    Variable(Identifier),
+    Constant(Constant),
     Power {
         base: Box<Element>,
         power: Box<Element>,
@@ -21,7 +24,7 @@ pub enum Node {
         child: Box<Element>,
     },
     Function {
-        name: String,
+        name: Identifier,
         arguments: Vec<Element>,
     },
 }
@@ -40,10 +43,11 @@ impl IsTimesVisible for Node {
             | Node::Function { .. }
             | Node::Modulo { .. }
             | Node::Factorial { .. } => true,
-            Node::Variable(_) => match &last.node_or_expression {
-                NodeOrExpression::Node(var_node) => {
-                    !matches!(var_node, Node::Number(_) | Node::Variable(_))
-                }
+            Node::Variable(_) | Node::Constant(_) => match &last.node_or_expression {
+                NodeOrExpression::Node(var_node) => !matches!(
+                    var_node,
+                    Node::Number(_) | Node::Variable(_) | Node::Constant(_)
+                ),
                 NodeOrExpression::Expression(_) => false,
             },
         }
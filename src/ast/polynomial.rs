@@ -0,0 +1,699 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+use num::{BigRational, One, Signed, ToPrimitive, Zero};
+use thiserror::Error;
+
+use crate::actions::evaluate::{Bindings, EvalError};
+
+use super::{product::Product, Element, Expression, Identifier, Node, NodeOrExpression, Sign};
+
+/// Powers of polynomials above this aren't expanded.
+pub(crate) const MAX_DEGREE: u32 = 64;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum PolynomialError {
+    #[error("{0} isn't a polynomial with rational coefficients")]
+    NotPolynomial(String),
+    #[error("The power {0} is too large to be expanded")]
+    TooLarge(String),
+    #[error("Division by zero")]
+    DivisionByZero,
+    #[error("{0} has more than one variable")]
+    NotUnivariate(String),
+}
+
+/// The exponents of the variables in a term, variables with the exponent zero are left out.
+pub type Monomial = BTreeMap<Identifier, u32>;
+
+/// Compares monomials in the lexicographic order, the variable which comes first by name counts
+/// the most, so `x^2 > x*y > x > y^3 > 1`.
+pub fn lex_cmp(lhs: &Monomial, rhs: &Monomial) -> Ordering {
+    let variables = lhs.keys().chain(rhs.keys()).collect::<BTreeSet<_>>();
+
+    variables
+        .into_iter()
+        .map(|variable| {
+            let exponent = |monomial: &Monomial| monomial.get(variable).copied().unwrap_or(0);
+            exponent(lhs).cmp(&exponent(rhs))
+        })
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// A sparse polynomial in any number of named variables with rational coefficients, like
+/// `x^2*y - 1/2*z + 3`. Only the terms with a non-zero coefficient are stored.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Polynomial {
+    terms: BTreeMap<Monomial, BigRational>,
+}
+
+impl Polynomial {
+    pub fn zero() -> Polynomial {
+        Polynomial::default()
+    }
+
+    pub fn one() -> Polynomial {
+        Polynomial::constant(BigRational::one())
+    }
+
+    pub fn constant(value: BigRational) -> Polynomial {
+        Polynomial::term(Monomial::new(), value)
+    }
+
+    pub fn variable(name: Identifier) -> Polynomial {
+        Polynomial::term(Monomial::from([(name, 1)]), BigRational::one())
+    }
+
+    /// The polynomial `coefficient*monomial`.
+    pub fn term(monomial: Monomial, coefficient: BigRational) -> Polynomial {
+        let mut polynomial = Polynomial::zero();
+        polynomial.add_term(monomial, coefficient);
+        polynomial
+    }
+
+    fn add_term(&mut self, monomial: Monomial, coefficient: BigRational) {
+        let monomial = monomial
+            .into_iter()
+            .filter(|(_, exponent)| *exponent != 0)
+            .collect::<Monomial>();
+
+        let sum = self.terms.remove(&monomial).unwrap_or_default() + coefficient;
+        if !sum.is_zero() {
+            self.terms.insert(monomial, sum);
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.as_constant().is_some_and(|value| value.is_one())
+    }
+
+    /// The value of the polynomial if it doesn't contain any variables.
+    pub fn as_constant(&self) -> Option<BigRational> {
+        match self.terms.iter().next() {
+            None => Some(BigRational::zero()),
+            Some((monomial, coefficient)) if self.terms.len() == 1 && monomial.is_empty() => {
+                Some(coefficient.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// The terms with their coefficients, in no particular order.
+    pub fn terms(&self) -> impl Iterator<Item = (&Monomial, &BigRational)> {
+        self.terms.iter()
+    }
+
+    pub fn variables(&self) -> BTreeSet<Identifier> {
+        self.terms
+            .keys()
+            .flat_map(|monomial| monomial.keys().cloned())
+            .collect()
+    }
+
+    /// The total degree, the highest sum of the exponents of a term. The zero polynomial has no
+    /// degree.
+    pub fn degree(&self) -> Option<u32> {
+        self.terms
+            .keys()
+            .map(|monomial| monomial.values().sum())
+            .max()
+    }
+
+    /// The highest exponent of `variable`.
+    pub fn degree_in(&self, variable: &Identifier) -> Option<u32> {
+        self.terms
+            .keys()
+            .map(|monomial| monomial.get(variable).copied().unwrap_or(0))
+            .max()
+    }
+
+    /// The highest term in the lexicographic order, see [`lex_cmp`].
+    pub fn leading_term(&self) -> Option<(&Monomial, &BigRational)> {
+        self.terms
+            .iter()
+            .max_by(|(lhs, _), (rhs, _)| lex_cmp(lhs, rhs))
+    }
+
+    /// The coefficient of the leading term, zero for the zero polynomial.
+    pub fn leading_coefficient(&self) -> BigRational {
+        self.leading_term()
+            .map(|(_, coefficient)| coefficient.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn pow(&self, exponent: u32) -> Polynomial {
+        (0..exponent).fold(Polynomial::one(), |power, _| &power * self)
+    }
+
+    /// Divides by `divisor`, so that `self = quotient*divisor + remainder` where no term of the
+    /// remainder is divisible by the leading term of the divisor. With a single variable, this is
+    /// the usual long division.
+    pub fn div_rem(
+        &self,
+        divisor: &Polynomial,
+    ) -> Result<(Polynomial, Polynomial), PolynomialError> {
+        let (divisor_monomial, divisor_coefficient) = divisor
+            .leading_term()
+            .ok_or(PolynomialError::DivisionByZero)?;
+
+        let mut quotient = Polynomial::zero();
+        let mut remainder = Polynomial::zero();
+        let mut rest = self.clone();
+
+        while let Some((monomial, coefficient)) = rest.leading_term() {
+            let term = divide_monomial(monomial, divisor_monomial)
+                .map(|monomial| Polynomial::term(monomial, coefficient / divisor_coefficient));
+
+            match term {
+                Some(term) => {
+                    rest = &rest - &(&term * divisor);
+                    quotient = &quotient + &term;
+                }
+                None => {
+                    let term = Polynomial::term(monomial.clone(), coefficient.clone());
+                    rest = &rest - &term;
+                    remainder = &remainder + &term;
+                }
+            }
+        }
+
+        Ok((quotient, remainder))
+    }
+
+    /// The polynomial times the number `factor`.
+    pub fn scale(&self, factor: &BigRational) -> Polynomial {
+        self * &Polynomial::constant(factor.clone())
+    }
+
+    /// The polynomial divided by its leading coefficient, the zero polynomial stays zero.
+    pub fn monic(&self) -> Polynomial {
+        match self.leading_term() {
+            Some((_, coefficient)) => self.scale(&coefficient.recip()),
+            None => Polynomial::zero(),
+        }
+    }
+
+    /// The coefficients of the powers of `variable`, which are polynomials in the other
+    /// variables. `x^2*y + x^2 + y` has the coefficient `y + 1` for `x^2` and `y` for `1`.
+    pub fn coefficients_in(&self, variable: &Identifier) -> BTreeMap<u32, Polynomial> {
+        let mut coefficients = BTreeMap::<u32, Polynomial>::new();
+
+        for (monomial, coefficient) in &self.terms {
+            let mut monomial = monomial.clone();
+            let exponent = monomial.remove(variable).unwrap_or(0);
+            coefficients
+                .entry(exponent)
+                .or_default()
+                .add_term(monomial, coefficient.clone());
+        }
+
+        coefficients
+    }
+
+    /// The derivative with respect to `variable`.
+    pub fn derivative(&self, variable: &Identifier) -> Polynomial {
+        let mut derivative = Polynomial::zero();
+
+        for (monomial, coefficient) in &self.terms {
+            let Some(exponent) = monomial.get(variable).copied() else {
+                continue;
+            };
+            let mut monomial = monomial.clone();
+            match exponent {
+                1 => monomial.remove(variable),
+                _ => monomial.insert(variable.clone(), exponent - 1),
+            };
+            derivative.add_term(
+                monomial,
+                coefficient * BigRational::from_integer(exponent.into()),
+            );
+        }
+
+        derivative
+    }
+
+    /// The greatest common divisor, normalized to a leading coefficient of `1`. Two numbers
+    /// other than zero have the divisor `1`.
+    ///
+    /// Both polynomials are seen as polynomials in their first variable with coefficients in the
+    /// others, the contents are handled recursively and the primitive parts with a pseudo
+    /// remainder sequence.
+    pub fn gcd(&self, other: &Polynomial) -> Polynomial {
+        if self.is_zero() {
+            return other.monic();
+        }
+        if other.is_zero() {
+            return self.monic();
+        }
+
+        let variables = &self.variables() | &other.variables();
+        let Some(variable) = variables.first() else {
+            return Polynomial::one();
+        };
+
+        let content = self.content_in(variable).gcd(&other.content_in(variable));
+        let (mut lhs, mut rhs) = (
+            self.primitive_part_in(variable),
+            other.primitive_part_in(variable),
+        );
+        if lhs.degree_in(variable) < rhs.degree_in(variable) {
+            std::mem::swap(&mut lhs, &mut rhs);
+        }
+
+        while !rhs.is_zero() {
+            let remainder = lhs.pseudo_remainder(&rhs, variable);
+            lhs = rhs;
+            rhs = remainder.primitive_part_in(variable);
+        }
+
+        (&content * &lhs).monic()
+    }
+
+    /// The greatest common divisor of the coefficients in `variable`.
+    fn content_in(&self, variable: &Identifier) -> Polynomial {
+        self.coefficients_in(variable)
+            .values()
+            .fold(Polynomial::zero(), |content, coefficient| {
+                content.gcd(coefficient)
+            })
+    }
+
+    /// The polynomial divided by its content in `variable`, with a leading coefficient of `1`.
+    fn primitive_part_in(&self, variable: &Identifier) -> Polynomial {
+        if self.is_zero() {
+            return Polynomial::zero();
+        }
+
+        let (quotient, _) = self
+            .div_rem(&self.content_in(variable))
+            .expect("the content of a non-zero polynomial isn't zero");
+        quotient.monic()
+    }
+
+    /// The remainder of `lc^k*self` divided by `divisor` as polynomials in `variable`, where
+    /// `lc` is the leading coefficient of the divisor. Unlike [`Polynomial::div_rem`], it never
+    /// has to divide the coefficients.
+    fn pseudo_remainder(&self, divisor: &Polynomial, variable: &Identifier) -> Polynomial {
+        let mut coefficients = divisor.coefficients_in(variable);
+        let Some((degree, leading)) = coefficients.pop_last() else {
+            return self.clone();
+        };
+
+        let mut remainder = self.clone();
+        while let Some((remainder_degree, remainder_leading)) = remainder
+            .coefficients_in(variable)
+            .pop_last()
+            .filter(|(remainder_degree, _)| *remainder_degree >= degree)
+        {
+            let shift = Polynomial::term(
+                Monomial::from([(variable.clone(), remainder_degree - degree)]),
+                BigRational::one(),
+            );
+            remainder = &(&leading * &remainder) - &(&(&remainder_leading * &shift) * divisor);
+        }
+
+        remainder
+    }
+
+    pub fn evaluate(&self, bindings: &Bindings) -> Result<BigRational, EvalError> {
+        self.terms
+            .iter()
+            .try_fold(BigRational::zero(), |sum, (monomial, coefficient)| {
+                let value = monomial.iter().try_fold(
+                    coefficient.clone(),
+                    |product, (variable, exponent)| {
+                        let value = bindings
+                            .get(variable)
+                            .ok_or_else(|| EvalError::UnboundVariable(variable.clone()))?;
+                        Ok(product * num::pow(value.clone(), *exponent as usize))
+                    },
+                )?;
+
+                Ok(sum + value)
+            })
+    }
+
+    /// Reads a polynomial from an element made of numbers, variables, sums, products, divisions
+    /// by numbers and integer powers. Parts which evaluate to a rational number count as numbers,
+    /// like `sqrt(4)`.
+    pub fn from_element(element: &Element) -> Result<Polynomial, PolynomialError> {
+        if let Ok(value) = element.evaluate(&Bindings::new()) {
+            return Ok(Polynomial::constant(value));
+        }
+
+        let polynomial = match &element.node_or_expression {
+            NodeOrExpression::Node(node) => Polynomial::from_node(node)?,
+            NodeOrExpression::Expression(expression) => Polynomial::from_expression(expression)?,
+        };
+
+        Ok(match element.sign {
+            Sign::Positive => polynomial,
+            Sign::Negative => -polynomial,
+        })
+    }
+
+    fn from_node(node: &Node) -> Result<Polynomial, PolynomialError> {
+        match node {
+            Node::Number(number) => Ok(Polynomial::constant(number.clone())),
+            Node::Variable(name) => Ok(Polynomial::variable(name.clone())),
+            Node::Power { base, power } => {
+                let base = Polynomial::from_element(base)?;
+                let exponent = power
+                    .evaluate(&Bindings::new())
+                    .ok()
+                    .filter(|exponent| exponent.is_integer() && !exponent.is_negative())
+                    .ok_or_else(|| PolynomialError::NotPolynomial(node.to_string()))?;
+                let exponent = exponent
+                    .to_integer()
+                    .to_u32()
+                    .filter(|exponent| *exponent <= MAX_DEGREE)
+                    .ok_or_else(|| PolynomialError::TooLarge(node.to_string()))?;
+
+                Ok(base.pow(exponent))
+            }
+            _ => Err(PolynomialError::NotPolynomial(node.to_string())),
+        }
+    }
+
+    fn from_expression(expression: &Expression) -> Result<Polynomial, PolynomialError> {
+        let mut sum = Polynomial::zero();
+
+        for product in &expression.products {
+            let mut value = Polynomial::one();
+            for element in &product.numerator {
+                value = &value * &Polynomial::from_element(element)?;
+            }
+            for element in &product.denominator {
+                let divisor = Polynomial::from_element(element)?
+                    .as_constant()
+                    .ok_or_else(|| PolynomialError::NotPolynomial(product.to_string()))?;
+                if divisor.is_zero() {
+                    return Err(PolynomialError::DivisionByZero);
+                }
+                value = &value * &Polynomial::constant(divisor.recip());
+            }
+
+            sum = &sum + &value;
+        }
+
+        Ok(sum)
+    }
+
+    /// Writes the polynomial as an element, with the terms in descending lexicographic order.
+    pub fn to_element(&self) -> Element {
+        let mut terms = self.terms.iter().collect::<Vec<_>>();
+        terms.sort_by(|(lhs, _), (rhs, _)| lex_cmp(rhs, lhs));
+
+        let mut products = terms
+            .into_iter()
+            .enumerate()
+            .map(|(position, (monomial, coefficient))| {
+                let mut numerator = monomial
+                    .iter()
+                    .map(|(variable, exponent)| {
+                        let variable = Element::new(
+                            Sign::Positive,
+                            NodeOrExpression::Node(Node::Variable(variable.clone())),
+                        );
+                        match exponent {
+                            1 => variable,
+                            _ => Element::new(
+                                Sign::Positive,
+                                NodeOrExpression::Node(Node::Power {
+                                    base: Box::new(variable),
+                                    power: Box::new(Element::number(BigRational::from_integer(
+                                        (*exponent).into(),
+                                    ))),
+                                }),
+                            ),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                if let Some(first) = numerator.first_mut().filter(|_| (-coefficient).is_one()) {
+                    first.invert_sign();
+                } else if position > 0 && coefficient.is_negative() {
+                    // later terms are subtracted, so that sums read `x - 2y` instead of
+                    // `x + -2*y`
+                    numerator.insert(0, Element::number(coefficient.abs()));
+                    let term = match numerator.len() {
+                        1 => numerator.remove(0),
+                        _ => Element::new(
+                            Sign::Positive,
+                            NodeOrExpression::Expression(Expression::new(vec![Product::new(
+                                numerator,
+                                vec![],
+                            )])),
+                        ),
+                    };
+                    numerator = vec![term.simple_neg()];
+                } else if numerator.is_empty() || !coefficient.is_one() {
+                    numerator.insert(0, Element::number(coefficient.clone()));
+                }
+
+                Product::new(numerator, vec![])
+            })
+            .collect::<Vec<_>>();
+
+        match products.as_mut_slice() {
+            [] => Element::number(BigRational::zero()),
+            [product] if product.numerator.len() == 1 => product.numerator.remove(0),
+            _ => Element::new(
+                Sign::Positive,
+                NodeOrExpression::Expression(Expression::new(products)),
+            ),
+        }
+    }
+}
+
+/// `lhs/rhs` if every exponent of `rhs` is at most the one of `lhs`.
+fn divide_monomial(lhs: &Monomial, rhs: &Monomial) -> Option<Monomial> {
+    let mut result = lhs.clone();
+
+    for (variable, exponent) in rhs {
+        let remaining = result
+            .get(variable)
+            .copied()
+            .unwrap_or(0)
+            .checked_sub(*exponent)?;
+        result.insert(variable.clone(), remaining);
+    }
+
+    result.retain(|_, exponent| *exponent != 0);
+    Some(result)
+}
+
+impl Display for Polynomial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_element())
+    }
+}
+
+impl TryFrom<&Element> for Polynomial {
+    type Error = PolynomialError;
+
+    fn try_from(element: &Element) -> Result<Self, Self::Error> {
+        Polynomial::from_element(element)
+    }
+}
+
+impl From<&Polynomial> for Element {
+    fn from(polynomial: &Polynomial) -> Self {
+        polynomial.to_element()
+    }
+}
+
+impl Add for &Polynomial {
+    type Output = Polynomial;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        let mut sum = self.clone();
+        for (monomial, coefficient) in &rhs.terms {
+            sum.add_term(monomial.clone(), coefficient.clone());
+        }
+        sum
+    }
+}
+
+impl Sub for &Polynomial {
+    type Output = Polynomial;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        let mut difference = self.clone();
+        for (monomial, coefficient) in &rhs.terms {
+            difference.add_term(monomial.clone(), -coefficient.clone());
+        }
+        difference
+    }
+}
+
+impl Mul for &Polynomial {
+    type Output = Polynomial;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        let mut product = Polynomial::zero();
+
+        for (lhs_monomial, lhs_coefficient) in &self.terms {
+            for (rhs_monomial, rhs_coefficient) in &rhs.terms {
+                let mut monomial = lhs_monomial.clone();
+                for (variable, exponent) in rhs_monomial {
+                    *monomial.entry(variable.clone()).or_default() += exponent;
+                }
+                product.add_term(monomial, lhs_coefficient * rhs_coefficient);
+            }
+        }
+
+        product
+    }
+}
+
+impl Neg for Polynomial {
+    type Output = Polynomial;
+
+    fn neg(mut self) -> Self::Output {
+        for coefficient in self.terms.values_mut() {
+            *coefficient = -coefficient.clone();
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{parse, polynomial};
+
+    fn rational(numerator: i64, denominator: i64) -> BigRational {
+        BigRational::new(numerator.into(), denominator.into())
+    }
+
+    #[test]
+    fn test_from_element() {
+        let cases = [
+            ("(x + 1)^2", "x^2 + 2x + 1"),
+            ("(x - y)*(x + y)", "x^2 - y^2"),
+            ("x/2 - 3/4*y*x + sqrt(4)", "-3/4*x*y + 1/2*x + 2"),
+            ("x*y^2*z - z*y^2*x", "0"),
+            ("-(a - 1)", "-a + 1"),
+            ("2^3*x", "8x"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(polynomial(input).to_string(), expected, "{input}");
+        }
+
+        for input in ["1/x", "sin(x)", "x^y", "x^(1/2)", "pi*x"] {
+            assert!(
+                matches!(
+                    Polynomial::from_element(&parse(input)),
+                    Err(PolynomialError::NotPolynomial(_))
+                ),
+                "{input}"
+            );
+        }
+        assert!(matches!(
+            Polynomial::from_element(&parse("(x + 1)^100")),
+            Err(PolynomialError::TooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let x = polynomial("x");
+        let y = polynomial("y");
+
+        assert_eq!(&(&x + &y) * &(&x - &y), polynomial("x^2 - y^2"));
+        assert_eq!((&x + &y).pow(3), polynomial("x^3 + 3x^2*y + 3x*y^2 + y^3"));
+        assert_eq!(&x - &x, Polynomial::zero());
+        assert_eq!(-polynomial("x - 1"), polynomial("1 - x"));
+    }
+
+    #[test]
+    fn test_degree() {
+        let p = polynomial("3x^2*y + 2y^4 - x + 5");
+        let x = Identifier::new("x");
+
+        assert_eq!(p.degree(), Some(4));
+        assert_eq!(p.degree_in(&x), Some(2));
+        assert_eq!(p.leading_coefficient(), rational(3, 1));
+        assert_eq!(p.variables(), BTreeSet::from([x, Identifier::new("y")]));
+        assert_eq!(Polynomial::zero().degree(), None);
+        assert_eq!(polynomial("7").as_constant(), Some(rational(7, 1)));
+    }
+
+    #[test]
+    fn test_div_rem() {
+        let cases = [
+            ("x^3 - 1", "x - 1", "x^2 + x + 1", "0"),
+            ("x^2 + 1", "2x", "1/2x", "1"),
+            ("x^2*y + x*y^2 + y^2", "x*y - 1", "x + y", "x + y^2 + y"),
+            ("3", "x", "0", "3"),
+        ];
+
+        for (dividend, divisor, quotient, remainder) in cases {
+            let (dividend, divisor) = (polynomial(dividend), polynomial(divisor));
+            let result = dividend.div_rem(&divisor).unwrap();
+            assert_eq!(result, (polynomial(quotient), polynomial(remainder)));
+            assert_eq!(&(&result.0 * &divisor) + &result.1, dividend);
+        }
+
+        assert_eq!(
+            polynomial("x").div_rem(&Polynomial::zero()),
+            Err(PolynomialError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn test_gcd() {
+        let cases = [
+            ("x^2 - 1", "x^2 - 2x + 1", "x - 1"),
+            ("2x + 2", "4", "1"),
+            ("6x^2 + 12x + 6", "3x + 3", "x + 1"),
+            ("x^2*y - y", "x*y^2 + y^2", "x*y + y"),
+            ("(x + y)^2*(x - z)", "(x + y)*(x - z)^3", "(x + y)*(x - z)"),
+            ("x^3 + y^3", "x + y + 1", "1"),
+            ("x^2 + 1", "0", "x^2 + 1"),
+            ("(a*b + 1)*(a - b)", "(a*b + 1)*(a + b)", "a*b + 1"),
+        ];
+
+        for (lhs, rhs, expected) in cases {
+            let expected = polynomial(expected).monic();
+            assert_eq!(
+                polynomial(lhs).gcd(&polynomial(rhs)),
+                expected,
+                "{lhs}, {rhs}"
+            );
+            assert_eq!(
+                polynomial(rhs).gcd(&polynomial(lhs)),
+                expected,
+                "{rhs}, {lhs}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let bindings = Bindings::from([
+            (Identifier::new("x"), rational(1, 2)),
+            (Identifier::new("y"), rational(-3, 1)),
+        ]);
+
+        assert_eq!(
+            polynomial("4x^2*y + y - x").evaluate(&bindings),
+            Ok(rational(-13, 2))
+        );
+        assert_eq!(
+            polynomial("z").evaluate(&bindings),
+            Err(EvalError::UnboundVariable(Identifier::new("z")))
+        );
+    }
+}
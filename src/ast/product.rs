@@ -1,11 +1,13 @@
+use num::{One, Signed, Zero};
 use serde::{Deserialize, Serialize};
 
 use super::{
+    complex::GaussianRational,
     element::{IsTimesVisible, ShouldBeParenthesized},
-    Element, Node, NodeOrExpression, Sign,
+    Constant, Element, Node, NodeOrExpression, Sign,
 };
 
-#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Serialize, Deserialize)]
 pub struct Product {
     pub numerator: Vec<Element>,
     pub denominator: Vec<Element>,
@@ -41,66 +43,63 @@ impl Product {
         sign
     }
 
+    /// Multiplies the numbers of the product into one coefficient in front of the other factors,
+    /// `numerator / denominator` for rationals. Factors with Gaussian rational values like `i` or
+    /// `(1 + 2i)` are multiplied in too, `i·i` becomes `-1`.
     pub fn rationalize(&mut self) -> Self {
         let mut new_product = Product::new(vec![], vec![]);
-
-        let mut new_number = num::BigRational::new(
-            num::BigInt::new(num::bigint::Sign::Plus, vec![1]),
-            num::BigInt::new(num::bigint::Sign::Plus, vec![1]),
-        );
-
-        let mut sign = Sign::Positive;
+        let mut coefficient = GaussianRational::one();
 
         for (side_pos, side) in [&self.numerator, &self.denominator].into_iter().enumerate() {
             for element in side {
-                sign = sign * element.sign;
-
-                let add_element = match &element.node_or_expression {
-                    NodeOrExpression::Node(node) => match node {
-                        Node::Number(number) => {
-                            if side_pos == 0 {
-                                new_number *= number;
-                            } else {
-                                new_number /= number;
-                            }
-                            false
+                match element.as_gaussian() {
+                    Some(value) if side_pos == 0 => coefficient *= value,
+                    // a division by zero is kept for the evaluation to report
+                    Some(value) if !value.is_zero() => coefficient /= value,
+                    _ => {
+                        if side_pos == 0 {
+                            new_product.numerator.push(element.clone());
+                        } else {
+                            new_product.denominator.push(element.clone());
                         }
-                        _ => true,
-                    },
-                    NodeOrExpression::Expression(_) => true,
-                };
-
-                if add_element {
-                    if side_pos == 0 {
-                        new_product.numerator.push(element.clone());
-                    } else {
-                        new_product.denominator.push(element.clone());
                     }
                 }
             }
         }
 
-        new_product.numerator.insert(
-            0,
-            Element::new(
-                sign,
-                NodeOrExpression::Node(Node::Number(num::BigRational::new(
-                    new_number.numer().clone(),
-                    num::BigInt::new(num::bigint::Sign::Plus, vec![1]),
-                ))),
-            ),
-        );
-
-        new_product.denominator.insert(
-            0,
-            Element::new(
+        // the sign goes on the element, `x - 1` would be printed as `x + -1` otherwise
+        let number = |value: &num::BigInt| {
+            let sign = if value.is_negative() {
+                Sign::Negative
+            } else {
+                Sign::Positive
+            };
+            Element::number(num::BigRational::from_integer(value.abs())).simple_mul_sign(sign)
+        };
+
+        let (numerator, denominator) = if coefficient.im.is_zero() {
+            (
+                vec![number(coefficient.re.numer())],
+                number(coefficient.re.denom()),
+            )
+        } else if coefficient.re.is_zero() {
+            let i = Element::new(
                 Sign::Positive,
-                NodeOrExpression::Node(Node::Number(num::BigRational::new(
-                    new_number.denom().clone(),
-                    num::BigInt::new(num::bigint::Sign::Plus, vec![1]),
-                ))),
-            ),
-        );
+                NodeOrExpression::Node(Node::Constant(Constant::I)),
+            );
+            (
+                vec![number(coefficient.im.numer()), i],
+                number(coefficient.im.denom()),
+            )
+        } else {
+            (
+                vec![Element::from_gaussian(&coefficient)],
+                number(&num::BigInt::one()),
+            )
+        };
+
+        new_product.numerator.splice(0..0, numerator);
+        new_product.denominator.insert(0, denominator);
 
         new_product
     }
@@ -0,0 +1,238 @@
+use std::{
+    fmt::Display,
+    ops::{Add, Mul},
+};
+
+use num::{Signed, ToPrimitive, Zero};
+
+use crate::actions::evaluate::Bindings;
+
+use super::{
+    polynomial::{Polynomial, PolynomialError, MAX_DEGREE},
+    Element, Expression, Node, NodeOrExpression, Sign,
+};
+
+/// A quotient of two polynomials, the denominator is never zero and has the leading coefficient
+/// `1`. Common factors are only removed by [`RationalFunction::cancel`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RationalFunction {
+    pub numerator: Polynomial,
+    pub denominator: Polynomial,
+}
+
+impl RationalFunction {
+    pub fn new(
+        numerator: Polynomial,
+        denominator: Polynomial,
+    ) -> Result<RationalFunction, PolynomialError> {
+        let leading = denominator.leading_coefficient();
+        if leading.is_zero() {
+            return Err(PolynomialError::DivisionByZero);
+        }
+
+        Ok(RationalFunction {
+            numerator: numerator.scale(&leading.recip()),
+            denominator: denominator.scale(&leading.recip()),
+        })
+    }
+
+    pub fn from_polynomial(polynomial: Polynomial) -> RationalFunction {
+        RationalFunction {
+            numerator: polynomial,
+            denominator: Polynomial::one(),
+        }
+    }
+
+    /// Reads an element made of numbers, variables, sums, products, quotients and integer
+    /// powers. Sums of fractions are put over their least common denominator.
+    pub fn from_element(element: &Element) -> Result<RationalFunction, PolynomialError> {
+        if let Ok(value) = element.evaluate(&Bindings::new()) {
+            return Ok(RationalFunction::from_polynomial(Polynomial::constant(
+                value,
+            )));
+        }
+
+        let function = match &element.node_or_expression {
+            NodeOrExpression::Node(node) => RationalFunction::from_node(node)?,
+            NodeOrExpression::Expression(expression) => {
+                RationalFunction::from_expression(expression)?
+            }
+        };
+
+        Ok(match element.sign {
+            Sign::Positive => function,
+            Sign::Negative => RationalFunction {
+                numerator: -function.numerator,
+                denominator: function.denominator,
+            },
+        })
+    }
+
+    fn from_node(node: &Node) -> Result<RationalFunction, PolynomialError> {
+        match node {
+            Node::Power { base, power } => {
+                let base = RationalFunction::from_element(base)?;
+                let exponent = power
+                    .evaluate(&Bindings::new())
+                    .ok()
+                    .filter(|exponent| exponent.is_integer())
+                    .ok_or_else(|| PolynomialError::NotPolynomial(node.to_string()))?;
+                let magnitude = exponent
+                    .abs()
+                    .to_integer()
+                    .to_u32()
+                    .filter(|magnitude| *magnitude <= MAX_DEGREE)
+                    .ok_or_else(|| PolynomialError::TooLarge(node.to_string()))?;
+
+                let power = RationalFunction {
+                    numerator: base.numerator.pow(magnitude),
+                    denominator: base.denominator.pow(magnitude),
+                };
+                if exponent.is_negative() {
+                    RationalFunction::new(power.denominator, power.numerator)
+                } else {
+                    Ok(power)
+                }
+            }
+            _ => Ok(RationalFunction::from_polynomial(Polynomial::from_element(
+                &Element::new(Sign::Positive, NodeOrExpression::Node(node.clone())),
+            )?)),
+        }
+    }
+
+    fn from_expression(expression: &Expression) -> Result<RationalFunction, PolynomialError> {
+        let mut sum = RationalFunction::from_polynomial(Polynomial::zero());
+
+        for product in &expression.products {
+            let mut value = RationalFunction::from_polynomial(Polynomial::one());
+            for element in &product.numerator {
+                value = &value * &RationalFunction::from_element(element)?;
+            }
+            for element in &product.denominator {
+                value = value.checked_div(&RationalFunction::from_element(element)?)?;
+            }
+
+            sum = &sum + &value;
+        }
+
+        Ok(sum)
+    }
+
+    /// `self/other`, unless `other` is zero.
+    pub fn checked_div(
+        &self,
+        other: &RationalFunction,
+    ) -> Result<RationalFunction, PolynomialError> {
+        RationalFunction::new(
+            &self.numerator * &other.denominator,
+            &self.denominator * &other.numerator,
+        )
+    }
+
+    /// Removes the common factors of the numerator and the denominator, `(x^2 - 1)/(x - 1)`
+    /// becomes `x + 1`.
+    pub fn cancel(&self) -> RationalFunction {
+        let divisor = self.numerator.gcd(&self.denominator);
+        if divisor.degree().unwrap_or(0) == 0 {
+            return self.clone();
+        }
+
+        let exact = |polynomial: &Polynomial| polynomial.div_rem(&divisor).unwrap().0;
+        RationalFunction::new(exact(&self.numerator), exact(&self.denominator))
+            .expect("the denominator is a non-zero multiple of the divisor")
+    }
+
+    /// Writes the function as `numerator/denominator`, or as a polynomial if the denominator is
+    /// `1`.
+    pub fn to_element(&self) -> Element {
+        if self.denominator.is_one() {
+            return self.numerator.to_element();
+        }
+
+        Element::simple_div(self.numerator.to_element(), self.denominator.to_element())
+    }
+}
+
+impl Add for &RationalFunction {
+    type Output = RationalFunction;
+
+    /// `a/b + c/d` over the least common denominator of `b` and `d`.
+    fn add(self, rhs: Self) -> Self::Output {
+        let divisor = self.denominator.gcd(&rhs.denominator);
+        let exact = |polynomial: &Polynomial| polynomial.div_rem(&divisor).unwrap().0;
+        let (lhs_factor, rhs_factor) = (exact(&rhs.denominator), exact(&self.denominator));
+
+        RationalFunction {
+            numerator: &(&self.numerator * &lhs_factor) + &(&rhs.numerator * &rhs_factor),
+            denominator: &self.denominator * &lhs_factor,
+        }
+    }
+}
+
+impl Mul for &RationalFunction {
+    type Output = RationalFunction;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        RationalFunction {
+            numerator: &self.numerator * &rhs.numerator,
+            denominator: &self.denominator * &rhs.denominator,
+        }
+    }
+}
+
+impl Display for RationalFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_element())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::parse;
+
+    fn function(input: &str) -> Result<RationalFunction, PolynomialError> {
+        RationalFunction::from_element(&parse(input))
+    }
+
+    #[test]
+    fn test_from_element() {
+        let cases = [
+            ("1/x + 1/y", "(x + y)/(x*y)"),
+            ("1/(x - 1) - 1/(x + 1)", "2/(x^2 - 1)"),
+            ("x/(2y)", "1/2*x/y"),
+            ("(x/y)^-2", "y^2/x^2"),
+            ("1/(x^2 - 1) + 1/(x - 1)", "(x + 2)/(x^2 - 1)"),
+            ("(x^2 - 1)/(x - 1)", "(x^2 - 1)/(x - 1)"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(function(input).unwrap().to_string(), expected, "{input}");
+        }
+
+        assert_eq!(function("x/(y - y)"), Err(PolynomialError::DivisionByZero));
+        assert!(matches!(
+            function("1/sin(x)"),
+            Err(PolynomialError::NotPolynomial(_))
+        ));
+    }
+
+    #[test]
+    fn test_cancel() {
+        let cases = [
+            ("(x^2 - 1)/(x - 1)", "x + 1"),
+            ("(x^2 - 1)/(2x + 2)", "1/2*x - 1/2"),
+            ("(x^2*y - y)/(x*y^2 + y^2)", "(x - 1)/y"),
+            ("(x + 1)/(x - 1)", "(x + 1)/(x - 1)"),
+            ("(a^2 - b^2)/(a^3 - b^3)", "(a + b)/(a^2 + a*b + b^2)"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(
+                function(input).unwrap().cancel().to_string(),
+                expected,
+                "{input}"
+            );
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use itertools::Itertools;
+use num::{BigRational, One, Signed, Zero};
+
+use crate::actions::{approximate::Interval, evaluate::Bindings};
+
+use super::{
+    polynomial::{Polynomial, PolynomialError},
+    Identifier,
+};
+
+impl Polynomial {
+    /// The Sturm sequence `p, p', -rem(p, p'), ...` of the polynomial in `variable`, the last
+    /// polynomial is the greatest common divisor of `p` and `p'` up to a constant.
+    pub fn sturm_sequence(&self, variable: &Identifier) -> Vec<Polynomial> {
+        let mut sequence = vec![self.clone(), self.derivative(variable)];
+
+        while let [.., previous, last] = sequence.as_slice() {
+            if last.is_zero() {
+                sequence.pop();
+                break;
+            }
+
+            let remainder = previous.div_rem(last).unwrap().1;
+            sequence.push(-remainder);
+        }
+
+        sequence
+    }
+
+    /// Bounds every real root of a polynomial in one variable by an interval at most
+    /// `tolerance` wide, which contains no other root. The intervals are sorted and roots which
+    /// are found exactly have equal bounds.
+    ///
+    /// The roots are isolated by bisecting an interval containing all of them, counting the
+    /// roots in each part with the Sturm sequence. Constants have no roots.
+    pub fn real_roots(&self, tolerance: &BigRational) -> Result<Vec<Interval>, PolynomialError> {
+        let variables = self.variables();
+        let variable = match variables.iter().collect::<Vec<_>>().as_slice() {
+            [] => return Ok(vec![]),
+            [variable] => (*variable).clone(),
+            _ => return Err(PolynomialError::NotUnivariate(self.to_string())),
+        };
+
+        // Sturm's theorem counts the roots in `(lower, upper]` only for square-free polynomials
+        let square_free = self.div_rem(&self.gcd(&self.derivative(&variable)))?.0;
+        let sequence = square_free.sturm_sequence(&variable);
+        let value = |point: &BigRational| {
+            let bindings = Bindings::from([(variable.clone(), point.clone())]);
+            square_free
+                .evaluate(&bindings)
+                .expect("the polynomial only has the bound variable")
+        };
+        let count = |lower: &BigRational, upper: &BigRational| {
+            sign_changes(&sequence, &variable, lower) - sign_changes(&sequence, &variable, upper)
+        };
+
+        // Cauchy's bound, every root is less than `1 + max|a_i/a_n|` away from zero
+        let leading = square_free.leading_coefficient().abs();
+        let bound = BigRational::one()
+            + square_free
+                .terms()
+                .map(|(_, coefficient)| coefficient.abs() / &leading)
+                .max()
+                .unwrap_or_default();
+
+        let mut pending = vec![Interval {
+            lower: -bound.clone(),
+            upper: bound,
+        }];
+        let mut roots = vec![];
+        while let Some(interval) = pending.pop() {
+            match count(&interval.lower, &interval.upper) {
+                0 => {}
+                1 => roots.push(interval),
+                _ => {
+                    let middle = interval.midpoint();
+                    pending.push(Interval {
+                        lower: middle.clone(),
+                        upper: interval.upper,
+                    });
+                    pending.push(Interval {
+                        lower: interval.lower,
+                        upper: middle,
+                    });
+                }
+            }
+        }
+
+        for root in &mut roots {
+            if value(&root.upper).is_zero() {
+                root.lower = root.upper.clone();
+            }
+            while &root.upper - &root.lower > *tolerance {
+                let middle = root.midpoint();
+                if value(&middle).is_zero() {
+                    root.lower = middle.clone();
+                    root.upper = middle;
+                } else if count(&root.lower, &middle) == 1 {
+                    root.upper = middle;
+                } else {
+                    root.lower = middle;
+                }
+            }
+        }
+        roots.sort_by(|lhs, rhs| lhs.lower.cmp(&rhs.lower));
+
+        Ok(roots)
+    }
+}
+
+/// The number of sign changes of the sequence at `point`, leaving out zeros.
+fn sign_changes(sequence: &[Polynomial], variable: &Identifier, point: &BigRational) -> usize {
+    let bindings = Bindings::from([(variable.clone(), point.clone())]);
+
+    sequence
+        .iter()
+        .map(|polynomial| {
+            polynomial
+                .evaluate(&bindings)
+                .expect("the polynomial only has the bound variable")
+        })
+        .filter(|value| !value.is_zero())
+        .map(|value| value.is_positive())
+        .tuple_windows()
+        .filter(|(lhs, rhs)| lhs != rhs)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::SQRT_2;
+
+    use num::BigInt;
+
+    use super::*;
+    use crate::test_utils::polynomial;
+
+    #[test]
+    fn test_real_roots() {
+        let tolerance = BigRational::new(BigInt::one(), BigInt::from(1_000_000));
+        let cases = [
+            ("x^2 - 2", vec![-SQRT_2, SQRT_2]),
+            ("x^3 - x - 1", vec![1.324_718]),
+            ("(x - 1)^2*(x + 3)", vec![-3.0, 1.0]),
+            (
+                "x^5 - 5x^3 + 4x + 1/10",
+                vec![-2.004_131, -0.983_091, -0.025_02, 1.016_445, 1.995_797],
+            ),
+            ("x^4 + 1", vec![]),
+            ("7", vec![]),
+        ];
+
+        for (input, expected) in cases {
+            let roots = polynomial(input).real_roots(&tolerance).unwrap();
+            assert_eq!(roots.len(), expected.len(), "{input}");
+
+            for (root, expected) in roots.iter().zip(expected) {
+                assert!(&root.upper - &root.lower <= tolerance, "{input}");
+                assert!((root.to_f64() - expected).abs() < 1e-3, "{input} {root:?}");
+            }
+        }
+
+        let roots = polynomial("x^3 - x").real_roots(&tolerance).unwrap();
+        assert!(roots.iter().all(|root| root.lower == root.upper));
+
+        assert!(matches!(
+            polynomial("x^2 - y").real_roots(&tolerance),
+            Err(PolynomialError::NotUnivariate(_))
+        ));
+    }
+}
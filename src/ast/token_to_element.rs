@@ -5,25 +5,40 @@ use crate::{
     ast::{Element, NodeOrExpression},
     tokenizer::{
         parser::TokenizedString,
+        span::Span,
         token::{Associativity, Operation, Token},
     },
 };
 
 use super::{
     equation::{EquationSide, NoContextEquation},
-    Node, Sign,
+    Constant, Node, Sign,
 };
 
 #[derive(Debug, Error)]
 pub enum TokenParseError {
-    #[error("Mismatched parenthesis at location {0}")]
-    MismatchedParenthesis(usize),
-    #[error("Unexpected comma at location ")]
-    UnexpectedComma(usize),
-    #[error("Not enough operands at location {0}")]
-    NotEnoughOperands(usize),
-    #[error("Too many operands")]
-    TooManyOperands,
+    #[error("Mismatched parenthesis at {0}")]
+    MismatchedParenthesis(Span),
+    #[error("Unexpected comma at {0}")]
+    UnexpectedComma(Span),
+    #[error("Not enough operands at {0}")]
+    NotEnoughOperands(Span),
+    #[error("Too many operands in {0}")]
+    TooManyOperands(Span),
+    #[error("Unexpected token at {0}")]
+    UnexpectedToken(Span),
+}
+
+impl TokenParseError {
+    pub fn span(&self) -> Span {
+        match self {
+            TokenParseError::MismatchedParenthesis(span)
+            | TokenParseError::UnexpectedComma(span)
+            | TokenParseError::NotEnoughOperands(span)
+            | TokenParseError::TooManyOperands(span)
+            | TokenParseError::UnexpectedToken(span) => *span,
+        }
+    }
 }
 
 #[tracing::instrument(skip_all)]
@@ -31,33 +46,53 @@ fn tokens_to_rpn<'a, I>(
     iterator: &mut I,
 ) -> Result<(Vec<Token>, Option<Operation>), TokenParseError>
 where
-    I: Iterator<Item = &'a Token>,
+    I: Iterator<Item = (&'a Token, &'a Span)>,
 {
-    let mut stack: Vec<(Token, usize)> = Vec::new();
-    let mut output: Vec<Token> = Vec::new();
+    let mut stack: Vec<(Token, Span)> = Vec::new();
+    let mut output: Vec<(Token, Span)> = Vec::new();
     let mut equal_sign = None;
+    let mut side_span: Option<Span> = None;
+    let mut last_span = Span::default();
 
-    for (pos, token) in iterator.enumerate() {
+    for (token, &pos) in iterator {
         let token = token.clone();
+        last_span = pos;
+
+        if !matches!(&token, Token::Binary(operation) if operation.is_comparison_sign()) {
+            side_span = Some(side_span.map_or(pos, |span| span.merge(pos)));
+        }
+
         match token {
-            Token::Number(_) | Token::Identifier { .. } => output.push(token),
-            Token::Unary(_) => stack.push((token, pos)),
+            Token::Number(_) | Token::Identifier { .. } | Token::Placeholder => {
+                output.push((token, pos))
+            }
+            Token::Unary(_) => {
+                if token.get_precedence_and_associativity().is_none() {
+                    return Err(TokenParseError::UnexpectedToken(pos));
+                }
+
+                stack.push((token, pos))
+            }
             Token::Binary(ref operation) => {
                 if operation.is_comparison_sign() {
                     equal_sign = Some(operation.clone());
                     break;
                 }
 
-                let pa1 = token.get_precedence_and_associativity();
+                let pa1 = token
+                    .get_precedence_and_associativity()
+                    .ok_or(TokenParseError::UnexpectedToken(pos))?;
 
-                while !stack.is_empty() {
-                    let pa2 = stack.last().unwrap().0.get_precedence_and_associativity();
+                while let Some(pa2) = stack
+                    .last()
+                    .and_then(|(last, _)| last.get_precedence_and_associativity())
+                {
                     match (pa1, pa2) {
                         ((i, Associativity::Left), (j, _)) if i <= j => {
-                            output.push(stack.pop().unwrap().0);
+                            output.push(stack.pop().unwrap());
                         }
                         ((i, Associativity::Right), (j, _)) if i < j => {
-                            output.push(stack.pop().unwrap().0);
+                            output.push(stack.pop().unwrap());
                         }
                         _ => {
                             break;
@@ -70,7 +105,7 @@ where
             Token::LeftParenthesis => stack.push((token, pos)),
             Token::RightParenthesis => {
                 let mut found = false;
-                while let Some((t, _)) = stack.pop() {
+                while let Some((t, i)) = stack.pop() {
                     match t {
                         Token::LeftParenthesis => {
                             found = true;
@@ -78,13 +113,16 @@ where
                         }
                         Token::Function { name, num_of_args } => {
                             found = true;
-                            output.push(Token::Function {
-                                name,
-                                num_of_args: Some(num_of_args.unwrap_or(0) + 1),
-                            });
+                            output.push((
+                                Token::Function {
+                                    name,
+                                    num_of_args: Some(num_of_args.unwrap_or(0) + 1),
+                                },
+                                i,
+                            ));
                             break;
                         }
-                        _ => output.push(t),
+                        _ => output.push((t, i)),
                     }
                 }
 
@@ -110,7 +148,7 @@ where
                             ));
                             break;
                         }
-                        _ => output.push(t),
+                        _ => output.push((t, i)),
                     }
                 }
 
@@ -122,38 +160,46 @@ where
         }
     }
 
-    while let Some((token, index)) = stack.pop() {
+    while let Some((token, span)) = stack.pop() {
         match token {
-            Token::Unary(_) | Token::Binary(_) => output.push(token),
+            Token::Unary(_) | Token::Binary(_) => output.push((token, span)),
             Token::LeftParenthesis | Token::Function { .. } => {
-                return Err(TokenParseError::MismatchedParenthesis(index));
+                return Err(TokenParseError::MismatchedParenthesis(span));
             }
-            _ => panic!("Unexpected token on stack."),
+            _ => return Err(TokenParseError::UnexpectedToken(span)),
         }
     }
 
+    if output.is_empty() {
+        return Err(TokenParseError::NotEnoughOperands(
+            side_span.unwrap_or(last_span),
+        ));
+    }
+
     let mut n_operands = 0isize;
-    for (index, token) in output.iter().enumerate() {
+    for (token, span) in output.iter() {
         match *token {
-            Token::Identifier { .. } | Token::Number(_) => n_operands += 1,
+            Token::Identifier { .. } | Token::Number(_) | Token::Placeholder => n_operands += 1,
             Token::Unary(_) => (),
             Token::Binary(_) => n_operands -= 1,
             Token::Function {
                 name: _,
                 num_of_args: Some(n_args),
             } => n_operands -= n_args as isize - 1,
-            _ => panic!("Nothing else should be here"),
+            _ => return Err(TokenParseError::UnexpectedToken(*span)),
         }
         if n_operands <= 0 {
-            return Err(TokenParseError::NotEnoughOperands(index));
+            return Err(TokenParseError::NotEnoughOperands(*span));
         }
     }
 
     if n_operands > 1 {
-        return Err(TokenParseError::TooManyOperands);
+        return Err(TokenParseError::TooManyOperands(
+            side_span.unwrap_or_default(),
+        ));
     }
 
-    output.shrink_to_fit();
+    let output = output.into_iter().map(|(token, _)| token).collect_vec();
 
     Ok((output, equal_sign))
 }
@@ -164,9 +210,19 @@ pub enum AbstractSyntaxTreeError {
     Unary,
     #[error("Binary AST error")]
     Binary,
+    #[error("A placeholder for a missing operand can't be converted")]
+    Placeholder,
+    #[error("An operator is missing an operand")]
+    MissingOperand,
+    #[error("A function has no argument count")]
+    UnknownArgumentCount,
+    #[error("Unexpected token {0:?} in reverse polish notation")]
+    UnexpectedToken(Token),
+    #[error("Expected one element, found {0}")]
+    WrongElementCount(usize),
 }
 
-fn rpn_to_ast(tokens: &[Token]) -> Result<Element, AbstractSyntaxTreeError> {
+pub(crate) fn rpn_to_ast(tokens: &[Token]) -> Result<Element, AbstractSyntaxTreeError> {
     let mut stack: Vec<Element> = Vec::new();
 
     for token in tokens.iter() {
@@ -178,25 +234,34 @@ fn rpn_to_ast(tokens: &[Token]) -> Result<Element, AbstractSyntaxTreeError> {
                 NodeOrExpression::Node(Node::Number(number)),
             )),
             Token::Identifier(name) => {
-                let node = Node::Variable(name);
+                let node = match Constant::from_identifier(&name) {
+                    Some(constant) => Node::Constant(constant),
+                    None => Node::Variable(name),
+                };
 
                 stack.push(Element::new(Sign::Positive, NodeOrExpression::Node(node)));
             }
             Token::Unary(operation) => {
-                let mut child = stack.pop().expect("Expected a token in the stack");
+                let mut child = stack.pop().ok_or(AbstractSyntaxTreeError::MissingOperand)?;
                 let result = match operation {
                     Operation::Add => child,
                     Operation::Subtract => {
                         child.invert_sign();
                         child
                     }
+                    Operation::Factorial => Element::new(
+                        Sign::Positive,
+                        NodeOrExpression::Node(Node::Factorial {
+                            child: Box::new(child),
+                        }),
+                    ),
                     _ => return Err(AbstractSyntaxTreeError::Unary),
                 };
                 stack.push(result);
             }
             Token::Binary(operation) => {
-                let right = stack.pop().expect("Expected a token in the stack");
-                let left = stack.pop().expect("Expected a token in the stack");
+                let right = stack.pop().ok_or(AbstractSyntaxTreeError::MissingOperand)?;
+                let left = stack.pop().ok_or(AbstractSyntaxTreeError::MissingOperand)?;
 
                 let result = match operation {
                     Operation::Add => Element::simple_add(left, right),
@@ -217,18 +282,17 @@ fn rpn_to_ast(tokens: &[Token]) -> Result<Element, AbstractSyntaxTreeError> {
                             power: Box::new(right),
                         }),
                     ),
-                    Operation::Equal
-                    | Operation::NotEqual
-                    | Operation::LessThan
-                    | Operation::LessThanOrEqual
-                    | Operation::GreaterThanOrEqual
-                    | Operation::GreaterThan => unreachable!(),
-                    _ => unreachable!(),
+                    _ => return Err(AbstractSyntaxTreeError::Binary),
                 };
                 stack.push(result);
             }
             Token::Function { name, num_of_args } => {
-                let num_of_args = num_of_args.expect("Expected a number of arguments");
+                let num_of_args =
+                    num_of_args.ok_or(AbstractSyntaxTreeError::UnknownArgumentCount)?;
+
+                if num_of_args > stack.len() {
+                    return Err(AbstractSyntaxTreeError::MissingOperand);
+                }
 
                 let arguments = stack.drain(stack.len() - num_of_args..).collect_vec();
 
@@ -239,13 +303,18 @@ fn rpn_to_ast(tokens: &[Token]) -> Result<Element, AbstractSyntaxTreeError> {
 
                 stack.push(function);
             }
-            _ => (),
+            Token::Placeholder => return Err(AbstractSyntaxTreeError::Placeholder),
+            Token::LeftParenthesis | Token::RightParenthesis | Token::Comma => {
+                return Err(AbstractSyntaxTreeError::UnexpectedToken(token))
+            }
         }
     }
 
-    assert!(stack.len() == 1);
-
-    Ok(stack.pop().unwrap())
+    let len = stack.len();
+    match stack.pop() {
+        Some(element) if len == 1 => Ok(element),
+        _ => Err(AbstractSyntaxTreeError::WrongElementCount(len)),
+    }
 }
 
 #[derive(Debug, Error)]
@@ -256,6 +325,15 @@ pub enum TokensToEquationError {
     AbstractSyntaxTreeError(AbstractSyntaxTreeError),
 }
 
+impl TokensToEquationError {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            TokensToEquationError::TokenParseError(error) => Some(error.span()),
+            TokensToEquationError::AbstractSyntaxTreeError(_) => None,
+        }
+    }
+}
+
 impl TryFrom<TokenizedString> for NoContextEquation {
     type Error = TokensToEquationError;
 
@@ -265,22 +343,156 @@ impl TryFrom<TokenizedString> for NoContextEquation {
         // println!("{tokenized_string:#?}");
 
         let mut sides: Vec<EquationSide> = Vec::new();
-        let mut token_iter = tokenized_string.iter();
-        let mut should_continue = true;
+        let mut token_iter = tokenized_string.iter_with_spans();
 
-        while should_continue {
-            let result = tokens_to_rpn(&mut token_iter);
+        // Every side ends with a comparison sign, except the last one.
+        loop {
+            let (rpn, operation) =
+                tokens_to_rpn(&mut token_iter).map_err(TokensToEquationError::TokenParseError)?;
 
-            if let Ok(rpn) = result {
-                if rpn.1.is_none() {
-                    should_continue = false;
-                }
-                if let Ok(expression) = rpn_to_ast(&rpn.0) {
-                    sides.push(EquationSide::new(expression, rpn.1));
-                }
+            let expression =
+                rpn_to_ast(&rpn).map_err(TokensToEquationError::AbstractSyntaxTreeError)?;
+
+            let is_last = operation.is_none();
+            sides.push(EquationSide::new(expression, operation));
+
+            if is_last {
+                break;
             }
         }
 
         Ok(NoContextEquation { sides })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ast::{context::CreateEquationError, Identifier},
+        test_utils::Random,
+    };
+
+    fn number(value: i64) -> Token {
+        Token::Number(num::BigRational::from_integer(value.into()))
+    }
+
+    fn random_token(random: &mut Random) -> Token {
+        let operations = [
+            Operation::Add,
+            Operation::Subtract,
+            Operation::Multiply,
+            Operation::Divide,
+            Operation::Mod,
+            Operation::Power,
+            Operation::Factorial,
+            Operation::Equal,
+            Operation::NotEqual,
+            Operation::LessThan,
+            Operation::GreaterThanOrEqual,
+        ];
+        let operation = operations[random.below(operations.len())].clone();
+
+        match random.below(9) {
+            0 => Token::Binary(operation),
+            1 => Token::Unary(operation),
+            2 => Token::LeftParenthesis,
+            3 => Token::RightParenthesis,
+            4 => Token::Comma,
+            5 => number(random.below(10) as i64),
+            6 => Token::Identifier(Identifier::new(["x", "y"][random.below(2)])),
+            7 => Token::Function {
+                name: Identifier::new("f"),
+                num_of_args: [None, Some(0), Some(1), Some(3)][random.below(4)],
+            },
+            _ => Token::Placeholder,
+        }
+    }
+
+    #[test]
+    fn test_random_tokens_do_not_panic() {
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..20_000 {
+            let length = random.below(12);
+            let tokens = (0..length).map(|_| random_token(&mut random)).collect_vec();
+
+            // Either result is fine, as long as there is no panic or endless loop.
+            let _ = NoContextEquation::try_from(TokenizedString::new_from_tokens(tokens));
+        }
+    }
+
+    #[test]
+    fn test_random_strings_do_not_panic() {
+        let mut random = Random(0x9e37_79b9_7f4a_7c15);
+        let alphabet = "0123456789.e_xyf(),+-*/%^!=<> \t€".chars().collect_vec();
+
+        for _ in 0..20_000 {
+            let length = random.below(16);
+            let input: String = (0..length)
+                .map(|_| alphabet[random.below(alphabet.len())])
+                .collect();
+
+            let _: Result<NoContextEquation, CreateEquationError> =
+                NoContextEquation::try_from(input.as_str());
+        }
+    }
+
+    #[test]
+    fn test_malformed_tokens() {
+        let cases = [
+            vec![],
+            vec![Token::Binary(Operation::Equal)],
+            vec![number(1), Token::Binary(Operation::Factorial)],
+            vec![number(1), number(2)],
+            vec![Token::RightParenthesis],
+            vec![Token::Unary(Operation::Multiply), number(1)],
+        ];
+
+        for tokens in cases {
+            assert!(NoContextEquation::try_from(TokenizedString::new_from_tokens(tokens)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_malformed_rpn() {
+        let cases = [
+            vec![],
+            vec![Token::Binary(Operation::Add)],
+            vec![number(1), number(2)],
+            vec![Token::Function {
+                name: Identifier::new("f"),
+                num_of_args: Some(2),
+            }],
+            vec![Token::LeftParenthesis],
+        ];
+
+        for tokens in cases {
+            assert!(rpn_to_ast(&tokens).is_err());
+        }
+    }
+
+    #[test]
+    fn test_structured_identifiers() {
+        let mut equation = NoContextEquation::try_from("v_0 + v = f'(x_{max})").unwrap();
+
+        let mut variables = std::collections::HashSet::new();
+        let mut functions = std::collections::HashSet::new();
+        for side in equation.sides.iter_mut() {
+            side.element.analyze(None);
+            let cache = side.element.cache.as_ref().unwrap();
+            variables.extend(cache.variables.iter().cloned());
+            functions.extend(cache.functions.iter().cloned());
+        }
+
+        assert_eq!(
+            variables,
+            ["v_0", "v", "x_{max}"].map(str::to_string).into()
+        );
+        assert_eq!(functions, ["f'"].map(str::to_string).into());
+        assert_eq!(
+            equation.sides[1].element.to_string(),
+            "f'(x_{max})".to_string()
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use std::fs;
+
+use color_eyre::eyre::Result;
+use math_eval::{initialize, tokenizer::parser::TokenizedString};
+
+/// Tokenizes every line of formulas.txt and prints all problems at once.
+fn main() -> Result<()> {
+    initialize()?;
+
+    let file = fs::read_to_string("formulas.txt").expect("File formulas.txt not found");
+    let mut broken_lines = 0;
+
+    for (line_number, equation) in file.lines().enumerate() {
+        if equation.trim().is_empty() {
+            continue;
+        }
+
+        let recovered = TokenizedString::tokenize_recovering(equation);
+
+        if !recovered.is_ok() {
+            broken_lines += 1;
+            println!("formulas.txt:{}", line_number + 1);
+            println!("{}\n", recovered.render(equation));
+        }
+    }
+
+    println!("{broken_lines} broken formulas");
+
+    Ok(())
+}
@@ -36,7 +36,7 @@ fn main() -> Result<()> {
                 println!("\n");
             }
             Err(err) => match err {
-                CreateEquationError::ParseError(ParseError::Empty) => {}
+                CreateEquationError::ParseError(ParseError::Empty(_)) => {}
                 _ => return Err(err.into()),
             },
         }
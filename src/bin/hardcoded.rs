@@ -31,7 +31,7 @@ fn main() -> Result<()> {
             debug!("{:#?}", equation);
         } */
 
-        App::solve(&mut borrowed_app, uuid);
+        App::solve(&mut borrowed_app, uuid, 20);
 
         let mut line = String::new();
         std::io::stdin().read_line(&mut line)?;
@@ -2,10 +2,14 @@ use color_eyre::eyre::Result;
 use math_eval::{
     ast::{app::App, context::Context},
     initialize,
+    output::equation_to_2d::{Charset, To2d},
 };
 use rustyline::{error::ReadlineError, DefaultEditor};
 use std::rc::Rc;
 
+/// The number of digits of roots which can't be written exactly.
+const DIGITS: u32 = 20;
+
 // TODO: vec remove unwrap
 fn main() -> Result<()> {
     initialize()?;
@@ -20,11 +24,33 @@ fn main() -> Result<()> {
                 let context = Context::new(Rc::clone(&app));
                 let ctx_uuid = app.borrow_mut().add_context(context);
 
-                App::try_add_equation(Rc::clone(&app), ctx_uuid, line.as_str())?;
+                let eq_uuid = match App::try_add_equation(Rc::clone(&app), ctx_uuid, line.as_str())
+                {
+                    Ok(eq_uuid) => eq_uuid,
+                    Err(err) => {
+                        println!("{}", err.render(&line));
+                        continue;
+                    }
+                };
+
                 let mut borrowed_app = app.borrow_mut();
-                // let context = borrowed_app.get_context_mut(ctx_uuid).unwrap();
-                App::solve(&mut borrowed_app, ctx_uuid);
-                // borrowed_app.solve(ctx_uuid);
+                let Some(mut equation) = borrowed_app
+                    .get_context(ctx_uuid)
+                    .and_then(|context| context.get_equation(eq_uuid))
+                    .cloned()
+                else {
+                    continue;
+                };
+                println!("{}\n", equation.to_2d(Charset::Unicode));
+
+                let graph = borrowed_app.solve_equation(&mut equation, DIGITS);
+                for (solution, constraints) in graph.leaves() {
+                    println!("{}", solution.to_2d(Charset::Unicode));
+                    for constraint in constraints {
+                        println!("  {constraint}");
+                    }
+                    println!();
+                }
 
                 /* let eq = ctx.remove_equation(uuid).unwrap();
 
@@ -1,6 +1,7 @@
 use petgraph::{
     graph::UnGraph,
     stable_graph::{EdgeIndex, NodeIndex},
+    visit::EdgeRef,
 };
 use serde::{Deserialize, Serialize};
 
@@ -33,4 +34,25 @@ impl EquationGraph {
 
         (node_index, edge_index)
     }
+
+    /// The leaves of the graph other than the equation it starts with, which are the solutions,
+    /// with the constraints of the edges on their path from it.
+    pub fn leaves(&self) -> Vec<(&Equation, Vec<String>)> {
+        self.graph
+            .node_indices()
+            .skip(1)
+            .filter(|index| self.graph.neighbors(*index).count() == 1)
+            .map(|leaf| {
+                // the graph is a tree and every node is added after its parent
+                let mut constraints = vec![];
+                let mut index = leaf;
+                while let Some(edge) = self.graph.edges(index).find(|edge| edge.target() < index) {
+                    constraints.splice(0..0, edge.weight().iter().cloned());
+                    index = edge.target();
+                }
+
+                (&self.graph[leaf], constraints)
+            })
+            .collect()
+    }
 }
@@ -9,6 +9,9 @@ pub mod output;
 pub mod strategies;
 pub mod tokenizer;
 
+#[cfg(test)]
+mod test_utils;
+
 /// Initialize error reporter and logger.
 pub fn initialize() -> Result<()> {
     color_eyre::install()?;
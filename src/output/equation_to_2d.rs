@@ -0,0 +1,479 @@
+use num::BigRational;
+
+use crate::ast::{product::Product, Element, Equation, Expression, Identifier, Node, Sign};
+
+use super::term::{Render, Term};
+
+/// Multi-line rendering for terminals, with stacked fractions, raised exponents and radicals.
+///
+/// ```text
+///   2
+///  x  + 1      _
+/// ──────── = ╲╱y
+///    2
+/// ```
+pub trait To2d {
+    fn to_2d(&self, charset: Charset) -> String;
+}
+
+/// The characters used to draw fraction bars, radicals and tall parentheses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Charset {
+    Ascii,
+    #[default]
+    Unicode,
+}
+
+impl Charset {
+    fn bar(self) -> char {
+        match self {
+            Charset::Ascii => '-',
+            Charset::Unicode => '─',
+        }
+    }
+
+    fn times(self) -> &'static str {
+        match self {
+            Charset::Ascii => "*",
+            Charset::Unicode => "·",
+        }
+    }
+
+    /// The bottom of a radical and the diagonal above it.
+    fn radical(self) -> (&'static str, char) {
+        match self {
+            Charset::Ascii => ("\\/", '/'),
+            Charset::Unicode => ("╲╱", '╱'),
+        }
+    }
+
+    /// Top, middle and bottom of a left and a right parenthesis taller than one line.
+    fn parentheses(self) -> ([char; 3], [char; 3]) {
+        match self {
+            Charset::Ascii => (['/', '|', '\\'], ['\\', '|', '/']),
+            Charset::Unicode => (['⎛', '⎜', '⎝'], ['⎞', '⎟', '⎠']),
+        }
+    }
+}
+
+/// A rectangle of text, every line is padded to `width` characters.
+///
+/// `baseline` is the line which is aligned with the blocks next to it, it can be one past the
+/// last line for blocks which are raised above their neighbours.
+#[derive(Debug, Clone)]
+struct Block {
+    lines: Vec<String>,
+    width: usize,
+    baseline: usize,
+}
+
+impl Block {
+    fn text(text: &str) -> Self {
+        Block {
+            lines: vec![text.to_string()],
+            width: text.chars().count(),
+            baseline: 0,
+        }
+    }
+
+    fn height(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Puts `blocks` next to each other with their baselines on the same line.
+    fn beside(blocks: &[Block]) -> Self {
+        let above = blocks.iter().map(|block| block.baseline).max().unwrap_or(0);
+        let below = blocks
+            .iter()
+            .map(|block| block.height().saturating_sub(block.baseline))
+            .max()
+            .unwrap_or(1);
+
+        let mut lines = vec![String::new(); above + below];
+        for block in blocks {
+            let top = above - block.baseline;
+            for (index, line) in lines.iter_mut().enumerate() {
+                match index.checked_sub(top).and_then(|row| block.lines.get(row)) {
+                    Some(row) => line.push_str(row),
+                    None => line.push_str(&" ".repeat(block.width)),
+                }
+            }
+        }
+
+        Block {
+            lines,
+            width: blocks.iter().map(|block| block.width).sum(),
+            baseline: above,
+        }
+    }
+
+    fn append(self, block: Block) -> Self {
+        Block::beside(&[self, block])
+    }
+
+    /// Puts `block` to the right of `self`, moved left into the blank space at the end of the
+    /// lines of `self` it shares, e.g. a multiplication sign under the exponent of a power.
+    fn kerned(self, block: Block) -> Self {
+        let left_width = self.width;
+        let (block_baseline, block_height) = (block.baseline, block.height());
+        let mut joined = Block::beside(&[self, block]);
+
+        let block_top = joined.baseline - block_baseline;
+        let rows = block_top..block_top + block_height;
+        let blank_end = |line: &str| {
+            let left = line.chars().take(left_width).collect::<String>();
+            left.len() - left.trim_end_matches(' ').len()
+        };
+        let overlap = joined.lines[rows.clone()]
+            .iter()
+            .map(|line| blank_end(line))
+            .min()
+            .unwrap_or(0);
+
+        for (row, line) in joined.lines.iter_mut().enumerate() {
+            // rows without `block` give up its padding instead of their own content
+            let cut = if rows.contains(&row) || blank_end(line) >= overlap {
+                left_width - overlap
+            } else {
+                left_width
+            };
+            *line = line
+                .chars()
+                .enumerate()
+                .filter(|(column, _)| !(cut..cut + overlap).contains(column))
+                .map(|(_, c)| c)
+                .collect();
+        }
+        joined.width -= overlap;
+
+        joined
+    }
+
+    fn fraction(numerator: Block, denominator: Block, charset: Charset) -> Self {
+        let width = numerator.width.max(denominator.width) + 2;
+        let baseline = numerator.height();
+
+        let mut lines = numerator.centered(width);
+        lines.push(charset.bar().to_string().repeat(width));
+        lines.extend(denominator.centered(width));
+
+        Block {
+            lines,
+            width,
+            baseline,
+        }
+    }
+
+    fn centered(self, width: usize) -> Vec<String> {
+        let left = (width - self.width) / 2;
+        let right = width - self.width - left;
+
+        self.lines
+            .into_iter()
+            .map(|line| format!("{}{line}{}", " ".repeat(left), " ".repeat(right)))
+            .collect()
+    }
+
+    /// Raises `exponent` to the upper right of `self`.
+    fn power(self, exponent: Block) -> Self {
+        let mut lines = exponent
+            .lines
+            .iter()
+            .map(|line| format!("{}{line}", " ".repeat(self.width)))
+            .collect::<Vec<_>>();
+        lines.extend(
+            self.lines
+                .iter()
+                .map(|line| format!("{line}{}", " ".repeat(exponent.width))),
+        );
+
+        Block {
+            lines,
+            width: self.width + exponent.width,
+            baseline: exponent.height() + self.baseline,
+        }
+    }
+
+    fn parenthesized(self, charset: Charset) -> Self {
+        let height = self.height();
+        let (left, right) = if height == 1 {
+            (Block::text("("), Block::text(")"))
+        } else {
+            let (left, right) = charset.parentheses();
+            (
+                Block::column(left, height, self.baseline),
+                Block::column(right, height, self.baseline),
+            )
+        };
+
+        Block::beside(&[left, self, right])
+    }
+
+    /// A one character wide column of `height` lines, drawn with the top, middle and bottom
+    /// character of `parts`.
+    fn column(parts: [char; 3], height: usize, baseline: usize) -> Self {
+        let lines = (0..height)
+            .map(|row| match row {
+                0 => parts[0],
+                row if row == height - 1 => parts[2],
+                _ => parts[1],
+            })
+            .map(String::from)
+            .collect();
+
+        Block {
+            lines,
+            width: 1,
+            baseline,
+        }
+    }
+
+    /// Draws a radical around `self`, with `index` in front of it for roots other than the
+    /// square root.
+    fn radical(self, index: Option<Block>, charset: Charset) -> Self {
+        let height = self.height();
+        let (bottom, diagonal) = charset.radical();
+
+        let mut lines = vec![format!(
+            "{}{}",
+            " ".repeat(height + 1),
+            "_".repeat(self.width)
+        )];
+        for (row, line) in self.lines.into_iter().enumerate() {
+            let prefix = if row == height - 1 {
+                format!("{bottom}{}", " ".repeat(row))
+            } else {
+                format!("{}{diagonal}{}", " ".repeat(height - row), " ".repeat(row))
+            };
+            lines.push(prefix + &line);
+        }
+
+        let radical = Block {
+            lines,
+            width: self.width + height + 1,
+            baseline: self.baseline + 1,
+        };
+
+        match index {
+            // the index sits on the line above the bottom of the radical
+            Some(mut index) => {
+                index.baseline = (index.height() + radical.baseline).saturating_sub(height);
+                Block::beside(&[index, radical])
+            }
+            None => radical,
+        }
+    }
+
+    fn render(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| line.trim_end())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl To2d for Equation {
+    fn to_2d(&self, charset: Charset) -> String {
+        let mut blocks = vec![];
+        for (index, side) in self.equation_sides.iter().enumerate() {
+            if index > 0 {
+                blocks.push(Block::text(" = "));
+            }
+            blocks.push(BlockRenderer(charset).element(side));
+        }
+
+        Block::beside(&blocks).render()
+    }
+}
+
+impl To2d for Element {
+    fn to_2d(&self, charset: Charset) -> String {
+        BlockRenderer(charset).element(self).render()
+    }
+}
+
+impl To2d for Expression {
+    fn to_2d(&self, charset: Charset) -> String {
+        BlockRenderer(charset).expression(self).render()
+    }
+}
+
+impl To2d for Product {
+    fn to_2d(&self, charset: Charset) -> String {
+        BlockRenderer(charset)
+            .term(&Term::from_product(self), true)
+            .render()
+    }
+}
+
+impl To2d for Node {
+    fn to_2d(&self, charset: Charset) -> String {
+        BlockRenderer(charset).node(self).render()
+    }
+}
+
+/// Lays out blocks with the characters of a charset, the denominator of a term is stacked under
+/// its numerator.
+struct BlockRenderer(Charset);
+
+impl Render for BlockRenderer {
+    type Output = Block;
+
+    fn number(&self, number: &BigRational) -> Block {
+        if number.is_integer() {
+            Block::text(&number.to_string())
+        } else {
+            Block::fraction(
+                Block::text(&number.numer().to_string()),
+                Block::text(&number.denom().to_string()),
+                self.0,
+            )
+        }
+    }
+
+    fn node(&self, node: &Node) -> Block {
+        match node {
+            Node::Number(number) => self.signed_number(number),
+            Node::Variable(variable) => identifier_to_block(variable),
+            Node::Constant(constant) => Block::text(match self.0 {
+                Charset::Ascii => constant.name(),
+                Charset::Unicode => constant.symbol(),
+            }),
+            Node::Power { base, power } => self.operand(base).power(self.element(power)),
+            Node::Modulo { lhs, rhs } => {
+                Block::beside(&[self.operand(lhs), Block::text(" mod "), self.operand(rhs)])
+            }
+            Node::Factorial { child } => self.operand(child).append(Block::text("!")),
+            Node::Function { name, arguments } => self.function(name, arguments),
+        }
+    }
+
+    fn negated(&self, body: Block) -> Block {
+        Block::text("-").append(body)
+    }
+
+    fn fraction(&self, numerator: Block, denominator: Block) -> Block {
+        Block::fraction(numerator, denominator, self.0)
+    }
+
+    fn parenthesized(&self, body: Block) -> Block {
+        body.parenthesized(self.0)
+    }
+
+    fn sum(&self, terms: Vec<(Sign, Block)>) -> Block {
+        let mut blocks = vec![];
+
+        for (index, (sign, body)) in terms.into_iter().enumerate() {
+            blocks.push(Block::text(match (index, sign) {
+                (0, Sign::Positive) => "",
+                (0, Sign::Negative) => "-",
+                (_, Sign::Positive) => " + ",
+                (_, Sign::Negative) => " - ",
+            }));
+            blocks.push(body);
+        }
+
+        Block::beside(&blocks)
+    }
+
+    fn product(&self, factors: Vec<(bool, Block)>) -> Block {
+        let mut factors = factors.into_iter();
+        let (_, first) = factors.next().expect("a product has at least one factor");
+
+        factors.fold(first, |result, (times_visible, body)| {
+            if times_visible {
+                result.kerned(Block::text(self.0.times())).append(body)
+            } else {
+                result.append(body)
+            }
+        })
+    }
+}
+
+impl BlockRenderer {
+    fn function(&self, name: &Identifier, arguments: &[Element]) -> Block {
+        match (name.as_plain(), arguments) {
+            (Some("sqrt"), [argument]) => self.element(argument).radical(None, self.0),
+            (Some("cbrt"), [argument]) => self
+                .element(argument)
+                .radical(Some(Block::text("3")), self.0),
+            _ => {
+                let mut blocks = vec![];
+                for (index, argument) in arguments.iter().enumerate() {
+                    if index > 0 {
+                        blocks.push(Block::text(", "));
+                    }
+                    blocks.push(self.element(argument));
+                }
+                if blocks.is_empty() {
+                    blocks.push(Block::text(""));
+                }
+
+                identifier_to_block(name).append(Block::beside(&blocks).parenthesized(self.0))
+            }
+        }
+    }
+}
+
+fn identifier_to_block(identifier: &Identifier) -> Block {
+    Block::text(&identifier.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::equation::NoContextEquation;
+
+    fn to_2d(input: &str, charset: Charset) -> String {
+        let equation = Equation {
+            equation_sides: NoContextEquation::try_from(input)
+                .unwrap()
+                .sides
+                .into_iter()
+                .map(|side| side.element)
+                .collect(),
+            ..Default::default()
+        };
+
+        equation.to_2d(charset)
+    }
+
+    #[test]
+    fn test_to_2d() {
+        let cases = [
+            ("2x + y", "2x + y"),
+            ("a/b", " a\n───\n b"),
+            ("x^2 + 1", " 2\nx  + 1"),
+            (
+                "(x^2 + 1)/2 = sqrt(y)",
+                "  2\n x  + 1      _\n──────── = ╲╱y\n   2",
+            ),
+            ("1/2 + a", " 1\n─── + a\n 2"),
+            ("-(a + b)", "-(a + b)"),
+            ("(1/x + 1)^2", "         2\n⎛ 1     ⎞\n⎜─── + 1⎟\n⎝ x     ⎠"),
+            ("sqrt(1/x)", "    ___\n   ╱ 1\n  ╱ ───\n╲╱   x"),
+            ("cbrt(x)", "3  _\n ╲╱x"),
+            ("f(x, y)*3 + n!", "f(x, y)·3 + n!"),
+            ("a % b", "a mod b"),
+            ("x^2*3 = y", " 2\nx·3 = y"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(to_2d(input, Charset::Unicode), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_ascii() {
+        let cases = [
+            ("a/b*c", " ac\n----\n b"),
+            ("sqrt(x + 1)", "  _____\n\\/x + 1"),
+            ("(a/b)^2*3", "     2\n/ a \\\n|---|*3\n\\ b /"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(to_2d(input, Charset::Ascii), expected, "{input}");
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use itertools::Itertools;
+use num::BigRational;
+
+use crate::{
+    ast::{product::Product, Element, Equation, Expression, Identifier, Node, Sign},
+    tokenizer::latex::{CONSTANTS, FUNCTIONS, GREEK_LETTERS},
+};
+
+use super::term::{Render, Term};
+
+pub trait ToLatex {
+    fn to_latex(&self) -> String;
+}
+
+impl ToLatex for Equation {
+    fn to_latex(&self) -> String {
+        self.equation_sides
+            .iter()
+            .map(|side| side.to_latex())
+            .join(" = ")
+    }
+}
+
+impl ToLatex for Element {
+    fn to_latex(&self) -> String {
+        LatexRenderer.element(self)
+    }
+}
+
+impl ToLatex for Expression {
+    fn to_latex(&self) -> String {
+        LatexRenderer.expression(self)
+    }
+}
+
+impl ToLatex for Product {
+    fn to_latex(&self) -> String {
+        LatexRenderer.term(&Term::from_product(self), true)
+    }
+}
+
+impl ToLatex for Node {
+    fn to_latex(&self) -> String {
+        LatexRenderer.node(self)
+    }
+}
+
+impl ToLatex for Identifier {
+    fn to_latex(&self) -> String {
+        let mut characters = self.base.chars();
+        let mut result = match (characters.next(), characters.next()) {
+            (Some(letter), None) => {
+                match GREEK_LETTERS.iter().find(|(_, greek)| *greek == letter) {
+                    Some((command, _)) => format!("\\{command}"),
+                    None => letter.to_string(),
+                }
+            }
+            _ => match CONSTANTS.iter().find(|(_, name)| *name == self.base) {
+                Some((command, _)) => format!("\\{command}"),
+                None => format!("\\mathit{{{}}}", self.base),
+            },
+        };
+
+        if let Some(subscript) = &self.subscript {
+            if subscript.chars().count() == 1 {
+                result += &format!("_{subscript}");
+            } else {
+                result += &format!("_{{{subscript}}}");
+            }
+        }
+
+        result + &"'".repeat(self.primes)
+    }
+}
+
+struct LatexRenderer;
+
+impl Render for LatexRenderer {
+    type Output = String;
+
+    fn number(&self, number: &BigRational) -> String {
+        if number.is_integer() {
+            number.to_string()
+        } else {
+            format!("\\frac{{{}}}{{{}}}", number.numer(), number.denom())
+        }
+    }
+
+    fn node(&self, node: &Node) -> String {
+        match node {
+            Node::Number(number) => self.signed_number(number),
+            Node::Variable(variable) => variable.to_latex(),
+            Node::Constant(constant) => Identifier::new(constant.name()).to_latex(),
+            Node::Power { base, power } => {
+                format!("{}^{{{}}}", self.operand(base), self.element(power))
+            }
+            Node::Modulo { lhs, rhs } => {
+                format!("{} \\bmod {}", self.operand(lhs), self.operand(rhs))
+            }
+            Node::Factorial { child } => format!("{}!", self.operand(child)),
+            Node::Function { name, arguments } => function_to_latex(name, arguments),
+        }
+    }
+
+    fn negated(&self, body: String) -> String {
+        format!("-{body}")
+    }
+
+    fn fraction(&self, numerator: String, denominator: String) -> String {
+        format!("\\frac{{{numerator}}}{{{denominator}}}")
+    }
+
+    fn parenthesized(&self, body: String) -> String {
+        format!("\\left({body}\\right)")
+    }
+
+    fn sum(&self, terms: Vec<(Sign, String)>) -> String {
+        let mut result = String::new();
+
+        for (index, (sign, body)) in terms.into_iter().enumerate() {
+            result += match (index, sign) {
+                (0, Sign::Positive) => "",
+                (0, Sign::Negative) => "-",
+                (_, Sign::Positive) => " + ",
+                (_, Sign::Negative) => " - ",
+            };
+            result += &body;
+        }
+
+        result
+    }
+
+    fn product(&self, factors: Vec<(bool, String)>) -> String {
+        let mut result = String::new();
+
+        for (times_visible, body) in factors {
+            if times_visible {
+                result += " \\cdot ";
+            } else if ends_with_command(&result) {
+                result.push(' ');
+            }
+            result += &body;
+        }
+
+        result
+    }
+}
+
+/// `\alpha x` needs the space, otherwise it would be read as the command `\alphax`.
+fn ends_with_command(latex: &str) -> bool {
+    let without_letters = latex.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    without_letters.len() < latex.len() && without_letters.ends_with('\\')
+}
+
+fn function_to_latex(name: &Identifier, arguments: &[Element]) -> String {
+    let latex_arguments = arguments
+        .iter()
+        .map(|argument| argument.to_latex())
+        .collect_vec();
+
+    match (name.as_plain(), latex_arguments.as_slice()) {
+        (Some("sqrt"), [argument]) => format!("\\sqrt{{{argument}}}"),
+        (Some("cbrt"), [argument]) => format!("\\sqrt[3]{{{argument}}}"),
+        (Some("log"), [argument, base]) => format!("\\log_{{{base}}}\\left({argument}\\right)"),
+        (Some(plain), _) if FUNCTIONS.contains(&plain) => {
+            format!("\\{plain}\\left({}\\right)", latex_arguments.join(", "))
+        }
+        _ => format!(
+            "\\operatorname{{{name}}}\\left({}\\right)",
+            latex_arguments.join(", ")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::equation::NoContextEquation, tokenizer::latex::Latex};
+
+    fn to_latex(input: &str) -> String {
+        NoContextEquation::try_from(input)
+            .unwrap()
+            .sides
+            .iter()
+            .map(|side| side.element.to_latex())
+            .join(" = ")
+    }
+
+    #[test]
+    fn test_to_latex() {
+        let cases = [
+            ("a/b", r"\frac{a}{b}"),
+            ("(1/2)*x", r"\frac{x}{2}"),
+            ("1*1", "1"),
+            ("a*b/c*d", r"\frac{abd}{c}"),
+            ("1/(x+1)", r"\frac{1}{x + 1}"),
+            ("2*(a+b)", r"2\left(a + b\right)"),
+            ("(a+b)*(c-d)", r"\left(a + b\right)\left(c - d\right)"),
+            ("a-(b+c)", r"a - \left(b + c\right)"),
+            ("a+b+c", r"a + b + c"),
+            ("-a*-b", r"ab"),
+            ("x*-3", r"-x \cdot 3"),
+            ("(x+1)^2", r"\left(x + 1\right)^{2}"),
+            ("x^(y+1)", r"x^{y + 1}"),
+            ("(2x)^2", r"\left(2x\right)^{2}"),
+            ("(-2)^x", r"\left(-2\right)^{x}"),
+            ("sqrt(x)+cbrt(y)", r"\sqrt{x} + \sqrt[3]{y}"),
+            ("sin(x)^2", r"\sin\left(x\right)^{2}"),
+            ("log(x, 2)", r"\log_{2}\left(x\right)"),
+            ("foo(x, y)", r"\operatorname{foo}\left(x, y\right)"),
+            ("f'(x)", r"\operatorname{f'}\left(x\right)"),
+            ("α*x + v_0 + x_{max}", r"\alpha x + v_0 + x_{max}"),
+            ("2π*r", r"2\pi r"),
+            ("rate*t", r"\mathit{rate}t"),
+            ("n!", "n!"),
+            ("(n+1)!", r"\left(n + 1\right)!"),
+            ("y = 3", "y = 3"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(to_latex(input), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let cases = [
+            "a/b + c",
+            "2(a+b)^2 - 1",
+            "sqrt(x+1)/(2*y)",
+            "sin(x)^2 + cos(x)^2 = 1",
+            "log(x, 2) + α*β_1",
+            r"1/2*x - 1",
+            "rate*t + f'(x) = foo(x, y)",
+            "width_{max}'' - 1",
+        ];
+
+        for input in cases {
+            let latex = to_latex(input);
+            let parsed = NoContextEquation::try_from(Latex(&latex))
+                .unwrap_or_else(|error| panic!("{latex}\n{}", error.render(&latex)));
+
+            assert_eq!(
+                parsed
+                    .sides
+                    .iter()
+                    .map(|side| side.element.to_latex())
+                    .join(" = "),
+                latex,
+                "{input}"
+            );
+        }
+
+        let parsed = NoContextEquation::try_from(Latex(r"\frac{1}{2}x")).unwrap();
+        assert_eq!(parsed.sides[0].element.to_latex(), r"\frac{x}{2}");
+    }
+}
@@ -0,0 +1,266 @@
+use itertools::Itertools;
+use num::BigRational;
+
+use crate::{
+    ast::{product::Product, Element, Equation, Expression, Identifier, Node, Sign},
+    tokenizer::latex::CONSTANTS,
+};
+
+use super::term::{Render, Term};
+
+/// Presentation MathML, e.g. `<msup><mi>x</mi><mn>2</mn></msup>` for `x^2`.
+///
+/// Only [`Equation`] returns a whole `<math>` element, everything else returns the content which
+/// can be placed inside of one.
+pub trait ToMathMl {
+    fn to_mathml(&self) -> String;
+}
+
+/// Between a function name and its arguments.
+const FUNCTION_APPLICATION: &str = "<mo>&#x2061;</mo>";
+/// Between factors like `2x`, where the multiplication sign isn't shown.
+const INVISIBLE_TIMES: &str = "<mo>&#x2062;</mo>";
+
+impl ToMathMl for Equation {
+    fn to_mathml(&self) -> String {
+        let sides = self
+            .equation_sides
+            .iter()
+            .map(|side| side.to_mathml())
+            .join("<mo>=</mo>");
+
+        format!("<math xmlns=\"http://www.w3.org/1998/Math/MathML\"><mrow>{sides}</mrow></math>")
+    }
+}
+
+impl ToMathMl for Element {
+    fn to_mathml(&self) -> String {
+        MathMlRenderer.element(self)
+    }
+}
+
+impl ToMathMl for Expression {
+    fn to_mathml(&self) -> String {
+        MathMlRenderer.expression(self)
+    }
+}
+
+impl ToMathMl for Product {
+    fn to_mathml(&self) -> String {
+        MathMlRenderer.term(&Term::from_product(self), true)
+    }
+}
+
+impl ToMathMl for Node {
+    fn to_mathml(&self) -> String {
+        MathMlRenderer.node(self)
+    }
+}
+
+impl ToMathMl for Identifier {
+    fn to_mathml(&self) -> String {
+        let base = match CONSTANTS.iter().find(|(_, name)| *name == self.base) {
+            Some(("pi", _)) => "&#x3C0;",
+            Some(_) => "&#x221E;",
+            None => &self.base,
+        };
+        let base = format!("<mi>{base}</mi>");
+
+        let subscript = self.subscript.as_ref().map(|subscript| {
+            if subscript.chars().all(|c| c.is_ascii_digit()) {
+                format!("<mn>{subscript}</mn>")
+            } else {
+                format!("<mi>{subscript}</mi>")
+            }
+        });
+
+        let primes = match self.primes {
+            0 => None,
+            1 => Some("<mo>&#x2032;</mo>".to_string()),
+            2 => Some("<mo>&#x2033;</mo>".to_string()),
+            3 => Some("<mo>&#x2034;</mo>".to_string()),
+            primes => Some(format!("<mo>{}</mo>", "&#x2032;".repeat(primes))),
+        };
+
+        match (subscript, primes) {
+            (None, None) => base,
+            (Some(subscript), None) => format!("<msub>{base}{subscript}</msub>"),
+            (None, Some(primes)) => format!("<msup>{base}{primes}</msup>"),
+            (Some(subscript), Some(primes)) => {
+                format!("<msubsup>{base}{subscript}{primes}</msubsup>")
+            }
+        }
+    }
+}
+
+struct MathMlRenderer;
+
+impl Render for MathMlRenderer {
+    type Output = String;
+
+    fn number(&self, number: &BigRational) -> String {
+        if number.is_integer() {
+            format!("<mn>{number}</mn>")
+        } else {
+            format!(
+                "<mfrac><mn>{}</mn><mn>{}</mn></mfrac>",
+                number.numer(),
+                number.denom()
+            )
+        }
+    }
+
+    fn node(&self, node: &Node) -> String {
+        match node {
+            Node::Number(number) => self.signed_number(number),
+            Node::Variable(variable) => variable.to_mathml(),
+            Node::Constant(constant) => Identifier::new(constant.name()).to_mathml(),
+            Node::Power { base, power } => {
+                format!(
+                    "<msup>{}<mrow>{}</mrow></msup>",
+                    self.operand(base),
+                    self.element(power)
+                )
+            }
+            Node::Modulo { lhs, rhs } => {
+                format!(
+                    "<mrow>{}<mo>mod</mo>{}</mrow>",
+                    self.operand(lhs),
+                    self.operand(rhs)
+                )
+            }
+            Node::Factorial { child } => {
+                format!("<mrow>{}<mo>!</mo></mrow>", self.operand(child))
+            }
+            Node::Function { name, arguments } => function_to_mathml(name, arguments),
+        }
+    }
+
+    fn negated(&self, body: String) -> String {
+        format!("<mrow><mo>&#x2212;</mo>{body}</mrow>")
+    }
+
+    fn fraction(&self, numerator: String, denominator: String) -> String {
+        format!("<mfrac>{numerator}{denominator}</mfrac>")
+    }
+
+    fn parenthesized(&self, body: String) -> String {
+        parenthesized(&body)
+    }
+
+    fn sum(&self, terms: Vec<(Sign, String)>) -> String {
+        let mut result = String::new();
+
+        for (index, (sign, body)) in terms.into_iter().enumerate() {
+            match (index, sign) {
+                (0, Sign::Positive) => {}
+                (_, Sign::Positive) => result += "<mo>+</mo>",
+                (_, Sign::Negative) => result += "<mo>&#x2212;</mo>",
+            }
+            result += &body;
+        }
+
+        format!("<mrow>{result}</mrow>")
+    }
+
+    fn product(&self, factors: Vec<(bool, String)>) -> String {
+        let grouped = factors.len() > 1;
+        let mut result = String::new();
+
+        for (index, (times_visible, body)) in factors.into_iter().enumerate() {
+            if times_visible {
+                result += "<mo>&#x22C5;</mo>";
+            } else if index > 0 {
+                result += INVISIBLE_TIMES;
+            }
+            result += &body;
+        }
+
+        if grouped {
+            format!("<mrow>{result}</mrow>")
+        } else {
+            result
+        }
+    }
+}
+
+fn parenthesized(body: &str) -> String {
+    format!("<mrow><mo>(</mo>{body}<mo>)</mo></mrow>")
+}
+
+fn function_to_mathml(name: &Identifier, arguments: &[Element]) -> String {
+    let mathml_arguments = arguments
+        .iter()
+        .map(|argument| argument.to_mathml())
+        .collect_vec();
+
+    match (name.as_plain(), mathml_arguments.as_slice()) {
+        (Some("sqrt"), [argument]) => format!("<msqrt>{argument}</msqrt>"),
+        (Some("cbrt"), [argument]) => format!("<mroot><mrow>{argument}</mrow><mn>3</mn></mroot>"),
+        (Some("log"), [argument, base]) => format!(
+            "<mrow><msub><mi>log</mi>{base}</msub>{FUNCTION_APPLICATION}{}</mrow>",
+            parenthesized(argument)
+        ),
+        _ => format!(
+            "<mrow>{}{FUNCTION_APPLICATION}{}</mrow>",
+            name.to_mathml(),
+            parenthesized(&mathml_arguments.join("<mo>,</mo>"))
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::equation::NoContextEquation;
+
+    fn to_mathml(input: &str) -> String {
+        NoContextEquation::try_from(input)
+            .unwrap()
+            .sides
+            .iter()
+            .map(|side| side.element.to_mathml())
+            .join("<mo>=</mo>")
+    }
+
+    #[test]
+    fn test_to_mathml() {
+        let cases = [
+            ("x^2", "<msup><mi>x</mi><mrow><mn>2</mn></mrow></msup>"),
+            (
+                "(x+1)^2",
+                "<msup><mrow><mo>(</mo><mrow><mi>x</mi><mo>+</mo><mn>1</mn></mrow><mo>)</mo></mrow><mrow><mn>2</mn></mrow></msup>",
+            ),
+            ("a/b", "<mfrac><mi>a</mi><mi>b</mi></mfrac>"),
+            ("(1/2)*x", "<mfrac><mi>x</mi><mn>2</mn></mfrac>"),
+            (
+                "2x-y",
+                "<mrow><mrow><mn>2</mn><mo>&#x2062;</mo><mi>x</mi></mrow><mo>&#x2212;</mo><mi>y</mi></mrow>",
+            ),
+            ("-x", "<mrow><mo>&#x2212;</mo><mi>x</mi></mrow>"),
+            (
+                "x*3",
+                "<mrow><mi>x</mi><mo>&#x22C5;</mo><mn>3</mn></mrow>",
+            ),
+            ("a%b", "<mrow><mi>a</mi><mo>mod</mo><mi>b</mi></mrow>"),
+            ("sqrt(x)", "<msqrt><mi>x</mi></msqrt>"),
+            (
+                "f(x, y)",
+                "<mrow><mi>f</mi><mo>&#x2061;</mo><mrow><mo>(</mo><mi>x</mi><mo>,</mo><mi>y</mi><mo>)</mo></mrow></mrow>",
+            ),
+            (
+                "log(x, 2)",
+                "<mrow><msub><mi>log</mi><mn>2</mn></msub><mo>&#x2061;</mo><mrow><mo>(</mo><mi>x</mi><mo>)</mo></mrow></mrow>",
+            ),
+            ("v_0", "<msub><mi>v</mi><mn>0</mn></msub>"),
+            ("y''", "<msup><mi>y</mi><mo>&#x2033;</mo></msup>"),
+            ("π", "<mi>&#x3C0;</mi>"),
+            ("1/2", "<mfrac><mn>1</mn><mn>2</mn></mfrac>"),
+            ("n!", "<mrow><mi>n</mi><mo>!</mo></mrow>"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(to_mathml(input), expected, "{input}");
+        }
+    }
+}
@@ -1,133 +1,225 @@
-use crate::ast::{product::Product, Element, Equation, Expression, Node, NodeOrExpression};
-
+use crate::ast::{product::Product, Element, Equation, Expression, Node, NodeOrExpression, Sign};
+
+/// Postfix notation with one space between tokens, e.g. `x 2 ^ ~ f:1` for `f(-x^2)`.
+///
+/// - `~` negates, `-` subtracts
+/// - numbers are written as `3`, `-3` or `1/2` without spaces
+/// - functions carry their number of arguments, `log:2`
+/// - sides of an equation are separated by `=`, `2 x * = 4`
+///
+/// The output can be read back with [`Rpn`](crate::tokenizer::rpn::Rpn).
 pub trait ReversePolishNotation {
     fn rpn(&self) -> String;
 }
 
 impl ReversePolishNotation for Equation {
     fn rpn(&self) -> String {
-        let mut result = String::new();
-
-        let len = self.equation_sides.len() as isize - 1;
-        if len.is_positive() {
-            for side in &self.equation_sides[0..len as usize] {
-                let side_rpn = side.rpn();
-                result += &format!("{side_rpn} = ",);
-            }
-        }
-
-        if let Some(last) = self.equation_sides.last() {
-            let side_rpn = last.rpn();
-            result += &format!("{side_rpn}")
-        }
-
-        //  1  2  -  3 *
-        let mut new_result = String::new();
-        if let Some(mut previous_char) = result.trim().chars().nth(0) {
-            if !previous_char.is_whitespace() {
-                new_result.push(previous_char);
-            }
-
-            for character in result.chars().skip(1).into_iter() {
-                if !previous_char.is_whitespace() || !character.is_whitespace() {
-                    new_result.push(character);
-                }
-
-                previous_char = character;
-            }
-        }
-
-        new_result
+        self.equation_sides
+            .iter()
+            .map(|side| side.rpn())
+            .collect::<Vec<_>>()
+            .join(" = ")
     }
 }
 
 impl ReversePolishNotation for Element {
     fn rpn(&self) -> String {
-        match &self.node_or_expression {
-            NodeOrExpression::Node(node) => node.rpn(),
-            NodeOrExpression::Expression(expression) => expression.rpn(),
-        }
+        let mut tokens = vec![];
+        push_element(self, &mut tokens);
+        tokens.join(" ")
     }
 }
 
 impl ReversePolishNotation for Expression {
     fn rpn(&self) -> String {
-        if self.products.len() == 0 {
-            return String::new();
-        }
+        let mut tokens = vec![];
+        push_expression(self, &mut tokens);
+        tokens.join(" ")
+    }
+}
 
-        let mut result = String::new();
+impl ReversePolishNotation for Product {
+    fn rpn(&self) -> String {
+        let mut tokens = vec![];
+        push_product(self, &mut tokens);
+        tokens.join(" ")
+    }
+}
 
-        for (pos, product) in self.products.iter().enumerate() {
-            if product.numerator.len() == 0 && product.denominator.len() == 0 {
-                break;
-            }
+impl ReversePolishNotation for Node {
+    fn rpn(&self) -> String {
+        let mut tokens = vec![];
+        push_node(self, &mut tokens);
+        tokens.join(" ")
+    }
+}
+
+fn push_element(element: &Element, tokens: &mut Vec<String>) {
+    match &element.node_or_expression {
+        NodeOrExpression::Node(node) => push_node(node, tokens),
+        NodeOrExpression::Expression(expression) => push_expression(expression, tokens),
+    }
 
-            let product_rpn = product.rpn();
-            result.push_str(&format!("{product_rpn} "));
+    if element.sign == Sign::Negative {
+        tokens.push("~".to_string());
+    }
+}
 
-            if pos >= 1 {
-                let sign = product.calculate_sign();
-                result.push_str(&format!("{sign} "));
+/// Terms are added one after the other, a negative term is subtracted, so `a - b` is `a b -`.
+fn push_expression(expression: &Expression, tokens: &mut Vec<String>) {
+    let Some((first, rest)) = expression.products.split_first() else {
+        tokens.push("0".to_string());
+        return;
+    };
+
+    push_product(first, tokens);
+
+    for product in rest {
+        match product.numerator.as_slice() {
+            [element] if product.denominator.is_empty() && element.sign == Sign::Negative => {
+                let mut positive = element.clone();
+                positive.sign = Sign::Positive;
+                push_element(&positive, tokens);
+                tokens.push("-".to_string());
+            }
+            _ => {
+                push_product(product, tokens);
+                tokens.push("+".to_string());
             }
         }
-
-        result
     }
 }
 
-impl ReversePolishNotation for Product {
-    fn rpn(&self) -> String {
-        let mut result = String::new();
-
-        for (side_pos, side) in [&self.numerator, &self.denominator].into_iter().enumerate() {
-            if side_pos == 0 && self.numerator.is_empty() {
-                result += "1l ";
+/// Factors are multiplied one after the other and then divided by each factor of the
+/// denominator, so `a*b/(c*d)` is `a b * c / d /`.
+fn push_product(product: &Product, tokens: &mut Vec<String>) {
+    match product.numerator.split_first() {
+        Some((first, rest)) => {
+            push_element(first, tokens);
+            for element in rest {
+                push_element(element, tokens);
+                tokens.push("*".to_string());
             }
+        }
+        None => tokens.push("1".to_string()),
+    }
 
-            for (elem_pos, element) in side.into_iter().enumerate() {
-                let element_rpn = element.rpn();
-                result.push_str(&format!("{element_rpn} "));
+    for element in &product.denominator {
+        push_element(element, tokens);
+        tokens.push("/".to_string());
+    }
+}
 
-                if elem_pos >= 1 {
-                    result.push_str("* ");
-                }
+fn push_node(node: &Node, tokens: &mut Vec<String>) {
+    match node {
+        Node::Number(number) => tokens.push(number.to_string()),
+        Node::Variable(name) => tokens.push(name.to_string()),
+        Node::Constant(constant) => tokens.push(constant.to_string()),
+        Node::Power { base, power } => {
+            push_element(base, tokens);
+            push_element(power, tokens);
+            tokens.push("^".to_string());
+        }
+        Node::Modulo { lhs, rhs } => {
+            push_element(lhs, tokens);
+            push_element(rhs, tokens);
+            tokens.push("%".to_string());
+        }
+        Node::Factorial { child } => {
+            push_element(child, tokens);
+            tokens.push("!".to_string());
+        }
+        Node::Function { name, arguments } => {
+            for argument in arguments {
+                push_element(argument, tokens);
             }
+            tokens.push(format!("{name}:{}", arguments.len()));
+        }
+    }
+}
 
-            if !self.denominator.is_empty() {
-                result.push(' ');
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actions::is_same::{IsSame, IsSameNames},
+        ast::equation::NoContextEquation,
+        tokenizer::rpn::Rpn,
+    };
+
+    fn rpn(equation: &NoContextEquation) -> String {
+        equation
+            .sides
+            .iter()
+            .map(|side| side.element.rpn())
+            .collect::<Vec<_>>()
+            .join(" = ")
+    }
+
+    #[test]
+    fn test_rpn_round_trip() {
+        let cases = [
+            ("1 + 2", "1 2 +"),
+            ("a - b", "a b -"),
+            ("-a + b", "a ~ b +"),
+            ("a - (b + c)", "a b c + -"),
+            ("2x^2", "2 x 2 ^ *"),
+            ("(a + b)/(c - d)", "a b + c d - /"),
+            ("-x^2", "x 2 ^ ~"),
+            ("(-x)^2", "x ~ 2 ^"),
+            ("n! % 3", "n ! 3 %"),
+            ("log(x, 2) + sin(x)", "x 2 log:2 x sin:1 +"),
+            ("f'(v_0) = x_{max}", "v_0 f':1 = x_{max}"),
+            ("2x = 4", "2 x * = 4"),
+        ];
+
+        for (input, expected) in cases {
+            let equation = NoContextEquation::try_from(input).unwrap();
+            let output = rpn(&equation);
+            assert_eq!(output, expected, "{input}");
+
+            let parsed = NoContextEquation::try_from(Rpn(&output))
+                .unwrap_or_else(|error| panic!("{}", error.render(&output)));
+            assert_eq!(rpn(&parsed), output);
+
+            for (left, right) in equation.sides.iter().zip(&parsed.sides) {
+                assert!(Element::is_same(
+                    &left.element,
+                    &right.element,
+                    &mut IsSameNames::new()
+                ));
             }
         }
+    }
 
-        if !self.denominator.is_empty() {
-            result.push('/');
-        }
+    #[test]
+    fn test_numbers() {
+        let half = Node::Number(num::BigRational::new((-1).into(), 2.into()));
+        assert_eq!(half.rpn(), "-1/2");
 
-        result
+        let parsed = NoContextEquation::try_from(Rpn("-1/2 x *")).unwrap();
+        assert_eq!(parsed.sides[0].element.rpn(), "-1/2 x *");
     }
-}
 
-impl ReversePolishNotation for Node {
-    fn rpn(&self) -> String {
-        match self {
-            Node::Number(number) => number.to_string(),
-            Node::Variable(name) => name.to_string(),
-            Node::Power { base, power } => format!("{base} {power} ^"),
-            Node::Modulo { lhs, rhs } => format!("{lhs} {rhs} %"),
-            Node::Factorial { child } => format!("{child}!"),
-            Node::Function { name, arguments } => {
-                let mut result = format!("{name}(");
-
-                for (index, argument) in arguments.iter().enumerate() {
-                    result += &argument.rpn();
-                    if index < arguments.len() - 1 {
-                        result += ", ";
-                    }
-                }
-
-                result.push(')');
-                result
-            }
-        }
+    #[test]
+    fn test_flattened_products_are_stable() {
+        // `a*b/(c*d)` as the flatten strategy leaves it, not as the parser produces it
+        let variable = |name: &str| {
+            Element::new(
+                Sign::Positive,
+                NodeOrExpression::Node(Node::Variable(name.into())),
+            )
+        };
+        let product = Product::new(
+            vec![variable("a"), variable("b")],
+            vec![variable("c"), variable("d")],
+        );
+        let expression = Expression::new(vec![product.clone(), product]);
+
+        let output = expression.rpn();
+        assert_eq!(output, "a b * c / d / a b * c / d / +");
+
+        let parsed = NoContextEquation::try_from(Rpn(&output)).unwrap();
+        assert_eq!(parsed.sides[0].element.rpn(), output);
     }
 }
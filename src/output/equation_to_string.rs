@@ -1,24 +1,52 @@
 use crate::ast::{Equation, Expression, Node, Sign};
-use std::fmt::{Display, Write};
+use num::{Signed, Zero};
+use std::fmt::Display;
+
+use crate::ast::{element::IsTimesVisible, product::Product, Element, NodeOrExpression};
+
+/// How tightly a printed form binds, the same order as the operator precedence of the parser.
+/// A form is parenthesized when it's used where something binding tighter is expected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Sum,
+    Product,
+    Unary,
+    Power,
+    Postfix,
+    Atom,
+}
 
-use crate::ast::{element::ShouldBeParenthesized, product::Product, Element, NodeOrExpression};
+/// A printed form and how tightly it binds.
+struct Printed {
+    text: String,
+    precedence: Precedence,
+}
 
-impl Display for Equation {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut result = String::new();
+impl Printed {
+    fn new(text: String, precedence: Precedence) -> Self {
+        Printed { text, precedence }
+    }
 
-        let len = self.equation_sides.len() as isize - 1;
-        if len.is_positive() {
-            for side in &self.equation_sides[0..len as usize] {
-                result += &format!("{} = ", side)
-            }
+    /// The text, parenthesized if it binds weaker than `required`.
+    fn at_least(self, required: Precedence) -> String {
+        if self.precedence < required {
+            format!("({})", self.text)
+        } else {
+            self.text
         }
+    }
+}
 
-        if let Some(last) = self.equation_sides.last() {
-            result += &format!("{}", last)
+impl Display for Equation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, side) in self.equation_sides.iter().enumerate() {
+            if index > 0 {
+                write!(f, " = ")?;
+            }
+            write!(f, "{side}")?;
         }
 
-        write!(f, "{}", result)
+        Ok(())
     }
 }
 
@@ -33,167 +61,441 @@ impl Display for Sign {
 
 impl Display for NodeOrExpression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let result = match self {
-            NodeOrExpression::Node(node) => node.to_string(),
-            NodeOrExpression::Expression(expression) => expression.to_string(),
-        };
-
-        write!(f, "{}", result)
+        write!(f, "{}", print_node_or_expression(self).text)
     }
 }
 
-fn to_string_with_parenthesis<T: ShouldBeParenthesized + Display>(item: &T) -> String {
-    let parenthesis = item.should_be_parenthesized();
-    format!(
-        "{}{}{}",
-        if parenthesis { "(" } else { "" },
-        &item.to_string(),
-        if parenthesis { ")" } else { "" },
-    )
+impl Display for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", print_node(self).text)
+    }
 }
 
-impl Display for Node {
+impl Display for Expression {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut result = String::new();
-        match self {
-            Node::Number(number) => result += &number.to_string(),
-            Node::Variable(variable) => result += variable,
-            Node::Power { base, power } => {
-                write!(
-                    result,
-                    "{}^{}",
-                    to_string_with_parenthesis(base.as_ref()),
-                    to_string_with_parenthesis(power.as_ref()),
-                )
-                .unwrap();
-            }
-            Node::Modulo { lhs, rhs } => {
-                write!(
-                    result,
-                    "{}%{}",
-                    to_string_with_parenthesis(lhs.as_ref()),
-                    to_string_with_parenthesis(rhs.as_ref())
-                )
-                .unwrap();
-            }
-            Node::Factorial { child } => {
-                write!(result, "{}!", to_string_with_parenthesis(child.as_ref())).unwrap();
-            }
-            Node::Function { name, arguments } => {
-                write!(result, "{}(", name).unwrap();
-                for (index, argument) in arguments.iter().enumerate() {
-                    result += &argument.to_string();
-                    if index < arguments.len() - 1 {
-                        result += ", ";
-                    }
-                }
-                result += ")";
-            }
-        }
+        write!(f, "{}", print_expression(self).text)
+    }
+}
 
-        write!(f, "{}", result)
+impl Display for Product {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", print_product(self).text)
     }
 }
 
-impl Display for Expression {
+impl Display for Element {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut result = String::new();
+        write!(f, "{}", print_element(self, self.sign).text)
+    }
+}
+
+/// The product `element` is printed as if it's negated, with the sign on its first factor like
+/// `-a/b` instead of `-(a/b)`.
+fn negated_product(element: &Element, sign: Sign) -> Option<Product> {
+    let NodeOrExpression::Expression(expression) = &element.node_or_expression else {
+        return None;
+    };
+    let [product] = expression.products.as_slice() else {
+        return None;
+    };
+    let first = product.numerator.first()?;
+    if sign == Sign::Positive || starts_with_minus(first) {
+        return None;
+    }
+
+    let mut product = product.clone();
+    product.numerator[0].sign = Sign::Negative;
+    Some(product)
+}
 
-        for product in &self.products {
-            result += &product.to_string();
+/// Whether `element` is printed with a minus in front, as the first factor of a product.
+fn starts_with_minus(element: &Element) -> bool {
+    if element.sign == Sign::Negative {
+        return true;
+    }
+
+    match &element.node_or_expression {
+        NodeOrExpression::Node(Node::Number(number)) => number.is_negative(),
+        NodeOrExpression::Node(Node::Modulo { lhs, .. }) => starts_with_minus(lhs),
+        NodeOrExpression::Node(_) => false,
+        NodeOrExpression::Expression(expression) => match expression.products.as_slice() {
+            [product] => product.numerator.first().is_some_and(starts_with_minus),
+            _ => false,
+        },
+    }
+}
+
+/// Prints `element` as if it had the sign `sign`.
+fn print_element(element: &Element, sign: Sign) -> Printed {
+    if let Some(product) = negated_product(element, sign) {
+        return print_product(&product);
+    }
+
+    let body = print_node_or_expression(&element.node_or_expression);
+
+    match sign {
+        Sign::Positive => body,
+        Sign::Negative => Printed::new(
+            format!("-{}", body.at_least(Precedence::Power)),
+            Precedence::Unary,
+        ),
+    }
+}
+
+fn print_node_or_expression(node_or_expression: &NodeOrExpression) -> Printed {
+    match node_or_expression {
+        NodeOrExpression::Node(node) => print_node(node),
+        NodeOrExpression::Expression(expression) => print_expression(expression),
+    }
+}
+
+fn print_node(node: &Node) -> Printed {
+    match node {
+        Node::Number(number) => {
+            let precedence = if number.is_negative() {
+                Precedence::Unary
+            } else if number.is_integer() {
+                Precedence::Atom
+            } else {
+                Precedence::Product
+            };
+
+            Printed::new(number.to_string(), precedence)
         }
+        Node::Variable(variable) => Printed::new(variable.to_string(), Precedence::Atom),
+        Node::Constant(constant) => Printed::new(constant.to_string(), Precedence::Atom),
+        Node::Power { base, power } => Printed::new(
+            format!(
+                "{}^{}",
+                print_element(base, base.sign).at_least(Precedence::Postfix),
+                print_element(power, power.sign).at_least(Precedence::Power),
+            ),
+            Precedence::Power,
+        ),
+        Node::Modulo { lhs, rhs } => Printed::new(
+            format!(
+                "{}%{}",
+                print_element(lhs, lhs.sign).at_least(Precedence::Product),
+                print_element(rhs, rhs.sign).at_least(Precedence::Unary),
+            ),
+            Precedence::Product,
+        ),
+        Node::Factorial { child } => Printed::new(
+            format!(
+                "{}!",
+                print_element(child, child.sign).at_least(Precedence::Postfix)
+            ),
+            Precedence::Postfix,
+        ),
+        Node::Function { name, arguments } => {
+            let arguments = arguments
+                .iter()
+                .map(|argument| argument.to_string())
+                .collect::<Vec<_>>();
+
+            Printed::new(
+                format!("{}({})", name, arguments.join(", ")),
+                Precedence::Atom,
+            )
+        }
+    }
+}
 
-        write!(f, "{}", result)
+/// Sums are printed left associative, so `a + b + c` is `(a + b) + c` and `a + (b + c)` keeps
+/// its parenthesis. A negative term is printed as a subtraction.
+fn print_expression(expression: &Expression) -> Printed {
+    match expression.products.as_slice() {
+        [] => Printed::new("0".to_string(), Precedence::Atom),
+        [product] => print_product(product),
+        products => {
+            let mut text = String::new();
+
+            for (index, product) in products.iter().enumerate() {
+                let (sign, term) = match product.numerator.as_slice() {
+                    [element] if product.denominator.is_empty() && index > 0 => {
+                        (element.sign, print_element(element, Sign::Positive))
+                    }
+                    [first, ..] if first.sign == Sign::Negative && index > 0 => {
+                        let mut product = product.clone();
+                        product.numerator[0].sign = Sign::Positive;
+                        (Sign::Negative, print_product(&product))
+                    }
+                    _ => (Sign::Positive, print_product(product)),
+                };
+
+                if index == 0 {
+                    text += &term.at_least(Precedence::Sum);
+                } else {
+                    text += &format!(" {sign} {}", term.at_least(Precedence::Product));
+                }
+            }
+
+            Printed::new(text, Precedence::Sum)
+        }
     }
 }
 
-impl Display for Product {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut result = String::new();
+/// Products are printed left associative like sums, the denominator is printed after a single
+/// `/`, so `a*b/(c*d)`.
+fn print_product(product: &Product) -> Printed {
+    if product.denominator.is_empty() {
+        if let [element] = product.numerator.as_slice() {
+            return print_element(element, element.sign);
+        }
+    }
 
-        if self.numerator.is_empty() {
-            result += " 1";
+    let mut text = print_factors(&product.numerator);
+
+    match product.denominator.as_slice() {
+        [] => {}
+        [element] => {
+            text += &format!(
+                "/{}",
+                print_element(element, element.sign).at_least(Precedence::Unary)
+            );
         }
+        denominator => text += &format!("/({})", print_factors(denominator)),
+    }
 
-        // println!("{self:#?}");
+    Printed::new(text, Precedence::Product)
+}
 
-        for (side_pos, side) in [&self.numerator, &self.denominator].into_iter().enumerate() {
-            let open = side.should_be_parenthesized();
+fn print_factors(factors: &[Element]) -> String {
+    let Some(first) = factors.first() else {
+        return "1".to_string();
+    };
 
-            if open {
-                result.push('(');
-            }
+    let mut text = print_element(first, first.sign).at_least(Precedence::Product);
 
-            for (element_pos, element) in side.iter().enumerate() {
-                let element_open = (element_pos > 0 || (side_pos == 1 && element_pos == 0))
-                    && element.sign == Sign::Negative;
+    for (index, (last, factor)) in factors.iter().zip(factors.iter().skip(1)).enumerate() {
+        let printed = print_element(factor, factor.sign).at_least(Precedence::Unary);
+        // only the first factor isn't parenthesized, `2/3*x` as `2/3x` would be read as `2/(3x)`
+        let after_quotient = index == 0 && ends_with_quotient(first);
 
-                if element_open {
-                    result.push('(');
-                }
+        if !after_quotient && !is_times_visible(factor, last, &printed) {
+            text += &printed;
+        } else {
+            text += &format!("*{printed}");
+        }
+    }
 
-                result += &format!("{} {} ", element.sign, element.to_string());
+    text
+}
 
-                if element_open {
-                    // result.push_str(&format!("_!{element_pos}_"));
-                    result.push(')');
-                }
+/// Uses [`IsTimesVisible`], but only leaves out the times sign where the parser inserts it
+/// again: `2x` and `(a + b)x`, but not `xy` (a single identifier) or `2e` (a float).
+fn is_times_visible(factor: &Element, last: &Element, printed: &str) -> bool {
+    let last = rightmost_factor(last);
 
-                // TODO: -6 isn't in denominator
-                // 1/((((-6)+1)+1)+1)
-                /* if side.len() > 1 && element_pos == 0 {
-                    result.push_str(&format!("__:{side_pos}:{element_pos}:{}", side.len()));
-                } */
-            }
+    if factor.is_times_visible(last)
+        || !printed.starts_with(|c: char| c.is_alphabetic() || c == '(')
+    {
+        return true;
+    }
 
-            if open {
-                result.push(')');
-            }
+    match &last.node_or_expression {
+        // `2e5` is a float and `0x1` a hexadecimal number
+        NodeOrExpression::Node(Node::Number(number)) => {
+            printed.starts_with(['e', 'E'])
+                || (number.is_zero() && printed.starts_with(['x', 'X', 'o', 'O', 'b', 'B']))
+        }
+        NodeOrExpression::Node(_) => true,
+        NodeOrExpression::Expression(_) => false,
+    }
+}
 
-            if side_pos == 0 && !self.denominator.is_empty() {
-                result.push('/');
+/// Whether `element` is printed ending in a denominator, like `2/3` or `a/b`.
+fn ends_with_quotient(element: &Element) -> bool {
+    match &element.node_or_expression {
+        NodeOrExpression::Node(Node::Number(number)) => !number.is_integer(),
+        NodeOrExpression::Node(_) => false,
+        NodeOrExpression::Expression(expression) => match expression.products.as_slice() {
+            [product] => {
+                !product.denominator.is_empty()
+                    || product.numerator.last().is_some_and(ends_with_quotient)
             }
-        }
+            _ => false,
+        },
+    }
+}
 
-        write!(f, "{}", result)
+/// The factor which is printed last in `element`, e.g. `x` in `2x` or `b` in `a/b`.
+fn rightmost_factor(element: &Element) -> &Element {
+    // `-(a + b)` ends with a parenthesis, while `-a*b` is printed without one
+    if element.sign == Sign::Negative && negated_product(element, element.sign).is_none() {
+        return element;
+    }
+
+    match &element.node_or_expression {
+        NodeOrExpression::Expression(expression) => match expression.products.as_slice() {
+            [product] => match (product.numerator.last(), product.denominator.as_slice()) {
+                (Some(last), []) => rightmost_factor(last),
+                (_, [last]) => rightmost_factor(last),
+                _ => element,
+            },
+            _ => element,
+        },
+        NodeOrExpression::Node(_) => element,
     }
 }
 
-/* TODO:
-5-6
-5*(-6)
- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::parse;
+    use crate::{
+        actions::is_same::{IsSame, IsSameNames},
+        ast::{Constant, Identifier},
+        test_utils::Random,
+    };
+
+    /// Moves the sign of a negated product onto its first factor like the printer does, where
+    /// two signs cancel, so trees which only differ in where a negation is written are equal.
+    fn sign_on_first_factor(mut element: Element) -> Element {
+        if let (Sign::Negative, NodeOrExpression::Expression(expression)) =
+            (element.sign, &mut element.node_or_expression)
+        {
+            if let [product] = expression.products.as_mut_slice() {
+                if let Some(first) = product.numerator.first_mut() {
+                    first.sign = first.sign * Sign::Negative;
+                    element.sign = Sign::Positive;
+                }
+            }
+        }
 
-impl Display for Element {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut result = String::new();
-        // println!("{self:#?}");
+        element
+    }
 
-        let one_product = match &self.node_or_expression {
-            NodeOrExpression::Expression(expression) => expression.products.len() < 2,
-            NodeOrExpression::Node(_) => true,
+    fn assert_round_trip(element: &Element) {
+        let printed = element.to_string();
+        let parsed = parse(&printed);
+        let normalized = |element: &Element| {
+            element
+                .clone()
+                .apply_to_every_element_into(&mut sign_on_first_factor, true, None)
         };
 
-        let open_element = matches!(self.node_or_expression, NodeOrExpression::Expression(_))
-            && (self.sign == Sign::Negative || !one_product);
-        if open_element {
-            result.push('(');
+        assert!(
+            Element::is_same(
+                &normalized(element),
+                &normalized(&parsed),
+                &mut IsSameNames::new()
+            ),
+            "{printed} was parsed as {parsed:#?}"
+        );
+        assert_eq!(parsed.to_string(), printed);
+    }
+
+    #[test]
+    fn test_minimal_parenthesis() {
+        let cases = [
+            ("1 + 2", "1 + 2"),
+            ("a - b", "a - b"),
+            ("a + -b", "a - b"),
+            ("a - (b + c)", "a - (b + c)"),
+            ("(a + b) + c", "a + b + c"),
+            ("a + (b + c)", "a + (b + c)"),
+            ("a - (-b)", "a + b"),
+            ("2 * x", "2x"),
+            ("2 * x * y", "2x*y"),
+            ("x * y", "x*y"),
+            ("2 * (a + b)", "2(a + b)"),
+            ("(a + b) * c", "(a + b)c"),
+            ("(a + b) * (c - d)", "(a + b)(c - d)"),
+            ("a * (b * c)", "a*(b*c)"),
+            ("a / b / c", "a/b/c"),
+            ("a / (b / c)", "a/(b/c)"),
+            ("a / b * c", "a/b*c"),
+            ("2 * e", "2*e"),
+            ("-x^2", "-x^2"),
+            ("(-x)^2", "(-x)^2"),
+            ("(x^2)^3", "(x^2)^3"),
+            ("x^2^3", "x^2^3"),
+            ("x^(y + 1)", "x^(y + 1)"),
+            ("2^(-x)", "2^(-x)"),
+            ("-(a + b)", "-(a + b)"),
+            ("-a * b", "-a*b"),
+            ("a * -b", "a*-b"),
+            ("a % (b * c)", "a%(b*c)"),
+            ("sin(x + 1)^2", "sin(x + 1)^2"),
+            ("log(x, 2) - 2 * sqrt(y)", "log(x, 2) - 2*sqrt(y)"),
+            ("v_0 * t", "v_0*t"),
+            ("x_1 * 2 * y", "x_1*2y"),
+            ("-2 * x", "-2x"),
+            ("(2/3) * x", "2/3*x"),
+            ("(1/2) * x", "1/2*x"),
+            ("(a/b) * c", "a/b*c"),
+            ("-(a/b)", "-a/b"),
+            ("-(a*b)", "-a*b"),
+            ("c - (a/b)", "c - a/b"),
+        ];
+
+        for (input, expected) in cases {
+            let element = parse(input);
+            assert_eq!(element.to_string(), expected, "{input}");
+            assert_round_trip(&element);
         }
+    }
 
-        match &self.node_or_expression {
-            NodeOrExpression::Expression(expression) => {
-                for product in &expression.products {
-                    result.push_str(&product.to_string());
-                }
+    /// A random tree with the shape the parser produces.
+    fn random_element(random: &mut Random, depth: usize) -> Element {
+        let leaf = depth == 0 || random.below(4) == 0;
+
+        match random.below(if leaf { 2 } else { 9 }) {
+            0 => Element::new(
+                Sign::Positive,
+                NodeOrExpression::Node(Node::Number(num::BigRational::from_integer(
+                    (random.below(12) as i64).into(),
+                ))),
+            ),
+            1 => {
+                let name = Identifier::new(["x", "y", "e", "v_0", "pi"][random.below(5)]);
+                let node = match Constant::from_identifier(&name) {
+                    Some(constant) => Node::Constant(constant),
+                    None => Node::Variable(name),
+                };
+                Element::new(Sign::Positive, NodeOrExpression::Node(node))
             }
-            NodeOrExpression::Node(node) => result += &node.to_string(),
+            2 => Element::simple_add(
+                random_element(random, depth - 1),
+                random_element(random, depth - 1),
+            ),
+            3 => Element::simple_sub(
+                random_element(random, depth - 1),
+                random_element(random, depth - 1),
+            ),
+            4 => Element::simple_mul(
+                random_element(random, depth - 1),
+                random_element(random, depth - 1),
+            ),
+            5 => Element::simple_div(
+                random_element(random, depth - 1),
+                random_element(random, depth - 1),
+            ),
+            6 => Element::new(
+                Sign::Positive,
+                NodeOrExpression::Node(Node::Power {
+                    base: Box::new(random_element(random, depth - 1)),
+                    power: Box::new(random_element(random, depth - 1)),
+                }),
+            ),
+            7 => Element::new(
+                Sign::Positive,
+                NodeOrExpression::Node(Node::Function {
+                    name: Identifier::new("f"),
+                    arguments: vec![random_element(random, depth - 1)],
+                }),
+            ),
+            _ => random_element(random, depth - 1).simple_neg(),
         }
+    }
 
-        if open_element {
-            result.push(')');
-        }
+    #[test]
+    fn test_random_round_trip() {
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
 
-        // println!("{result}");
-        write!(f, "{}", result)
+        for _ in 0..5_000 {
+            assert_round_trip(&random_element(&mut random, 5));
+        }
     }
 }
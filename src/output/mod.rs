@@ -1,3 +1,7 @@
 // pub mod debug_tree;
+pub mod equation_to_2d;
+pub mod equation_to_latex;
+pub mod equation_to_mathml;
 pub mod equation_to_rpn;
 pub mod equation_to_string;
+mod term;
@@ -0,0 +1,205 @@
+use num::{BigRational, One, Signed, Zero};
+
+use crate::ast::{
+    element::{IsTimesVisible, ShouldBeParenthesized},
+    product::Product,
+    Element, Expression, Node, NodeOrExpression, Sign,
+};
+
+/// An output format which only renders atoms, the sums, signs, fractions and factors around them
+/// are laid out the same way for every format.
+pub(crate) trait Render {
+    type Output;
+
+    /// A non-negative number.
+    fn number(&self, number: &BigRational) -> Self::Output;
+    fn node(&self, node: &Node) -> Self::Output;
+    fn negated(&self, body: Self::Output) -> Self::Output;
+    fn fraction(&self, numerator: Self::Output, denominator: Self::Output) -> Self::Output;
+    fn parenthesized(&self, body: Self::Output) -> Self::Output;
+    /// Joins the terms of a sum, each with its sign.
+    fn sum(&self, terms: Vec<(Sign, Self::Output)>) -> Self::Output;
+    /// Joins the factors of a product, each with whether the multiplication sign in front of it
+    /// is visible.
+    fn product(&self, factors: Vec<(bool, Self::Output)>) -> Self::Output;
+
+    fn element(&self, element: &Element) -> Self::Output {
+        self.term(&Term::from_element(element), true)
+    }
+
+    fn expression(&self, expression: &Expression) -> Self::Output {
+        if expression.products.is_empty() {
+            return self.number(&BigRational::zero());
+        }
+
+        let terms = expression
+            .products
+            .iter()
+            .map(|product| {
+                let term = Term::from_product(product);
+                (term.sign, self.term(&term, false))
+            })
+            .collect();
+
+        self.sum(terms)
+    }
+
+    /// Renders `term`, with the sign in front of it if `with_sign` is set.
+    fn term(&self, term: &Term, with_sign: bool) -> Self::Output {
+        let negative = term.sign == Sign::Negative;
+        // `a - (b + c)`, the sum has to be grouped even if it's alone
+        let group_alone = negative && term.denominator.is_empty();
+
+        let numerator = self.factors(&term.numerator, group_alone);
+        let body = if term.denominator.is_empty() {
+            numerator
+        } else {
+            self.fraction(numerator, self.factors(&term.denominator, false))
+        };
+
+        if with_sign && negative {
+            self.negated(body)
+        } else {
+            body
+        }
+    }
+
+    /// Joins factors, with a multiplication sign where [`IsTimesVisible`] asks for it and
+    /// parentheses where [`ShouldBeParenthesized`] does. A lone factor is only grouped if
+    /// `group_alone` is set. Factors of `1` are left out next to other factors.
+    fn factors(&self, factors: &[&Element], group_alone: bool) -> Self::Output {
+        let is_one = |factor: &&Element| {
+            factor.node_or_expression == NodeOrExpression::Node(Node::Number(BigRational::one()))
+        };
+        let factors = if factors.iter().all(is_one) {
+            factors.get(..1).unwrap_or_default().to_vec()
+        } else {
+            factors
+                .iter()
+                .filter(|factor| !is_one(factor))
+                .copied()
+                .collect()
+        };
+        if factors.is_empty() {
+            return self.number(&BigRational::one());
+        }
+
+        let factors = factors
+            .iter()
+            .enumerate()
+            .map(|(index, factor)| {
+                let mut body = match &factor.node_or_expression {
+                    NodeOrExpression::Node(Node::Number(number)) => self.number(&number.abs()),
+                    NodeOrExpression::Node(node) => self.node(node),
+                    NodeOrExpression::Expression(expression) => self.expression(expression),
+                };
+
+                if factor.should_be_parenthesized() && (factors.len() > 1 || group_alone) {
+                    body = self.parenthesized(body);
+                }
+
+                let times_visible = index > 0 && factor.is_times_visible(factors[index - 1]);
+                (times_visible, body)
+            })
+            .collect();
+
+        self.product(factors)
+    }
+
+    /// A power base or factorial operand, parenthesized unless it's an atom.
+    fn operand(&self, element: &Element) -> Self::Output {
+        let body = self.element(element);
+        if is_atom(element) {
+            body
+        } else {
+            self.parenthesized(body)
+        }
+    }
+
+    /// A number node, which unlike a factor keeps its sign.
+    fn signed_number(&self, number: &BigRational) -> Self::Output {
+        let magnitude = self.number(&number.abs());
+        if number.is_negative() {
+            self.negated(magnitude)
+        } else {
+            magnitude
+        }
+    }
+}
+
+/// A product flattened through nested single product expressions, with every sign (including
+/// the sign of negative numbers) pulled out, so `a * -(b / c)` is `-(a * b) / c`.
+#[derive(Default)]
+pub(crate) struct Term<'a> {
+    pub sign: Sign,
+    pub numerator: Vec<&'a Element>,
+    pub denominator: Vec<&'a Element>,
+}
+
+impl<'a> Term<'a> {
+    pub fn from_product(product: &'a Product) -> Self {
+        let mut term = Term::default();
+        term.collect_product(product, false);
+        term
+    }
+
+    pub fn from_element(element: &'a Element) -> Self {
+        let mut term = Term::default();
+        term.collect(element, false);
+        term
+    }
+
+    fn collect_product(&mut self, product: &'a Product, in_denominator: bool) {
+        for element in &product.numerator {
+            self.collect(element, in_denominator);
+        }
+        for element in &product.denominator {
+            self.collect(element, !in_denominator);
+        }
+    }
+
+    fn collect(&mut self, element: &'a Element, in_denominator: bool) {
+        self.sign = self.sign * element.sign;
+
+        match &element.node_or_expression {
+            NodeOrExpression::Expression(expression) if expression.products.len() == 1 => {
+                self.collect_product(&expression.products[0], in_denominator);
+                return;
+            }
+            NodeOrExpression::Node(Node::Number(number)) if number.is_negative() => {
+                self.sign = self.sign * Sign::Negative;
+            }
+            _ => {}
+        }
+
+        if in_denominator {
+            self.denominator.push(element);
+        } else {
+            self.numerator.push(element);
+        }
+    }
+}
+
+/// Returns whether `element` can be used as a power base or factorial operand without
+/// parentheses.
+fn is_atom(element: &Element) -> bool {
+    if element.sign == Sign::Negative {
+        return false;
+    }
+
+    match &element.node_or_expression {
+        NodeOrExpression::Node(Node::Number(number)) => {
+            number.is_integer() && !number.is_negative()
+        }
+        NodeOrExpression::Node(Node::Variable(_) | Node::Constant(_) | Node::Function { .. }) => {
+            true
+        }
+        NodeOrExpression::Node(_) => false,
+        NodeOrExpression::Expression(expression) => match expression.products.as_slice() {
+            [product] if product.denominator.is_empty() && product.numerator.len() == 1 => {
+                is_atom(&product.numerator[0])
+            }
+            _ => false,
+        },
+    }
+}
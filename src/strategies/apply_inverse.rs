@@ -2,16 +2,20 @@ use core::panic;
 use std::collections::HashMap;
 
 use itertools::Itertools;
+use num::One;
 use once_cell::sync::Lazy;
 
-use crate::ast::{product::Product, Element, Equation, Expression, Node, NodeOrExpression, Sign};
+use crate::actions::evaluate::Bindings;
+use crate::ast::{
+    product::Product, Element, Equation, Expression, Identifier, Node, NodeOrExpression, Sign,
+};
 
 use super::strategy::Strategy;
 
 #[derive(Debug, Clone)]
 pub enum EquationTransformation {
-    Function(String),
-    InverseFunction(String),
+    Function(Identifier),
+    InverseFunction(Identifier),
     Multiply {
         multiply: Product,
         side_with_variable: Product,
@@ -36,6 +40,19 @@ fn apply_inverse(equation: &mut Equation) -> Vec<String> {
 
     let mut constraints = vec![];
 
+    // inverting a side only isolates the unknown if the other side doesn't contain it
+    let sides_with_variables = equation
+        .equation_sides
+        .iter()
+        .filter(|side| match &side.cache {
+            Some(cache) => !cache.variables.is_empty(),
+            None => panic!("Equation has not been analyzed, cannot simplify"),
+        })
+        .count();
+    if sides_with_variables != 1 {
+        return constraints;
+    }
+
     let mut inverse = None;
 
     for side_element in &mut equation.equation_sides {
@@ -112,9 +129,9 @@ fn get_element_inverse(element: &Element) -> Option<TransformResult> {
                 }
             }
             Node::Function { name, arguments: _ } => {
-                if let Some(value) = INVERSE_FUNCTIONS.get(name) {
+                if let Some(value) = name.as_plain().and_then(|name| INVERSE_FUNCTIONS.get(name)) {
                     constraints.extend(value.1.clone());
-                    Some(EquationTransformation::Function(value.0.clone()))
+                    Some(EquationTransformation::Function(Identifier::new(&value.0)))
                 } else {
                     Some(EquationTransformation::InverseFunction(name.clone()))
                 }
@@ -159,8 +176,16 @@ fn one_product(product: &Product, constraints: &mut Vec<String>) -> Option<(Prod
     {
         for pr_elem in side {
             match &pr_elem.cache {
-                Some(cache) => match cache.variables.len() {
-                    0 => {
+                Some(cache) => {
+                    if cache.variables.is_empty() {
+                        // moving a factor of one to the other side changes nothing
+                        if pr_elem
+                            .evaluate(&Bindings::new())
+                            .is_ok_and(|value| value.is_one())
+                        {
+                            continue;
+                        }
+
                         if side_pos == 0 {
                             new_product.denominator.push(pr_elem.clone());
                         } else if side_pos == 1 {
@@ -168,18 +193,14 @@ fn one_product(product: &Product, constraints: &mut Vec<String>) -> Option<(Prod
                         } else {
                             panic!("Side position is wrong");
                         }
+                    } else if side_pos == 0 {
+                        pr_with_variable.numerator.push(pr_elem.clone());
+                    } else if side_pos == 1 {
+                        pr_with_variable.denominator.push(pr_elem.clone());
+                    } else {
+                        panic!("Side position is wrong");
                     }
-                    1 => {
-                        if side_pos == 0 {
-                            pr_with_variable.numerator.push(pr_elem.clone());
-                        } else if side_pos == 1 {
-                            pr_with_variable.denominator.push(pr_elem.clone());
-                        } else {
-                            panic!("Side position is wrong");
-                        }
-                    }
-                    _ => panic!("Too many variables when getting inverse"),
-                },
+                }
                 None => panic!("Element should be analyzed when applying inverse"),
             }
         }
@@ -228,7 +249,7 @@ fn multiple_products(expression: &Expression) -> Option<(Expression, Expression)
             for pr_elem in side {
                 match &pr_elem.cache {
                     Some(cache) => {
-                        if cache.variables.len() == 1 {
+                        if !cache.variables.is_empty() {
                             skip_product = true;
                             break;
                         }
@@ -242,9 +263,9 @@ fn multiple_products(expression: &Expression) -> Option<(Expression, Expression)
             expr_with_variable.products.push(product.clone());
         } else {
             let mut new_product = product.clone();
-            let pr_elem = if new_product.numerator.len() > 0 {
+            let pr_elem = if !new_product.numerator.is_empty() {
                 new_product.numerator.first_mut().unwrap()
-            } else if new_product.numerator.len() > 0 {
+            } else if !new_product.denominator.is_empty() {
                 new_product.denominator.first_mut().unwrap()
             } else {
                 panic!("Product shouldn't be empty");
@@ -268,11 +289,7 @@ fn transform_equation(equation: &mut Equation, inverse: EquationTransformation)
 
     for side in &mut equation.equation_sides {
         let is_side_with_variable = if let Some(cache) = &side.cache {
-            match cache.variables.len() {
-                0 => false,
-                1 => true,
-                _ => panic!("Too many variables"),
-            }
+            !cache.variables.is_empty()
         } else {
             panic!("Not analyzed");
         };
@@ -0,0 +1,95 @@
+use num::{BigRational, Zero};
+
+use crate::{
+    actions::canonicalize::{Key, Term},
+    ast::{product::Product, Element, Expression, NodeOrExpression},
+};
+
+/// Adds up the products of an expression that only differ in their rational coefficient, like
+/// `2x + 3x = 5x` and `a*b - b*a = 0`. Terms whose coefficients cancel are dropped.
+pub fn collect_like_terms(element: Element) -> Element {
+    let NodeOrExpression::Expression(expression) = &element.node_or_expression else {
+        return element;
+    };
+
+    let mut groups: Vec<(Key, Vec<&Product>)> = vec![];
+    for product in &expression.products {
+        let key = Term::new(product).key();
+        match groups.iter_mut().find(|(group_key, _)| *group_key == key) {
+            Some((_, products)) => products.push(product),
+            None => groups.push((key, vec![product])),
+        }
+    }
+
+    if groups.len() == expression.products.len() {
+        return element;
+    }
+
+    let mut products = vec![];
+    for (_, group) in groups {
+        let [product] = group.as_slice() else {
+            let mut terms = group.into_iter().map(Term::new);
+            let mut sum = terms.next().unwrap();
+            sum.coefficient = terms.fold(sum.coefficient, |total, term| total + term.coefficient);
+
+            if !sum.coefficient.is_zero() {
+                products.push(sum.into_product());
+            }
+            continue;
+        };
+
+        products.push((*product).clone());
+    }
+
+    if products.is_empty() {
+        products.push(Product::new(
+            vec![Element::number(BigRational::zero())],
+            vec![],
+        ));
+    }
+
+    Element::new(
+        element.sign,
+        NodeOrExpression::Expression(Expression::new(products)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::parse;
+    use crate::{ast::Equation, strategies::flatten::get_flatten};
+
+    fn collect(input: &str) -> String {
+        let mut equation = Equation {
+            equation_sides: vec![parse(input)],
+            ..Default::default()
+        };
+        get_flatten().apply.unwrap()(&mut equation);
+
+        equation.equation_sides[0]
+            .clone()
+            .apply_to_every_element_into(&mut collect_like_terms, false, None)
+            .to_string()
+    }
+
+    #[test]
+    fn test_collect_like_terms() {
+        let cases = [
+            ("2x + 3x", "5x"),
+            ("a*b - b*a", "0"),
+            ("x + y - x", "y"),
+            ("x/2 + x/3", "5/6*x"),
+            ("2 + x + 3", "5 + x"),
+            ("x*y + 2*y*x + z", "3x*y + z"),
+            ("x - 2x", "-x"),
+            ("x/y + 1/y", "x/y + 1/y"),
+            ("sin(x) + sin(x)", "2*sin(x)"),
+            ("x + x^2", "x + x^2"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(collect(input), expected, "{input}");
+        }
+    }
+}
@@ -1,5 +1,3 @@
-use itertools::Itertools;
-
 use crate::ast::{product::Product, Element, Equation, Expression, NodeOrExpression, Sign};
 
 use super::strategy::Strategy;
@@ -73,10 +71,11 @@ fn process_inner_element(
             if inner_expression.products.len() == 1 {
                 transfer_products(inner_expression.clone(), new_products, side_pos);
             } else {
-                if is_surrounded {
+                if is_surrounded || side_pos == 1 {
                     move_element_to_products(inner_element.clone(), new_products, side_pos);
                 } else {
-                    transfer_elements_in_products(inner_expression.clone(), new_products, side_pos);
+                    // a sum on its own is lifted into the outer sum, `(a*b + c) + d`
+                    new_products.extend(inner_expression.products.clone());
                 }
             }
         }
@@ -133,27 +132,3 @@ fn transfer_products(
 
     new_products.push(new_product);
 }
-
-fn transfer_elements_in_products(
-    inner_expression: Expression,
-    new_products: &mut Vec<Product>,
-    side_pos: usize,
-) {
-    for inner_product in inner_expression.products {
-        for side in [inner_product.numerator, inner_product.denominator] {
-            let products = if side_pos == 0 {
-                side.into_iter()
-                    .map(|elem| Product::new(vec![elem], vec![]))
-                    .collect_vec()
-            } else if side_pos == 1 {
-                side.into_iter()
-                    .map(|elem| Product::new(vec![], vec![elem]))
-                    .collect_vec()
-            } else {
-                panic!("Too many ratio sides");
-            };
-
-            new_products.extend(products);
-        }
-    }
-}
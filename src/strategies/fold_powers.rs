@@ -0,0 +1,375 @@
+use num::{BigInt, BigRational, Integer, One, Signed, ToPrimitive, Zero};
+
+use crate::{
+    actions::evaluate::{Bindings, EvalError},
+    ast::{product::Product, Element, Expression, Node, NodeOrExpression, Sign},
+};
+
+/// Trial divisors above this aren't tried when pulling perfect powers out of radicals.
+const MAX_DIVISOR: u32 = 10_000;
+
+fn power(base: Element, exponent: Element) -> Element {
+    Element::new(
+        Sign::Positive,
+        NodeOrExpression::Node(Node::Power {
+            base: Box::new(base),
+            power: Box::new(exponent),
+        }),
+    )
+}
+
+/// Splits `number` into `outside^degree * inside` with `inside` free of perfect powers, as far
+/// as they can be found by trial division.
+fn extract_perfect_power(number: &BigInt, degree: u32) -> (BigInt, BigInt) {
+    let mut outside = BigInt::one();
+    let mut inside = number.clone();
+
+    for divisor in 2..=MAX_DIVISOR {
+        let factor = num::pow(BigInt::from(divisor), degree as usize);
+        if factor > inside {
+            break;
+        }
+        while inside.is_multiple_of(&factor) {
+            inside /= &factor;
+            outside *= divisor;
+        }
+    }
+
+    (outside, inside)
+}
+
+/// Writes the `degree`-th root of a positive rational as `a*root(b)`, like
+/// `sqrt(12) = 2*sqrt(3)`. `radical` builds the root of the remaining number.
+fn simplify_radical(
+    radicand: &BigRational,
+    degree: u32,
+    radical: impl FnOnce(Element) -> Element,
+) -> Option<Element> {
+    if !radicand.is_positive() {
+        return None;
+    }
+
+    let (outside, inside) = split_radicand(radicand, degree);
+    if outside.is_one() {
+        return None;
+    }
+
+    Some(Element::simple_mul(
+        Element::number(outside),
+        radical(Element::number(inside)),
+    ))
+}
+
+/// Splits a positive rational into `outside^degree * inside`, like `12 = 2^2 * 3`, so its
+/// `degree`-th root is `outside*root(inside)`.
+pub(crate) fn split_radicand(radicand: &BigRational, degree: u32) -> (BigRational, BigRational) {
+    let (numer_outside, numer_inside) = extract_perfect_power(radicand.numer(), degree);
+    let (denom_outside, denom_inside) = extract_perfect_power(radicand.denom(), degree);
+
+    (
+        BigRational::new(numer_outside, denom_outside),
+        BigRational::new(numer_inside, denom_inside),
+    )
+}
+
+/// Folds numeric powers, `x^1`, `x^0` and `(x^a)^n` for an integer `n`.
+fn fold_power(element: Element, base: &Element, exponent: &Element) -> Element {
+    let bindings = Bindings::new();
+    let sign = element.sign;
+
+    match (base.evaluate(&bindings), exponent.evaluate(&bindings)) {
+        (Ok(base), Ok(exponent)) => {
+            return match element.evaluate(&bindings) {
+                Ok(value) => Element::number(value),
+                Err(EvalError::Irrational) if exponent.numer().is_one() => exponent
+                    .denom()
+                    .to_u32()
+                    .and_then(|degree| {
+                        simplify_radical(&base, degree, |inside| {
+                            power(inside, Element::number(exponent.clone()))
+                        })
+                    })
+                    .map_or(element, |result| result.simple_mul_sign(sign)),
+                Err(_) => element,
+            };
+        }
+        (Ok(base), _) if base.is_one() => {
+            return Element::number(BigRational::one()).simple_mul_sign(sign)
+        }
+        (_, Ok(exponent)) if exponent.is_one() => return base.clone().simple_mul_sign(sign),
+        (_, Ok(exponent)) if exponent.is_zero() => {
+            return Element::number(BigRational::one()).simple_mul_sign(sign)
+        }
+        _ => (),
+    }
+
+    // `(x^a)^n = x^(a*n)` only holds for integers `n`, `(x^2)^(1/2)` is `|x|`
+    let NodeOrExpression::Node(Node::Power {
+        base: inner_base,
+        power: inner_exponent,
+    }) = &base.node_or_expression
+    else {
+        return element;
+    };
+    let is_integer = exponent
+        .evaluate(&bindings)
+        .is_ok_and(|exponent| exponent.is_integer());
+    if base.sign == Sign::Negative || !is_integer {
+        return element;
+    }
+
+    let product = Element::simple_mul(*inner_exponent.clone(), exponent.clone());
+    let product = match product.evaluate(&bindings) {
+        Ok(value) => Element::number(value),
+        Err(_) => product,
+    };
+
+    fold_power(
+        power(*inner_base.clone(), product.clone()).simple_mul_sign(sign),
+        inner_base,
+        &product,
+    )
+}
+
+/// Factors with the same base, raised to some power.
+struct Group {
+    base: Element,
+    key: Element,
+    slot: usize,
+    count: usize,
+    numeric: BigRational,
+    symbolic: Vec<Element>,
+}
+
+enum Slot {
+    Kept(usize, Element),
+    Group(usize),
+}
+
+/// Merges the factors with the same base in `product`, `x^a * x^b = x^(a+b)`, with the ones in
+/// the denominator counted negatively.
+fn fold_product(product: Product) -> Product {
+    let bindings = Bindings::new();
+    let mut groups: Vec<Group> = vec![];
+    let mut slots = vec![];
+
+    for (side_pos, side) in [product.numerator, product.denominator]
+        .into_iter()
+        .enumerate()
+    {
+        for factor in side {
+            let (base, exponent) = match &factor.node_or_expression {
+                _ if factor.sign == Sign::Negative => {
+                    slots.push(Slot::Kept(side_pos, factor));
+                    continue;
+                }
+                NodeOrExpression::Node(Node::Number(_)) => {
+                    slots.push(Slot::Kept(side_pos, factor));
+                    continue;
+                }
+                NodeOrExpression::Node(Node::Power { base, power }) => {
+                    (*base.clone(), *power.clone())
+                }
+                _ => (factor.clone(), Element::number(BigRational::one())),
+            };
+            let exponent = if side_pos == 0 {
+                exponent
+            } else {
+                exponent.simple_neg()
+            };
+
+            let key = base.without_cache();
+            let index = match groups.iter().position(|group| group.key == key) {
+                Some(index) => index,
+                None => {
+                    groups.push(Group {
+                        base,
+                        key,
+                        slot: slots.len(),
+                        count: 0,
+                        numeric: BigRational::zero(),
+                        symbolic: vec![],
+                    });
+                    slots.push(Slot::Kept(side_pos, factor));
+                    groups.len() - 1
+                }
+            };
+
+            let group = &mut groups[index];
+            group.count += 1;
+            match exponent.evaluate(&bindings) {
+                Ok(value) => group.numeric += value,
+                Err(_) => group.symbolic.push(exponent),
+            }
+            if group.count == 2 {
+                // the first factor of the group is replaced by the merged one
+                slots[group.slot] = Slot::Group(index);
+            }
+        }
+    }
+
+    let mut result = Product::new(vec![], vec![]);
+    for slot in slots {
+        match slot {
+            Slot::Kept(0, factor) => result.numerator.push(factor),
+            Slot::Kept(_, factor) => result.denominator.push(factor),
+            Slot::Group(index) => {
+                let Group {
+                    base,
+                    numeric,
+                    symbolic,
+                    ..
+                } = &groups[index];
+
+                if !symbolic.is_empty() {
+                    let mut terms = symbolic.clone();
+                    if !numeric.is_zero() {
+                        terms.push(Element::number(numeric.clone()));
+                    }
+                    let exponent = Expression::new(
+                        terms
+                            .into_iter()
+                            .map(|term| Product::new(vec![term], vec![]))
+                            .collect(),
+                    );
+                    result.numerator.push(power(
+                        base.clone(),
+                        Element::new(Sign::Positive, NodeOrExpression::Expression(exponent)),
+                    ));
+                    continue;
+                }
+
+                let side = if numeric.is_negative() {
+                    &mut result.denominator
+                } else {
+                    &mut result.numerator
+                };
+                match numeric.abs() {
+                    magnitude if magnitude.is_zero() => (),
+                    magnitude if magnitude.is_one() => side.push(base.clone()),
+                    magnitude => side.push(power(base.clone(), Element::number(magnitude))),
+                }
+            }
+        }
+    }
+
+    if result.numerator.is_empty() {
+        result.numerator.push(Element::number(BigRational::one()));
+    }
+
+    result
+}
+
+/// Simplifies the powers in `element` itself, its children are expected to be folded already:
+///
+/// - numeric powers are computed exactly, `2^3 = 8` and `4^(1/2) = 2`
+/// - perfect powers are pulled out of radicals, `sqrt(12) = 2*sqrt(3)`
+/// - `x^1 = x`, `x^0 = 1` and `(x^2)^3 = x^6`
+/// - factors with the same base are merged, `x*x = x^2` and `x^3/x = x^2`
+pub fn fold_powers(element: Element) -> Element {
+    let sign = element.sign;
+
+    match &element.node_or_expression {
+        NodeOrExpression::Node(Node::Power { base, power }) => {
+            let (base, power) = (base.clone(), power.clone());
+            fold_power(element, &base, &power)
+        }
+        NodeOrExpression::Node(Node::Function { name, arguments }) => {
+            let degree = match name.as_plain() {
+                Some("sqrt") => 2,
+                Some("cbrt") => 3,
+                _ => return element,
+            };
+            let [argument] = arguments.as_slice() else {
+                return element;
+            };
+            let Ok(radicand) = argument.evaluate(&Bindings::new()) else {
+                return element;
+            };
+
+            if let Ok(value) = element.evaluate(&Bindings::new()) {
+                return Element::number(value);
+            }
+
+            simplify_radical(&radicand, degree, |inside| {
+                Element::new(
+                    Sign::Positive,
+                    NodeOrExpression::Node(Node::Function {
+                        name: name.clone(),
+                        arguments: vec![inside],
+                    }),
+                )
+            })
+            .map_or(element, |result| result.simple_mul_sign(sign))
+        }
+        NodeOrExpression::Expression(expression) => {
+            let expression = Expression::new(
+                expression
+                    .products
+                    .iter()
+                    .cloned()
+                    .map(fold_product)
+                    .collect(),
+            );
+            Element::new(sign, NodeOrExpression::Expression(expression))
+        }
+        NodeOrExpression::Node(_) => element,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::parse;
+    use crate::{ast::Equation, strategies::flatten::get_flatten};
+
+    /// Flattens the parsed products first, `x*y*x` is parsed as `(x*y)*x`.
+    fn fold(input: &str) -> String {
+        let mut equation = Equation {
+            equation_sides: vec![parse(input)],
+            ..Default::default()
+        };
+        let mut flatten = get_flatten().apply.unwrap();
+        for _ in 0..3 {
+            flatten(&mut equation);
+        }
+
+        equation.equation_sides[0]
+            .clone()
+            .apply_to_every_element_into(&mut fold_powers, false, None)
+            .to_string()
+    }
+
+    #[test]
+    fn test_fold_powers() {
+        let cases = [
+            ("2^3", "8"),
+            ("(-2)^3", "-8"),
+            ("2^-2", "1/4"),
+            ("4^(1/2)", "2"),
+            ("x^1", "x"),
+            ("x^0", "1"),
+            ("1^x", "1"),
+            ("(x^2)^3", "x^6"),
+            ("(x^a)^2", "x^(a*2)"),
+            ("(x^2)^(1/2)", "(x^2)^(1/2)"),
+            ("x*x", "x^2"),
+            ("x^3/x", "x^2"),
+            ("x/x^3", "1/x^2"),
+            ("x*y*x", "x^2*y"),
+            ("x*y/x", "y"),
+            ("x/x", "1"),
+            ("x^a*x^b", "x^(a + b)"),
+            ("2^(1/2)*2^(1/2)", "2"),
+            ("sqrt(12)", "2*sqrt(3)"),
+            ("sqrt(9/8)", "3/2*sqrt(1/2)"),
+            ("cbrt(-8)", "-2"),
+            ("12^(1/2)", "2*3^(1/2)"),
+            ("sqrt(7) + sqrt(x)", "sqrt(7) + sqrt(x)"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(fold(input), expected, "{input}");
+        }
+    }
+}
@@ -1,4 +1,6 @@
 pub mod apply_inverse;
+pub mod collect_like_terms;
 pub mod flatten;
+pub mod fold_powers;
 pub mod simplify;
 pub mod strategy;
@@ -1,10 +1,168 @@
-use crate::ast::{product::Product, Element, Equation, Expression, Node, NodeOrExpression, Sign};
+use num::{BigRational, Integer, One, ToPrimitive, Zero};
+
+use crate::{
+    actions::evaluate::Bindings,
+    ast::{
+        product::Product, rational_function::RationalFunction, Constant, Element, Equation,
+        Expression, Node, NodeOrExpression, Sign,
+    },
+};
+
+use super::{collect_like_terms::collect_like_terms, fold_powers::fold_powers, strategy::Strategy};
+
+/// `k` if `element` is `k·pi` for a rational `k`, zero counts as `0·pi`.
+fn multiple_of_pi(element: &Element) -> Option<BigRational> {
+    let bindings = Bindings::new();
+    if let Ok(value) = element.evaluate(&bindings) {
+        return value.is_zero().then(BigRational::zero);
+    }
+
+    let is_pi = |factor: &Element| {
+        factor.node_or_expression == NodeOrExpression::Node(Node::Constant(Constant::Pi))
+    };
+
+    let multiple = match &element.node_or_expression {
+        NodeOrExpression::Node(_) => is_pi(element).then(BigRational::one)?,
+        NodeOrExpression::Expression(expression) => {
+            let [product] = expression.products.as_slice() else {
+                return None;
+            };
+            let position = product.numerator.iter().position(is_pi)?;
+
+            let mut rest = product.clone();
+            let pi = rest.numerator.remove(position);
+            let multiple = rest.evaluate(&bindings).ok()?;
+            match pi.sign {
+                Sign::Positive => multiple,
+                Sign::Negative => -multiple,
+            }
+        }
+    };
+
+    Some(match element.sign {
+        Sign::Positive => multiple,
+        Sign::Negative => -multiple,
+    })
+}
+
+fn number(sign: Sign, value: i64) -> Element {
+    Element::number(BigRational::from_integer(value.into())).simple_mul_sign(sign)
+}
+
+/// Replaces powers and functions of constants with known values, like `e^0 = 1`, `i^2 = -1`,
+/// `ln(e) = 1` and `sin(pi) = 0`.
+fn apply_constant_identities(element: Element) -> Element {
+    let NodeOrExpression::Node(node) = &element.node_or_expression else {
+        return element;
+    };
+    let sign = element.sign;
+
+    match node {
+        Node::Power { base, power } => {
+            let NodeOrExpression::Node(Node::Constant(constant)) = &base.node_or_expression else {
+                return element;
+            };
+            let Ok(exponent) = power.evaluate(&Bindings::new()) else {
+                return element;
+            };
+            if base.sign == Sign::Negative || !exponent.is_integer() {
+                return element;
+            }
+
+            let i = |sign| Element::new(sign, NodeOrExpression::Node(Node::Constant(Constant::I)));
+            match constant {
+                // the powers of i repeat every four steps
+                Constant::I => match exponent.to_integer().mod_floor(&4.into()).to_u8() {
+                    Some(0) => number(sign, 1),
+                    Some(1) => i(sign),
+                    Some(2) => number(sign * Sign::Negative, 1),
+                    _ => i(sign * Sign::Negative),
+                },
+                Constant::Pi | Constant::E if exponent.is_zero() => number(sign, 1),
+                _ => element,
+            }
+        }
+        Node::Function { name, arguments } => {
+            let [argument] = arguments.as_slice() else {
+                return element;
+            };
+
+            if name.as_plain() == Some("ln")
+                && argument.sign == Sign::Positive
+                && argument.node_or_expression
+                    == NodeOrExpression::Node(Node::Constant(Constant::E))
+            {
+                return number(sign, 1);
+            }
+
+            let Some(multiple) = multiple_of_pi(argument) else {
+                return element;
+            };
+            // `sin` and `cos` at the quarter periods `k·pi/2`
+            let halves = multiple * BigRational::from_integer(2.into());
+            if !halves.is_integer() {
+                return element;
+            }
+            let quarter = halves.to_integer().mod_floor(&4.into());
+
+            match (name.as_plain(), quarter.to_u8()) {
+                (Some("sin" | "tan"), Some(0 | 2)) | (Some("cos"), Some(1 | 3)) => number(sign, 0),
+                (Some("sin"), Some(1)) | (Some("cos"), Some(0)) => number(sign, 1),
+                (Some("sin"), Some(3)) | (Some("cos"), Some(2)) => number(sign * Sign::Negative, 1),
+                _ => element,
+            }
+        }
+        _ => element,
+    }
+}
 
-use super::strategy::Strategy;
+/// Cancels the common factors of fractions of polynomials, `(x^2 - 1)/(x - 1) = x + 1`. Other
+/// elements are kept as they are written.
+///
+/// The cancelled element is undefined where the divisor is zero, so `divisor != 0` is pushed to
+/// `constraints`.
+fn cancel_fractions(element: Element, constraints: &mut Vec<String>) -> Element {
+    if !element.has_denominator() {
+        return element;
+    }
+    let Ok(function) = RationalFunction::from_element(&element) else {
+        return element;
+    };
+
+    let cancelled = function.cancel();
+    if cancelled == function {
+        element
+    } else {
+        let divisor = function.numerator.gcd(&function.denominator);
+        constraints.push(format!("{divisor} != 0"));
+        cancelled.to_element()
+    }
+}
 
 // assume that it has been analysed
 fn simplify_equation(equation: &mut Equation) -> Vec<String> {
+    let mut constraints = vec![];
+
     for side_element in &mut equation.equation_sides {
+        *side_element = side_element.clone().apply_to_every_element_into(
+            &mut apply_constant_identities,
+            false,
+            None,
+        );
+        *side_element =
+            side_element
+                .clone()
+                .apply_to_every_element_into(&mut fold_powers, false, None);
+        *side_element =
+            side_element
+                .clone()
+                .apply_to_every_element_into(&mut collect_like_terms, false, None);
+        *side_element = side_element.clone().apply_to_every_element_into(
+            &mut |element| cancel_fractions(element, &mut constraints),
+            false,
+            None,
+        );
+
         // debug!("{}", side_element.rpn());
 
         side_element.apply_to_every_element_mut(
@@ -174,7 +332,7 @@ fn simplify_equation(equation: &mut Equation) -> Vec<String> {
         }
     }
 
-    vec![]
+    constraints
 }
 
 pub fn get_simplify() -> Strategy {
@@ -183,3 +341,82 @@ pub fn get_simplify() -> Strategy {
         check: None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Equation;
+    use crate::test_utils::parse;
+
+    #[test]
+    fn test_constant_identities() {
+        let cases = [
+            ("e^0 + pi^0", "1 + 1"),
+            ("i^2", "-1"),
+            ("i^7 + i^-1 + i^4", "-i - i + 1"),
+            ("ln(e)", "1"),
+            ("sin(pi) + cos(3pi)", "0 - 1"),
+            ("-sin(pi/2) + sin(-pi/2)", "-1 - 1"),
+            ("cos(pi/2) + tan(2pi) + sin(0)", "0 + 0 + 0"),
+            (
+                "sin(pi/3) + cos(x) + e^x + i^(1/2)",
+                "sin(pi/3) + cos(x) + e^x + i^(1/2)",
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let element = parse(input).apply_to_every_element_into(
+                &mut apply_constant_identities,
+                false,
+                None,
+            );
+
+            assert_eq!(element.to_string(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_simplify() {
+        let cases = [
+            ("x - 1", "x - 1"),
+            ("x - 2/4", "x - 1/2"),
+            ("2*3 - x", "6 - x"),
+            ("x - 2i", "x - 2i"),
+        ];
+
+        for (input, expected) in cases {
+            let mut equation = Equation {
+                equation_sides: vec![parse(input)],
+                ..Default::default()
+            };
+            simplify_equation(&mut equation);
+
+            assert_eq!(equation.equation_sides[0].to_string(), expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_cancel_fractions() {
+        let cases = [
+            ("(x^2 - 1)/(x - 1)", "x + 1", vec!["x - 1 != 0"]),
+            ("(x^2 - 1)/(x - 1) + 1", "x + 1 + 1", vec!["x - 1 != 0"]),
+            ("sin((x^2 - 1)/(x + 1))", "sin(x - 1)", vec!["x + 1 != 0"]),
+            ("x/x", "1", vec!["x != 0"]),
+            ("(x + 1)/(x - 1)", "(x + 1)/(x - 1)", vec![]),
+            ("x/2 + y", "x/2 + y", vec![]),
+            ("sin(x)/sin(x)", "sin(x)/sin(x)", vec![]),
+        ];
+
+        for (input, expected, expected_constraints) in cases {
+            let mut constraints = vec![];
+            let element = parse(input).apply_to_every_element_into(
+                &mut |element| cancel_fractions(element, &mut constraints),
+                false,
+                None,
+            );
+
+            assert_eq!(element.to_string(), expected, "{input}");
+            assert_eq!(constraints, expected_constraints, "{input}");
+        }
+    }
+}
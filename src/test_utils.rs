@@ -0,0 +1,32 @@
+//! Helpers shared by the tests of several modules.
+
+use crate::ast::{equation::NoContextEquation, polynomial::Polynomial, Element};
+
+/// Small xorshift generator, so the property tests are reproducible without extra crates.
+pub(crate) struct Random(pub u64);
+
+impl Random {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number in `0..bound`.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+/// The first side of the equation `input`, a parse error is rendered in the panic message.
+pub(crate) fn parse(input: &str) -> Element {
+    let mut equation = NoContextEquation::try_from(input)
+        .unwrap_or_else(|error| panic!("{}", error.render(input)));
+    equation.sides.remove(0).element
+}
+
+/// The first side of the equation `input` as a polynomial.
+pub(crate) fn polynomial(input: &str) -> Polynomial {
+    Polynomial::from_element(&parse(input)).unwrap()
+}
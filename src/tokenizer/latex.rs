@@ -0,0 +1,829 @@
+use crate::ast::Identifier;
+
+use super::{
+    parser::{ParseError, TokenizedString},
+    small_parsers::parse_number,
+    span::Span,
+    token::{Operation, Token},
+};
+
+/// Marks a formula as LaTeX, e.g. `Latex(r"\frac{a}{b} \le \sqrt[3]{x}")`.
+///
+/// It's tokenized into the same tokens as plain formulas, so it can be used wherever a `&str` is
+/// accepted, like [`App::try_add_equation`](crate::ast::app::App::try_add_equation).
+#[derive(Debug, Clone, Copy)]
+pub struct Latex<'a>(pub &'a str);
+
+impl TryFrom<Latex<'_>> for TokenizedString {
+    type Error = ParseError;
+
+    fn try_from(latex: Latex<'_>) -> Result<TokenizedString, ParseError> {
+        let mut tokenizer = LatexTokenizer::new(latex.0);
+        tokenizer.expression(Closing::End, Span::new(0, latex.0.len()))?;
+
+        Ok(TokenizedString::new_from_tokens_and_spans(
+            tokenizer.tokens,
+            tokenizer.spans,
+        ))
+    }
+}
+
+/// Functions which are written as commands, e.g. `\sin x`.
+pub(crate) static FUNCTIONS: [&str; 16] = [
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh",
+    "coth", "exp", "ln", "log",
+];
+
+pub(crate) static GREEK_LETTERS: [(&str, char); 34] = [
+    ("alpha", 'α'),
+    ("beta", 'β'),
+    ("gamma", 'γ'),
+    ("delta", 'δ'),
+    ("epsilon", 'ε'),
+    ("varepsilon", 'ε'),
+    ("zeta", 'ζ'),
+    ("eta", 'η'),
+    ("theta", 'θ'),
+    ("vartheta", 'ϑ'),
+    ("iota", 'ι'),
+    ("kappa", 'κ'),
+    ("lambda", 'λ'),
+    ("mu", 'μ'),
+    ("nu", 'ν'),
+    ("xi", 'ξ'),
+    ("rho", 'ρ'),
+    ("sigma", 'σ'),
+    ("tau", 'τ'),
+    ("upsilon", 'υ'),
+    ("phi", 'φ'),
+    ("varphi", 'φ'),
+    ("chi", 'χ'),
+    ("psi", 'ψ'),
+    ("omega", 'ω'),
+    ("Gamma", 'Γ'),
+    ("Delta", 'Δ'),
+    ("Theta", 'Θ'),
+    ("Lambda", 'Λ'),
+    ("Xi", 'Ξ'),
+    ("Sigma", 'Σ'),
+    ("Phi", 'Φ'),
+    ("Psi", 'Ψ'),
+    ("Omega", 'Ω'),
+];
+
+/// Named constants and the identifier they are read as, the same ones `π` and `∞` map to.
+pub(crate) static CONSTANTS: [(&str, &str); 2] = [("pi", "pi"), ("infty", "infinity")];
+
+static BINARY_COMMANDS: [(&str, Operation); 13] = [
+    ("cdot", Operation::Multiply),
+    ("times", Operation::Multiply),
+    ("div", Operation::Divide),
+    ("bmod", Operation::Mod),
+    ("mod", Operation::Mod),
+    ("le", Operation::LessThanOrEqual),
+    ("leq", Operation::LessThanOrEqual),
+    ("ge", Operation::GreaterThanOrEqual),
+    ("geq", Operation::GreaterThanOrEqual),
+    ("ne", Operation::NotEqual),
+    ("neq", Operation::NotEqual),
+    ("lt", Operation::LessThan),
+    ("gt", Operation::GreaterThan),
+];
+
+static SPACING_COMMANDS: [&str; 7] = [",", ";", ":", "!", " ", "quad", "qquad"];
+
+/// What ends the expression which is being tokenized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Closing {
+    End,
+    /// `}`
+    Brace,
+    /// `]` of an optional argument like the index in `\sqrt[3]{x}`
+    Bracket,
+    /// `)` or `]`, optionally written as `\right)`
+    Parenthesis,
+}
+
+struct LatexTokenizer<'a> {
+    input: &'a str,
+    position: usize,
+    tokens: Vec<Token>,
+    spans: Vec<Span>,
+}
+
+impl<'a> LatexTokenizer<'a> {
+    fn new(input: &'a str) -> Self {
+        LatexTokenizer {
+            input,
+            position: 0,
+            tokens: vec![],
+            spans: vec![],
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn push(&mut self, token: Token, span: Span) {
+        self.tokens.push(token);
+        self.spans.push(span);
+    }
+
+    /// Consumes `length` bytes and returns their span.
+    fn advance(&mut self, length: usize) -> Span {
+        let span = Span::new(self.position, self.position + length);
+        self.position += length;
+        span
+    }
+
+    /// Returns the name of the command at the current position and its length with the
+    /// backslash, e.g. `("frac", 5)` for `\frac{1}{2}` and `(",", 2)` for `\,`.
+    fn peek_command(&self) -> Option<(&'a str, usize)> {
+        let name = self.rest().strip_prefix('\\')?;
+
+        let length = name
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(name.len());
+        let length = match length {
+            0 => name.chars().next()?.len_utf8(),
+            length => length,
+        };
+
+        Some((&name[..length], length + 1))
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            let trimmed = self.rest().trim_start();
+            self.position = self.input.len() - trimmed.len();
+
+            match self.peek_command() {
+                Some((name, length)) if SPACING_COMMANDS.contains(&name) => {
+                    self.advance(length);
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Consumes the closing delimiter of `closing` if it's next. Closing delimiters which don't
+    /// belong to `closing` are an error.
+    fn closing(&mut self, closing: Closing) -> Result<Option<Span>, ParseError> {
+        let rest = self.rest();
+
+        let (kind, length) = if let Some(right) = rest.strip_prefix("\\right") {
+            match right.chars().next() {
+                Some(')' | ']' | '.') => (Closing::Parenthesis, 7),
+                _ if right.starts_with("\\}") => (Closing::Parenthesis, 8),
+                _ => {
+                    return Err(ParseError::UnexpectedToken(Span::new(
+                        self.position,
+                        self.position + 6,
+                    )))
+                }
+            }
+        } else {
+            match rest.chars().next() {
+                Some('}') => (Closing::Brace, 1),
+                Some(']') if closing == Closing::Bracket => (Closing::Bracket, 1),
+                Some(')' | ']') => (Closing::Parenthesis, 1),
+                _ => return Ok(None),
+            }
+        };
+
+        let span = Span::new(self.position, self.position + length);
+        if kind == closing {
+            self.position += length;
+            Ok(Some(span))
+        } else {
+            Err(ParseError::MissingLeftParenthesis(span))
+        }
+    }
+
+    /// Tokenizes operands and operators until `closing`, which is consumed but not pushed.
+    fn expression(&mut self, closing: Closing, opening: Span) -> Result<(), ParseError> {
+        let first_token = self.tokens.len();
+        let mut expects_operand = true;
+
+        loop {
+            self.skip_whitespace();
+
+            let end = match self.closing(closing)? {
+                Some(span) => Some(span),
+                None if self.rest().is_empty() => {
+                    if closing != Closing::End {
+                        return Err(ParseError::MissingRightParenthesis(opening));
+                    }
+                    Some(Span::point(self.position))
+                }
+                None => None,
+            };
+
+            if let Some(end) = end {
+                return if self.tokens.len() == first_token {
+                    Err(ParseError::Empty(opening.merge(end)))
+                } else if expects_operand {
+                    Err(ParseError::MissingArgument(Span::point(end.start)))
+                } else {
+                    Ok(())
+                };
+            }
+
+            expects_operand = if expects_operand {
+                self.operand()?
+            } else {
+                self.operator()?
+            };
+        }
+    }
+
+    /// Returns whether an operand can start at the current position, used for implicit
+    /// multiplication like `2x` or `a\sqrt{b}`.
+    fn starts_operand(&self) -> bool {
+        match self.peek_command() {
+            Some((name, _)) => {
+                matches!(
+                    name,
+                    "frac" | "dfrac" | "tfrac" | "sqrt" | "left" | "operatorname" | "mathit"
+                ) || FUNCTIONS.contains(&name)
+                    || GREEK_LETTERS.iter().any(|(letter, _)| *letter == name)
+                    || CONSTANTS.iter().any(|(constant, _)| *constant == name)
+            }
+            None => self
+                .rest()
+                .starts_with(|c: char| c.is_alphanumeric() || matches!(c, '.' | '(' | '[' | '{')),
+        }
+    }
+
+    /// Tokenizes an operand, returns `true` if it was only a sign and an operand still has to
+    /// follow.
+    fn operand(&mut self) -> Result<bool, ParseError> {
+        let rest = self.rest();
+        let Some(first) = rest.chars().next() else {
+            return Err(ParseError::MissingArgument(Span::point(self.position)));
+        };
+
+        match first {
+            '+' => {
+                let span = self.advance(1);
+                self.push(Token::Unary(Operation::Add), span);
+                return Ok(true);
+            }
+            '-' => {
+                let span = self.advance(1);
+                self.push(Token::Unary(Operation::Subtract), span);
+                return Ok(true);
+            }
+            '(' | '[' => {
+                let span = self.advance(1);
+                self.group(Token::LeftParenthesis, span, Closing::Parenthesis)?;
+            }
+            '{' => {
+                let span = self.advance(1);
+                self.group(Token::LeftParenthesis, span, Closing::Brace)?;
+            }
+            '\\' => self.command()?,
+            c if c.is_ascii_digit() || c == '.' => {
+                let (after, number) = match parse_number(rest) {
+                    Ok(parsed) => parsed,
+                    Err(nom::Err::Failure(error)) => {
+                        return Err(ParseError::NumberTooLarge(Span::new(
+                            self.position,
+                            self.position + rest.len() - error.input.len(),
+                        )))
+                    }
+                    Err(_) => {
+                        return Err(ParseError::UnexpectedToken(Span::new(
+                            self.position,
+                            self.position + 1,
+                        )))
+                    }
+                };
+                let span = self.advance(rest.len() - after.len());
+                self.push(number, span);
+            }
+            c if c.is_alphabetic() => {
+                let span = self.advance(c.len_utf8());
+                self.identifier(c.to_string(), span)?;
+            }
+            c => {
+                return Err(ParseError::UnexpectedToken(Span::new(
+                    self.position,
+                    self.position + c.len_utf8(),
+                )))
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Tokenizes an operator, returns `true` if an operand has to follow.
+    fn operator(&mut self) -> Result<bool, ParseError> {
+        let rest = self.rest();
+        let operation = match rest.chars().next() {
+            Some('+') => Some(Operation::Add),
+            Some('-') => Some(Operation::Subtract),
+            Some('*') => Some(Operation::Multiply),
+            Some('/') => Some(Operation::Divide),
+            Some('=') => Some(Operation::Equal),
+            Some('<') => Some(Operation::LessThan),
+            Some('>') => Some(Operation::GreaterThan),
+            _ => None,
+        };
+
+        if let Some(operation) = operation {
+            let span = self.advance(1);
+            self.push(Token::Binary(operation), span);
+            return Ok(true);
+        }
+
+        match rest.chars().next() {
+            Some(',') => {
+                let span = self.advance(1);
+                self.push(Token::Comma, span);
+                return Ok(true);
+            }
+            Some('!') => {
+                let span = self.advance(1);
+                self.push(Token::Unary(Operation::Factorial), span);
+                return Ok(false);
+            }
+            Some('^') => {
+                let span = self.advance(1);
+                self.push(Token::Binary(Operation::Power), span);
+                self.argument(Token::LeftParenthesis, span)?;
+                return Ok(false);
+            }
+            _ => {}
+        }
+
+        if let Some((name, length)) = self.peek_command() {
+            if let Some((_, operation)) =
+                BINARY_COMMANDS.iter().find(|(command, _)| *command == name)
+            {
+                let span = self.advance(length);
+                self.push(Token::Binary(operation.clone()), span);
+                return Ok(true);
+            }
+        }
+
+        if self.starts_operand() {
+            self.push(
+                Token::Binary(Operation::Multiply),
+                Span::point(self.position),
+            );
+            return Ok(true);
+        }
+
+        let length = rest.chars().next().map_or(0, char::len_utf8);
+        Err(ParseError::UnexpectedToken(Span::new(
+            self.position,
+            self.position + length,
+        )))
+    }
+
+    /// Pushes `opening`, the expression up to `closing` and a right parenthesis.
+    fn group(&mut self, opening: Token, span: Span, closing: Closing) -> Result<(), ParseError> {
+        self.push(opening, span);
+        self.expression(closing, span)?;
+        self.push(Token::RightParenthesis, Span::point(self.position));
+        Ok(())
+    }
+
+    /// Tokenizes a command argument like `{x + 1}` in `\frac{x + 1}{2}` or `2` in `x^2`, wrapped
+    /// in `opening` and a right parenthesis. Without braces only one character or command is
+    /// taken, so `x^23` is `x^2 * 3`.
+    fn argument(&mut self, opening: Token, command: Span) -> Result<(), ParseError> {
+        self.skip_whitespace();
+
+        let rest = self.rest();
+        match rest.chars().next() {
+            Some('{') => {
+                let span = self.advance(1);
+                self.group(opening, span, Closing::Brace)
+            }
+            Some('\\') => {
+                self.push(opening, command);
+                if self.operand()? {
+                    return Err(ParseError::UnexpectedToken(
+                        self.spans[self.spans.len() - 1],
+                    ));
+                }
+                self.push(Token::RightParenthesis, Span::point(self.position));
+                Ok(())
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let span = self.advance(1);
+                self.push(opening, command);
+                self.push(
+                    Token::Number(num::BigRational::from_integer(
+                        c.to_digit(10).unwrap().into(),
+                    )),
+                    span,
+                );
+                self.push(Token::RightParenthesis, Span::point(self.position));
+                Ok(())
+            }
+            Some(c) if c.is_alphabetic() => {
+                let span = self.advance(c.len_utf8());
+                self.push(opening, command);
+                self.push(Token::Identifier(Identifier::new(c)), span);
+                self.push(Token::RightParenthesis, Span::point(self.position));
+                Ok(())
+            }
+            _ => Err(ParseError::MissingArgument(Span::point(self.position))),
+        }
+    }
+
+    /// Returns the tokens pushed by `tokenize`, e.g. to emit them in a different order.
+    fn take_tokens(
+        &mut self,
+        tokenize: impl FnOnce(&mut Self) -> Result<(), ParseError>,
+    ) -> Result<(Vec<Token>, Vec<Span>), ParseError> {
+        let start = self.tokens.len();
+        tokenize(self)?;
+        Ok((self.tokens.split_off(start), self.spans.split_off(start)))
+    }
+
+    fn extend(&mut self, (tokens, spans): (Vec<Token>, Vec<Span>)) {
+        self.tokens.extend(tokens);
+        self.spans.extend(spans);
+    }
+
+    /// Tokenizes a variable starting with `base`, with its subscript and primes, e.g.
+    /// `x_{max}` or `f'`. A primed variable followed by a parenthesis is a function call.
+    fn identifier(&mut self, base: String, span: Span) -> Result<(), ParseError> {
+        let mut identifier = Identifier::new(base);
+
+        if let Some(subscript) = self.rest().strip_prefix('_') {
+            let (text, length) = if let Some(braced) = subscript.strip_prefix('{') {
+                match braced.find('}') {
+                    Some(end) => (braced[..end].trim(), end + 3),
+                    None => {
+                        return Err(ParseError::MissingRightParenthesis(Span::new(
+                            self.position + 1,
+                            self.position + 2,
+                        )))
+                    }
+                }
+            } else {
+                match subscript.chars().next() {
+                    Some(c) if c.is_alphanumeric() => {
+                        (&subscript[..c.len_utf8()], c.len_utf8() + 1)
+                    }
+                    _ => ("", 1),
+                }
+            };
+
+            if text.is_empty() {
+                return Err(ParseError::MissingArgument(Span::point(self.position + 1)));
+            }
+
+            identifier.subscript = Some(text.to_string());
+            self.advance(length);
+        }
+
+        let primes = self.rest().len() - self.rest().trim_start_matches('\'').len();
+        identifier.primes = primes;
+        self.advance(primes);
+
+        let span = span.merge(Span::point(self.position));
+
+        self.skip_whitespace();
+        if primes > 0 && (self.rest().starts_with('(') || self.rest().starts_with("\\left(")) {
+            let length = if self.rest().starts_with('(') { 1 } else { 6 };
+            let opening = self.advance(length);
+            self.group(
+                Token::Function {
+                    name: identifier,
+                    num_of_args: None,
+                },
+                span.merge(opening),
+                Closing::Parenthesis,
+            )
+        } else {
+            self.push(Token::Identifier(identifier), span);
+            Ok(())
+        }
+    }
+
+    fn command(&mut self) -> Result<(), ParseError> {
+        let Some((name, length)) = self.peek_command() else {
+            return Err(ParseError::UnexpectedToken(Span::point(self.position)));
+        };
+        let span = Span::new(self.position, self.position + length);
+
+        if let Some((_, letter)) = GREEK_LETTERS.iter().find(|(letter, _)| *letter == name) {
+            self.advance(length);
+            return self.identifier(letter.to_string(), span);
+        }
+
+        if let Some((_, constant)) = CONSTANTS.iter().find(|(constant, _)| *constant == name) {
+            self.advance(length);
+            self.push(Token::Identifier(Identifier::new(*constant)), span);
+            return Ok(());
+        }
+
+        if FUNCTIONS.contains(&name) {
+            self.advance(length);
+            return self.function(Identifier::new(name), span);
+        }
+
+        match name {
+            "left" => {
+                self.advance(length);
+                let rest = self.rest();
+                let length = if rest.starts_with(['(', '[']) {
+                    1
+                } else if rest.starts_with("\\{") {
+                    2
+                } else {
+                    return Err(ParseError::UnexpectedToken(span));
+                };
+                let span = span.merge(self.advance(length));
+                self.group(Token::LeftParenthesis, span, Closing::Parenthesis)
+            }
+            "frac" | "dfrac" | "tfrac" => {
+                self.advance(length);
+                self.push(Token::LeftParenthesis, span);
+                self.argument(Token::LeftParenthesis, span)?;
+                self.push(Token::Binary(Operation::Divide), Span::point(self.position));
+                self.argument(Token::LeftParenthesis, span)?;
+                self.push(Token::RightParenthesis, Span::point(self.position));
+                Ok(())
+            }
+            "sqrt" => {
+                self.advance(length);
+                self.sqrt(span)
+            }
+            "operatorname" => {
+                self.advance(length);
+                let (name, name_span) = self.braced_name()?;
+                self.function(Identifier::parse(name), span.merge(name_span))
+            }
+            "mathit" => {
+                self.advance(length);
+                let (name, name_span) = self.braced_name()?;
+                self.identifier(name.to_string(), span.merge(name_span))
+            }
+            _ => Err(ParseError::UnexpectedToken(span)),
+        }
+    }
+
+    /// The trimmed, non-empty name in the braces of `\operatorname{...}` or `\mathit{...}`.
+    fn braced_name(&mut self) -> Result<(&'a str, Span), ParseError> {
+        let rest = self.rest();
+        let name = rest
+            .strip_prefix('{')
+            .and_then(|braced| braced.find('}').map(|end| &braced[..end]))
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .ok_or(ParseError::MissingArgument(Span::point(self.position)))?;
+        let span = self.advance(rest.find('}').unwrap() + 1);
+
+        Ok((name, span))
+    }
+
+    /// `\sqrt{x}` and `\sqrt[3]{x}` are read as `sqrt(x)` and `cbrt(x)`, other indices as a
+    /// power like `x^(1/n)`.
+    fn sqrt(&mut self, span: Span) -> Result<(), ParseError> {
+        let index = if let Some(bracket) = self.rest().strip_prefix('[') {
+            let opening = self.advance(1);
+            let text = bracket[..bracket.find(']').unwrap_or(bracket.len())].trim();
+            let tokens =
+                self.take_tokens(|tokenizer| tokenizer.expression(Closing::Bracket, opening))?;
+            Some((text, tokens))
+        } else {
+            None
+        };
+
+        let name = match &index {
+            None | Some(("2", _)) => "sqrt",
+            Some(("3", _)) => "cbrt",
+            Some((_, tokens)) => {
+                let tokens = tokens.clone();
+                self.push(Token::LeftParenthesis, span);
+                self.argument(Token::LeftParenthesis, span)?;
+                self.push(Token::Binary(Operation::Power), span);
+                self.push(Token::LeftParenthesis, span);
+                self.push(
+                    Token::Number(num::BigRational::from_integer(1.into())),
+                    span,
+                );
+                self.push(Token::Binary(Operation::Divide), span);
+                self.push(Token::LeftParenthesis, span);
+                self.extend(tokens);
+                self.push(Token::RightParenthesis, span);
+                self.push(Token::RightParenthesis, span);
+                self.push(Token::RightParenthesis, Span::point(self.position));
+                return Ok(());
+            }
+        };
+
+        self.argument(
+            Token::Function {
+                name: Identifier::new(name),
+                num_of_args: None,
+            },
+            span,
+        )
+    }
+
+    /// Tokenizes a function like `\sin^2 x` or `\log_{2}(x)` into `sin(x)^2` and `log(x, 2)`.
+    ///
+    /// Without parenthesis or braces the argument is the product right after the function, so
+    /// `\sin 2x + 1` is `sin(2x) + 1`.
+    fn function(&mut self, name: Identifier, span: Span) -> Result<(), ParseError> {
+        let mut power = None;
+        let mut base = None;
+
+        for _ in 0..2 {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with('^') && power.is_none() {
+                let script = self.advance(1);
+                power =
+                    Some(self.take_tokens(|tokenizer| {
+                        tokenizer.argument(Token::LeftParenthesis, script)
+                    })?);
+            } else if rest.starts_with('_') && base.is_none() {
+                let script = self.advance(1);
+                base =
+                    Some(self.take_tokens(|tokenizer| {
+                        tokenizer.argument(Token::LeftParenthesis, script)
+                    })?);
+            }
+        }
+
+        self.skip_whitespace();
+        let function = Token::Function {
+            name,
+            num_of_args: None,
+        };
+
+        let rest = self.rest();
+        let closing = if rest.starts_with("\\left(") || rest.starts_with("\\left[") {
+            Some((Closing::Parenthesis, 6))
+        } else if rest.starts_with(['(', '[']) {
+            Some((Closing::Parenthesis, 1))
+        } else if rest.starts_with('{') {
+            Some((Closing::Brace, 1))
+        } else {
+            None
+        };
+
+        self.push(function, span);
+        match closing {
+            Some((closing, length)) => {
+                let opening = self.advance(length);
+                self.expression(closing, opening)?;
+            }
+            None => self.implicit_argument()?,
+        }
+
+        if let Some(base) = base {
+            self.push(Token::Comma, Span::point(self.position));
+            self.extend(base);
+        }
+        self.push(Token::RightParenthesis, Span::point(self.position));
+
+        if let Some(power) = power {
+            self.push(Token::Binary(Operation::Power), span);
+            self.extend(power);
+        }
+
+        Ok(())
+    }
+
+    /// The argument of a function written without parenthesis, a product of numbers,
+    /// variables and commands with their exponents.
+    fn implicit_argument(&mut self) -> Result<(), ParseError> {
+        let mut first = true;
+
+        loop {
+            self.skip_whitespace();
+
+            let starts_atom = match self.peek_command() {
+                Some((name, _)) => !FUNCTIONS.contains(&name) && self.starts_operand(),
+                None => self
+                    .rest()
+                    .starts_with(|c: char| c.is_alphanumeric() || c == '.'),
+            };
+
+            if !starts_atom {
+                if first {
+                    return Err(ParseError::MissingArgument(Span::point(self.position)));
+                }
+                return Ok(());
+            }
+
+            if !first {
+                self.push(
+                    Token::Binary(Operation::Multiply),
+                    Span::point(self.position),
+                );
+            }
+            self.operand()?;
+            first = false;
+
+            self.skip_whitespace();
+            if self.rest().starts_with('^') {
+                let span = self.advance(1);
+                self.push(Token::Binary(Operation::Power), span);
+                self.argument(Token::LeftParenthesis, span)?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::{context::CreateEquationError, equation::NoContextEquation};
+
+    fn sides(input: &str, equation: Result<NoContextEquation, CreateEquationError>) -> String {
+        equation
+            .unwrap_or_else(|error| panic!("{input}\n{}", error.render(input)))
+            .sides
+            .iter()
+            .map(|side| side.element.to_string())
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+
+    #[test]
+    fn test_same_ast_as_plain_syntax() {
+        let cases = [
+            (r"\frac{a}{b}", "a/b"),
+            (r"\frac12 + x", "1/2 + x"),
+            (r"\sqrt{x + 1}", "sqrt(x + 1)"),
+            (r"\sqrt[3]{x}", "cbrt(x)"),
+            (r"\sqrt[n]{x}", "x^(1/n)"),
+            (r"x^{2y} - 1", "x^(2y) - 1"),
+            (r"x^23", "x^2 * 3"),
+            (r"\sin x", "sin(x)"),
+            (r"\sin 2x + 1", "sin(2*x) + 1"),
+            (r"\sin^2 x", "sin(x)^2"),
+            (r"\cos\left(\frac{\pi}{2}\right)", "cos(pi/2)"),
+            (r"\log_{2} x", "log(x, 2)"),
+            (r"\log_2(x+1)", "log(x + 1, 2)"),
+            (r"a \cdot b \times c \div d", "a * b * c / d"),
+            (r"\left( a + b \right) c", "(a + b) * c"),
+            (r"x \le 2", "x <= 2"),
+            (r"x \geq 2", "x >= 2"),
+            (r"x \ne 2", "x != 2"),
+            (r"2xy", "2 * x * y"),
+            (r"-x^2", "-x^2"),
+            (r"\alpha + \beta_1", "α + β_1"),
+            (r"v_{max}' = f'(x)", "v_{max}' = f'(x)"),
+            (r"\operatorname{foo}(x, y)", "foo(x, y)"),
+            (r"\mathit{max}_1 + \mathit{rate}'", "max_1 + rate'"),
+            (r"\mathit{speed}\,t", "speed * t"),
+            (r"2\,\pi r", "2 * pi * r"),
+            (r"\infty", "infinity"),
+            (r"n! + 1", "n! + 1"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(
+                sides(input, NoContextEquation::try_from(Latex(input))),
+                sides(expected, NoContextEquation::try_from(expected)),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let cases = [
+            ("", ParseError::Empty(Span::new(0, 0))),
+            (r"\frac{a}", ParseError::MissingArgument(Span::point(8))),
+            (
+                r"2^{1e1000001}",
+                ParseError::NumberTooLarge(Span::new(3, 12)),
+            ),
+            (
+                r"{a + b",
+                ParseError::MissingRightParenthesis(Span::new(0, 1)),
+            ),
+            (
+                r"a + b}",
+                ParseError::MissingLeftParenthesis(Span::new(5, 6)),
+            ),
+            (r"a + \foo", ParseError::UnexpectedToken(Span::new(4, 8))),
+            (r"a +", ParseError::MissingArgument(Span::point(3))),
+            (r"\cdot a", ParseError::UnexpectedToken(Span::new(0, 5))),
+            (
+                r"\mathit{} + 1",
+                ParseError::MissingArgument(Span::point(7)),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let error = TokenizedString::try_from(Latex(input)).unwrap_err();
+            assert_eq!(error.to_string(), expected.to_string(), "{input}");
+            assert_eq!(error.span(), expected.span(), "{input}");
+        }
+    }
+}
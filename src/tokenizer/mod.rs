@@ -1,3 +1,6 @@
+pub mod latex;
 pub mod parser;
+pub mod rpn;
 pub mod small_parsers;
+pub mod span;
 pub mod token;
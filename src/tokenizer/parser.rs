@@ -1,27 +1,60 @@
+use itertools::Itertools;
 use nom::IResult;
 use std::ops::Deref;
 use thiserror::Error;
 
+use crate::ast::{Constant, Identifier};
+
 use crate::tokenizer::small_parsers::{
-    parse_left_expression, parse_right_expression, parse_right_expression_no_parenthesis,
-    parse_right_expression_with_comma, parse_unit,
+    parse_left_expression, parse_radical, parse_radicand, parse_right_expression,
+    parse_right_expression_no_parenthesis, parse_right_expression_with_comma, parse_superscript,
+    parse_unit, MAX_FLOAT_EXPONENT,
 };
 
 use super::{
     small_parsers::trim_with_comments,
+    span::{render_diagnostic, Span},
     token::{Operation, Token},
 };
 
 #[derive(Debug, Error)]
 pub enum ParseError {
     #[error("Unexpected token at {0}")]
-    UnexpectedToken(usize),
-    #[error("Missing right parenthesis at {0}")]
-    MissingRightParenthesis(usize),
-    #[error("An function argument is missing")]
-    MissingArgument,
+    UnexpectedToken(Span),
+    #[error("Missing right parenthesis for the one at {0}")]
+    MissingRightParenthesis(Span),
+    #[error("An function argument is missing at {0}")]
+    MissingArgument(Span),
     #[error("The expression is empty")]
-    Empty,
+    Empty(Span),
+    #[error("Missing left parenthesis for the one at {0}")]
+    MissingLeftParenthesis(Span),
+    #[error("An operator is missing at {0}")]
+    MissingOperator(Span),
+    #[error(
+        "The exponent of the number at {0} is above the limit of {limit}",
+        limit = MAX_FLOAT_EXPONENT
+    )]
+    NumberTooLarge(Span),
+}
+
+impl ParseError {
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedToken(span)
+            | ParseError::MissingRightParenthesis(span)
+            | ParseError::MissingArgument(span)
+            | ParseError::Empty(span)
+            | ParseError::MissingLeftParenthesis(span)
+            | ParseError::MissingOperator(span)
+            | ParseError::NumberTooLarge(span) => *span,
+        }
+    }
+
+    /// Renders the error with the offending part of `input` underlined.
+    pub fn render(&self, input: &str) -> String {
+        render_diagnostic(input, self.span(), &self.to_string())
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -39,6 +72,8 @@ enum ParenthesisState {
 #[derive(Debug, Clone)]
 pub struct TokenizedString {
     pub tokens: Vec<Token>,
+    /// Location of every token in the input, `spans[i]` belongs to `tokens[i]`.
+    pub spans: Vec<Span>,
 }
 
 impl Deref for TokenizedString {
@@ -51,7 +86,36 @@ impl Deref for TokenizedString {
 
 impl TokenizedString {
     pub fn new_from_tokens(tokens: Vec<Token>) -> Self {
-        Self { tokens }
+        let spans = vec![Span::default(); tokens.len()];
+        Self { tokens, spans }
+    }
+
+    pub fn new_from_tokens_and_spans(tokens: Vec<Token>, spans: Vec<Span>) -> Self {
+        assert_eq!(tokens.len(), spans.len(), "Every token needs a span");
+        Self { tokens, spans }
+    }
+
+    pub fn iter_with_spans(&self) -> impl Iterator<Item = (&Token, &Span)> {
+        self.tokens.iter().zip(self.spans.iter())
+    }
+}
+
+impl TokenizedString {
+    /// Tokenizes `input` without stopping at the first problem.
+    ///
+    /// Unexpected characters are skipped up to the next place where a token can start, missing
+    /// operands are replaced with [`Token::Placeholder`], missing operators with a
+    /// multiplication and unclosed parenthesis are closed at the end of the input. Every
+    /// recovery is reported in [`RecoveredTokens::diagnostics`].
+    pub fn tokenize_recovering(input: &str) -> RecoveredTokens {
+        let mut tokenizer = Tokenizer::new(input, true);
+        // In recovering mode the errors are collected instead of returned.
+        let _ = tokenizer.run();
+
+        RecoveredTokens {
+            tokens: TokenizedString::new_from_tokens_and_spans(tokenizer.result, tokenizer.spans),
+            diagnostics: tokenizer.diagnostics,
+        }
     }
 }
 
@@ -59,114 +123,520 @@ impl TryFrom<&str> for TokenizedString {
     type Error = ParseError;
 
     fn try_from(input: &str) -> Result<TokenizedString, ParseError> {
-        let mut result: Vec<Token> = vec![];
-        let mut parenthesis_stack: Vec<ParenthesisState> = vec![];
-        let mut state = TokenizerState::LeftExpression;
-        let mut next_is_unit = true;
-        let mut work_string = input;
+        let mut tokenizer = Tokenizer::new(input, false);
+        tokenizer.run()?;
+
+        Ok(TokenizedString::new_from_tokens_and_spans(
+            tokenizer.result,
+            tokenizer.spans,
+        ))
+    }
+}
+
+/// Best-effort output of [`TokenizedString::tokenize_recovering`].
+#[derive(Debug)]
+pub struct RecoveredTokens {
+    pub tokens: TokenizedString,
+    pub diagnostics: Vec<ParseError>,
+}
+
+impl RecoveredTokens {
+    pub fn is_ok(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// Renders every diagnostic against `input`, separated by empty lines.
+    pub fn render(&self, input: &str) -> String {
+        self.diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.render(input))
+            .join("\n\n")
+    }
+}
+
+struct Tokenizer<'a> {
+    input: &'a str,
+    work_string: &'a str,
+    result: Vec<Token>,
+    spans: Vec<Span>,
+    parenthesis_stack: Vec<(ParenthesisState, Span)>,
+    state: TokenizerState,
+    next_is_unit: bool,
+    recover: bool,
+    diagnostics: Vec<ParseError>,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(input: &'a str, recover: bool) -> Self {
+        Tokenizer {
+            input,
+            work_string: input,
+            result: vec![],
+            spans: vec![],
+            parenthesis_stack: vec![],
+            state: TokenizerState::LeftExpression,
+            next_is_unit: true,
+            recover,
+            diagnostics: vec![],
+        }
+    }
+
+    fn offset(&self, rest: &str) -> usize {
+        self.input.len() - rest.len()
+    }
 
+    /// Returns the error when not recovering, otherwise remembers it and lets the caller recover.
+    fn report(&mut self, error: ParseError) -> Result<(), ParseError> {
+        if self.recover {
+            self.diagnostics.push(error);
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    fn push(&mut self, token: Token, span: Span) {
+        self.result.push(token);
+        self.spans.push(span);
+    }
+
+    fn parse_next(&self, state: TokenizerState, input: &'a str) -> IResult<&'a str, Token> {
+        match (
+            state,
+            self.parenthesis_stack
+                .last()
+                .map(|(parenthesis, _)| parenthesis),
+        ) {
+            (TokenizerState::LeftExpression, _) => parse_left_expression(input),
+            (TokenizerState::RightExpression, None) => parse_right_expression_no_parenthesis(input),
+            (TokenizerState::RightExpression, Some(&ParenthesisState::Function)) => {
+                parse_right_expression_with_comma(input)
+            }
+            (TokenizerState::RightExpression, Some(&ParenthesisState::Subexpression)) => {
+                parse_right_expression(input)
+            }
+        }
+    }
+
+    fn run(&mut self) -> Result<(), ParseError> {
         let mut last_string;
         let mut last_state;
 
-        while !work_string.is_empty() {
-            if let Ok(trimmed) = trim_with_comments::<nom::error::Error<_>>(work_string) {
-                work_string = trimmed.0;
-                if work_string.is_empty() {
+        while !self.work_string.is_empty() {
+            if let Ok(trimmed) = trim_with_comments::<nom::error::Error<_>>(self.work_string) {
+                self.work_string = trimmed.0;
+                if self.work_string.is_empty() {
                     break;
                 }
             }
 
-            let mut parsing_result: IResult<&str, Token> = match (&state, parenthesis_stack.last())
-            {
-                (TokenizerState::LeftExpression, _) => parse_left_expression(work_string),
-                (TokenizerState::RightExpression, None) => {
-                    parse_right_expression_no_parenthesis(work_string)
-                }
-                (TokenizerState::RightExpression, Some(&ParenthesisState::Function)) => {
-                    parse_right_expression_with_comma(work_string)
+            match self.state {
+                TokenizerState::LeftExpression => {
+                    if let Ok((rest, name)) = parse_radical(self.work_string) {
+                        self.push_radical(name, rest)?;
+                        continue;
+                    }
                 }
-                (TokenizerState::RightExpression, Some(&ParenthesisState::Subexpression)) => {
-                    parse_right_expression(work_string)
+                TokenizerState::RightExpression => {
+                    if let Ok((rest, tokens)) = parse_superscript(self.work_string) {
+                        self.push_superscript(tokens, rest);
+                        continue;
+                    }
+
+                    // a radical right after a factor is multiplied with it, like `2√3`
+                    if let Ok((rest, name)) = parse_radical(self.work_string) {
+                        let position = self.offset(self.work_string);
+                        self.push(Token::Binary(Operation::Multiply), Span::point(position));
+                        self.state = TokenizerState::LeftExpression;
+                        self.next_is_unit = true;
+                        self.push_radical(name, rest)?;
+                        continue;
+                    }
                 }
-            };
+            }
 
-            last_state = state;
+            let mut parsing_result = self.parse_next(self.state, self.work_string);
+
+            last_state = self.state;
 
             match &mut parsing_result {
                 Ok((rest, token)) => {
-                    if next_is_unit {
-                        next_is_unit = false;
+                    if self.next_is_unit {
+                        self.next_is_unit = false;
 
                         match token {
                             Token::Identifier(name) => {
                                 *token = Token::Identifier(name.clone());
                             }
                             Token::LeftParenthesis | Token::RightParenthesis => {
-                                next_is_unit = true;
+                                self.next_is_unit = true;
                             }
                             _ => (), //println!("next_is_unit is set, but token is {:?}", token),
                         };
                     }
 
+                    let span = Span::new(self.offset(self.work_string), self.offset(rest));
+
                     // println!("Token: {:?}\n", token);
                     match token {
-                        Token::Binary(_) | Token::Comma => state = TokenizerState::LeftExpression,
-                        Token::LeftParenthesis => {
-                            parenthesis_stack.push(ParenthesisState::Subexpression)
+                        Token::Binary(_) | Token::Comma => {
+                            self.state = TokenizerState::LeftExpression
                         }
+                        Token::LeftParenthesis => self
+                            .parenthesis_stack
+                            .push((ParenthesisState::Subexpression, span)),
                         Token::RightParenthesis => {
-                            parenthesis_stack.pop().expect("Missing left parenthesis");
+                            self.parenthesis_stack.pop();
                         }
                         Token::Identifier { .. } | Token::Number(..) => {
-                            state = TokenizerState::RightExpression
-                        }
-                        Token::Function { .. } => {
-                            parenthesis_stack.push(ParenthesisState::Function)
+                            self.state = TokenizerState::RightExpression
                         }
+                        Token::Function { .. } => self
+                            .parenthesis_stack
+                            .push((ParenthesisState::Function, span)),
                         _ => (),
                     }
 
-                    result.push(token.clone());
-                    last_string = work_string;
-                    work_string = rest;
+                    self.push(token.clone(), span);
+                    last_string = self.work_string;
+                    self.work_string = rest;
 
                     if last_state == TokenizerState::RightExpression
                         && parse_unit(last_string).is_ok()
                     {
                         // println!("Overriding unit in identifier");
-                        next_is_unit = true;
+                        self.next_is_unit = true;
                     }
                 }
-                Err(nom::Err::Error(_)) => {
-                    // TODO: handle error
+                Err(_) => {
                     // println!("Normal: {}\nLast: {}", work_string, last_string);
-                    if let Some(last_token) = result.last() {
-                        state = TokenizerState::LeftExpression;
-                        next_is_unit = true;
-                        match last_token {
-                            Token::Number(_) | Token::RightParenthesis => {
-                                result.push(Token::Binary(Operation::Multiply));
-                                continue;
+                    let position = self.offset(self.work_string);
+                    let left_expression =
+                        self.parse_next(TokenizerState::LeftExpression, self.work_string);
+
+                    if let Err(nom::Err::Failure(error)) = left_expression {
+                        self.push_too_large(error.input)?;
+                        continue;
+                    }
+
+                    let is_constant = |name: &Identifier| Constant::from_identifier(name).is_some();
+                    let implicit_multiplication = left_expression.as_ref().is_ok_and(
+                        |(_, next)| match self.result.last() {
+                            Some(Token::Number(_) | Token::RightParenthesis) => true,
+                            // `2πr` and `rπ`, but `x y` is still missing an operator
+                            Some(Token::Identifier(last)) => {
+                                is_constant(last)
+                                    || matches!(next, Token::Identifier(next) if is_constant(next))
                             }
-                            _ => (),
-                        };
+                            _ => false,
+                        },
+                    );
+
+                    if implicit_multiplication {
+                        self.state = TokenizerState::LeftExpression;
+                        self.next_is_unit = true;
+                        self.push(Token::Binary(Operation::Multiply), Span::point(position));
+                        continue;
                     }
-                    return Err(ParseError::UnexpectedToken(1));
+
+                    self.recover_from_error(position)?;
                 }
-                Err(error) => panic!("{}", error),
             }
         }
 
-        if result.is_empty() {
-            return Err(ParseError::Empty);
+        if self.result.is_empty() {
+            self.report(ParseError::Empty(Span::new(0, self.input.len())))?;
+            return Ok(());
         }
 
-        match state {
-            TokenizerState::LeftExpression => Err(ParseError::MissingArgument),
-            _ if !parenthesis_stack.is_empty() => {
-                Err(ParseError::MissingRightParenthesis(parenthesis_stack.len()))
+        let end = Span::point(self.input.trim_end().len());
+
+        if self.state == TokenizerState::LeftExpression {
+            self.report(ParseError::MissingArgument(end))?;
+            self.push(Token::Placeholder, end);
+            self.state = TokenizerState::RightExpression;
+        }
+
+        while let Some((_, span)) = self.parenthesis_stack.pop() {
+            self.report(ParseError::MissingRightParenthesis(span))?;
+            self.push(Token::RightParenthesis, end);
+        }
+
+        Ok(())
+    }
+
+    /// A number literal which is too large is replaced with a placeholder, its operators are
+    /// still checked.
+    fn push_too_large(&mut self, rest: &'a str) -> Result<(), ParseError> {
+        let span = Span::new(self.offset(self.work_string), self.offset(rest));
+        self.report(ParseError::NumberTooLarge(span))?;
+
+        if self.state == TokenizerState::RightExpression {
+            self.push(Token::Binary(Operation::Multiply), Span::point(span.start));
+        }
+        self.push(Token::Placeholder, span);
+        self.state = TokenizerState::RightExpression;
+        self.work_string = rest;
+
+        Ok(())
+    }
+
+    fn push_superscript(&mut self, tokens: Vec<Token>, rest: &'a str) {
+        let span = Span::new(self.offset(self.work_string), self.offset(rest));
+
+        for token in tokens {
+            self.push(token, span);
+        }
+
+        self.work_string = rest;
+    }
+
+    /// `√(x + 1)` is read like `sqrt(x + 1)`, while `√2x` and `√x²` only take the number or
+    /// variable right after the radical sign (and its superscript), like `sqrt(2)x` and
+    /// `sqrt(x^2)`.
+    fn push_radical(&mut self, name: &str, rest: &'a str) -> Result<(), ParseError> {
+        let start = self.offset(self.work_string);
+        let function = Token::Function {
+            name: Identifier::new(name),
+            num_of_args: None,
+        };
+        let argument = rest.trim_start();
+
+        if let Some(after_parenthesis) = argument.strip_prefix('(') {
+            let span = Span::new(start, self.offset(after_parenthesis));
+            self.push(function, span);
+            self.parenthesis_stack
+                .push((ParenthesisState::Function, span));
+            self.work_string = after_parenthesis;
+            return Ok(());
+        }
+
+        self.push(function, Span::new(start, self.offset(rest)));
+
+        match parse_radicand(argument) {
+            Ok((after_radicand, token)) => {
+                let span = Span::new(self.offset(argument), self.offset(after_radicand));
+                self.push(token, span);
+                self.work_string = after_radicand;
+
+                if let Ok((after_superscript, tokens)) = parse_superscript(after_radicand) {
+                    self.push_superscript(tokens, after_superscript);
+                }
+            }
+            Err(nom::Err::Failure(error)) => {
+                let span = Span::new(self.offset(argument), self.offset(error.input));
+                self.report(ParseError::NumberTooLarge(span))?;
+                self.push(Token::Placeholder, span);
+                self.work_string = error.input;
+            }
+            Err(_) => {
+                let span = Span::point(self.offset(argument));
+                self.report(ParseError::MissingArgument(span))?;
+                self.push(Token::Placeholder, span);
+                self.work_string = argument;
             }
-            _ => Ok(TokenizedString { tokens: result }),
         }
+
+        self.push(
+            Token::RightParenthesis,
+            Span::point(self.offset(self.work_string)),
+        );
+        self.state = TokenizerState::RightExpression;
+
+        Ok(())
+    }
+
+    /// Called when nothing can be parsed at `position`. Either a placeholder token is inserted,
+    /// after which parsing can continue, or the offending characters are skipped.
+    fn recover_from_error(&mut self, position: usize) -> Result<(), ParseError> {
+        let work_string = self.work_string;
+
+        match self.state {
+            TokenizerState::LeftExpression
+                if self
+                    .parse_next(TokenizerState::RightExpression, work_string)
+                    .is_ok() =>
+            {
+                self.report(ParseError::MissingArgument(Span::point(position)))?;
+                self.push(Token::Placeholder, Span::point(position));
+                self.state = TokenizerState::RightExpression;
+            }
+            TokenizerState::RightExpression
+                if self
+                    .parse_next(TokenizerState::LeftExpression, work_string)
+                    .is_ok() =>
+            {
+                self.report(ParseError::MissingOperator(Span::point(position)))?;
+                self.push(Token::Binary(Operation::Multiply), Span::point(position));
+                self.state = TokenizerState::LeftExpression;
+            }
+            _ => {
+                let skipped = self.skip_to_synchronization_point(work_string);
+                let span = Span::new(position, position + skipped);
+
+                if self.state == TokenizerState::RightExpression
+                    && self.parenthesis_stack.is_empty()
+                    && work_string.starts_with(')')
+                {
+                    self.report(ParseError::MissingLeftParenthesis(span))?;
+                } else {
+                    self.report(ParseError::UnexpectedToken(span))?;
+                }
+
+                self.work_string = &work_string[skipped..];
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of bytes until whitespace or a place where a token can start.
+    fn skip_to_synchronization_point(&self, input: &'a str) -> usize {
+        let mut chars = input.char_indices().skip(1);
+
+        loop {
+            match chars.next() {
+                Some((index, character)) => {
+                    let rest = &input[index..];
+                    if character.is_whitespace()
+                        || (character == ')' && self.parenthesis_stack.is_empty())
+                        || self
+                            .parse_next(TokenizerState::LeftExpression, rest)
+                            .is_ok()
+                        || self
+                            .parse_next(TokenizerState::RightExpression, rest)
+                            .is_ok()
+                    {
+                        return index;
+                    }
+                }
+                None => return input.len(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_spans() {
+        let tokenized = TokenizedString::try_from("12 + sin(x)").unwrap();
+        let spans = [(0, 2), (3, 4), (5, 9), (9, 10), (10, 11)];
+
+        assert_eq!(tokenized.tokens.len(), spans.len());
+        for (span, (start, end)) in tokenized.spans.iter().zip(spans) {
+            assert_eq!(*span, Span::new(start, end));
+        }
+    }
+
+    #[test]
+    fn test_error_spans() {
+        let cases = [
+            ("1 + # 2", Span::new(4, 5)),
+            ("(1 + 2", Span::new(0, 1)),
+            ("1 + 2 *  ", Span::point(7)),
+            ("   ", Span::new(0, 3)),
+            ("1e1000001", Span::new(0, 9)),
+            ("2 + 1.5e-1000001 * x", Span::new(4, 16)),
+            ("√1e1000001", Span::new(3, 12)),
+        ];
+
+        for (input, span) in cases {
+            assert_eq!(TokenizedString::try_from(input).unwrap_err().span(), span);
+        }
+        assert_eq!(
+            TokenizedString::try_from("1e1000001")
+                .unwrap_err()
+                .to_string(),
+            "The exponent of the number at 0..9 is above the limit of 1000000"
+        );
+    }
+
+    #[test]
+    fn test_missing_operator() {
+        let cases = [
+            ("x y", Span::point(2)),
+            ("x 2", Span::point(2)),
+            ("sin x", Span::point(4)),
+        ];
+
+        for (input, span) in cases {
+            let error = TokenizedString::try_from(input).unwrap_err();
+            assert!(matches!(error, ParseError::MissingOperator(_)), "{input}");
+            assert_eq!(error.span(), span, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_recovering() {
+        let input = "1 + * 2 # 3) + (4";
+        let recovered = TokenizedString::tokenize_recovering(input);
+
+        let diagnostics = recovered
+            .diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.span())
+            .collect_vec();
+        assert_eq!(
+            diagnostics,
+            [
+                Span::point(4),
+                Span::new(8, 9),
+                Span::new(11, 12),
+                Span::new(15, 16)
+            ]
+        );
+        assert!(matches!(
+            recovered.diagnostics[2],
+            ParseError::MissingLeftParenthesis(_)
+        ));
+
+        assert_eq!(recovered.tokens.tokens[2], Token::Placeholder);
+        assert_eq!(recovered.tokens.last(), Some(&Token::RightParenthesis));
+        assert_eq!(recovered.tokens.tokens.len(), recovered.tokens.spans.len());
+    }
+
+    #[test]
+    fn test_recovering_without_errors() {
+        let recovered = TokenizedString::tokenize_recovering("2x + sin(y, 3)");
+        let tokens = TokenizedString::try_from("2x + sin(y, 3)").unwrap();
+
+        assert!(recovered.is_ok());
+        assert_eq!(recovered.tokens.tokens, tokens.tokens);
+        assert_eq!(recovered.tokens.spans, tokens.spans);
+    }
+
+    #[test]
+    fn test_unicode() {
+        let cases = [
+            ("2πr²", "2 * pi * r ^ 2"),
+            ("√x² + ∛(8)", "sqrt(x ^ 2) + cbrt(8)"),
+            ("√2x", "sqrt(2) * x"),
+            ("2√3", "2 * sqrt(3)"),
+            ("x√2 + 1", "x * sqrt(2) + 1"),
+            ("(a + b)∛(x + 1)", "(a + b) * cbrt(x + 1)"),
+            ("2 √3√x", "2 * sqrt(3) * sqrt(x)"),
+            ("rπ + π r", "r * pi + pi * r"),
+            ("a·b × c ÷ d − e", "a * b * c / d - e"),
+            ("ω ≤ ∞", "ω <= infinity"),
+            ("α ≥ β ≠ x⁻¹", "α >= β != x ^ -1"),
+        ];
+
+        for (unicode, ascii) in cases {
+            let unicode = TokenizedString::try_from(unicode).unwrap();
+            let ascii = TokenizedString::try_from(ascii).unwrap();
+            assert_eq!(unicode.tokens, ascii.tokens);
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let error = TokenizedString::try_from("a = 1\nb = 2 # 3").unwrap_err();
+        assert_eq!(
+            error.render("a = 1\nb = 2 # 3"),
+            "error: Unexpected token at 12..13\n  |\n2 | b = 2 # 3\n  |       ^"
+        );
     }
 }
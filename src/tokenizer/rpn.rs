@@ -0,0 +1,190 @@
+use num::{BigInt, BigRational, Zero};
+
+use crate::ast::Identifier;
+
+use super::{
+    parser::ParseError,
+    small_parsers::parse_idenifier,
+    span::Span,
+    token::{Operation, Token},
+};
+
+/// Marks a formula as reverse polish notation, in the format written by
+/// [`ReversePolishNotation`](crate::output::equation_to_rpn::ReversePolishNotation), e.g.
+/// `Rpn("2 x * = 4")`.
+#[derive(Debug, Clone, Copy)]
+pub struct Rpn<'a>(pub &'a str);
+
+/// The tokens of one side of an equation in postfix order and the comparison sign after it.
+pub(crate) type RpnSide = (Vec<Token>, Option<Operation>);
+
+impl Rpn<'_> {
+    pub(crate) fn tokenize(&self) -> Result<Vec<RpnSide>, ParseError> {
+        let mut sides = vec![];
+        let mut tokens = vec![];
+        let mut side_start = 0;
+
+        for (start, word) in words(self.0) {
+            let span = Span::new(start, start + word.len());
+
+            if let Some(operation) = comparison_sign(word) {
+                if tokens.is_empty() {
+                    return Err(ParseError::Empty(Span::new(side_start, span.start)));
+                }
+                sides.push((std::mem::take(&mut tokens), Some(operation)));
+                side_start = span.end;
+                continue;
+            }
+
+            tokens.push(token(word).ok_or(ParseError::UnexpectedToken(span))?);
+        }
+
+        if tokens.is_empty() {
+            return Err(ParseError::Empty(Span::new(side_start, self.0.len())));
+        }
+        sides.push((tokens, None));
+
+        Ok(sides)
+    }
+}
+
+/// Splits `input` at whitespace and returns every word with its byte offset.
+fn words(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input
+        .split_inclusive(char::is_whitespace)
+        .scan(0, |offset, chunk| {
+            let start = *offset;
+            *offset += chunk.len();
+
+            // the separator can be wider than one byte, like a non-breaking space
+            let separator = chunk
+                .chars()
+                .next_back()
+                .filter(|character| character.is_whitespace())
+                .map_or(0, char::len_utf8);
+            Some((start, &chunk[..chunk.len() - separator]))
+        })
+        .filter(|(_, word)| !word.is_empty())
+}
+
+fn comparison_sign(word: &str) -> Option<Operation> {
+    match word {
+        "=" => Some(Operation::Equal),
+        "!=" => Some(Operation::NotEqual),
+        "<" => Some(Operation::LessThan),
+        "<=" => Some(Operation::LessThanOrEqual),
+        ">" => Some(Operation::GreaterThan),
+        ">=" => Some(Operation::GreaterThanOrEqual),
+        _ => None,
+    }
+}
+
+fn token(word: &str) -> Option<Token> {
+    let token = match word {
+        "+" => Token::Binary(Operation::Add),
+        "-" => Token::Binary(Operation::Subtract),
+        "*" => Token::Binary(Operation::Multiply),
+        "/" => Token::Binary(Operation::Divide),
+        "%" => Token::Binary(Operation::Mod),
+        "^" => Token::Binary(Operation::Power),
+        "~" => Token::Unary(Operation::Subtract),
+        "!" => Token::Unary(Operation::Factorial),
+        _ => {
+            if let Some(number) = number(word) {
+                Token::Number(number)
+            } else if let Some((name, arguments)) = word.rsplit_once(':') {
+                Token::Function {
+                    name: identifier(name)?,
+                    num_of_args: Some(arguments.parse().ok()?),
+                }
+            } else {
+                Token::Identifier(identifier(word)?)
+            }
+        }
+    };
+
+    Some(token)
+}
+
+/// Numbers are written like `3`, `-3` or `-1/2`.
+fn number(word: &str) -> Option<BigRational> {
+    let digits = word.strip_prefix('-').unwrap_or(word);
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let number = match word.split_once('/') {
+        Some((numerator, denominator)) => {
+            let denominator: BigInt = denominator.parse().ok()?;
+            if denominator.is_zero() {
+                return None;
+            }
+            BigRational::new(numerator.parse().ok()?, denominator)
+        }
+        None => BigRational::from_integer(word.parse().ok()?),
+    };
+
+    Some(number)
+}
+
+fn identifier(word: &str) -> Option<Identifier> {
+    match parse_idenifier(word) {
+        Ok(("", name)) => Some(Identifier::parse(name)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let sides = Rpn("2 x * != -1/2 f':1").tokenize().unwrap();
+
+        assert_eq!(
+            sides,
+            vec![
+                (
+                    vec![
+                        Token::Number(BigRational::from_integer(2.into())),
+                        Token::Identifier(Identifier::new("x")),
+                        Token::Binary(Operation::Multiply),
+                    ],
+                    Some(Operation::NotEqual)
+                ),
+                (
+                    vec![
+                        Token::Number(BigRational::new((-1).into(), 2.into())),
+                        Token::Function {
+                            name: Identifier::new("f").with_primes(1),
+                            num_of_args: Some(1)
+                        },
+                    ],
+                    None
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let cases = [
+            ("", ParseError::Empty(Span::new(0, 0))),
+            ("1 2 + =", ParseError::Empty(Span::new(7, 7))),
+            ("= 1", ParseError::Empty(Span::new(0, 0))),
+            ("1 2 #", ParseError::UnexpectedToken(Span::new(4, 5))),
+            ("1/0", ParseError::UnexpectedToken(Span::new(0, 3))),
+            ("x f:y", ParseError::UnexpectedToken(Span::new(2, 5))),
+            ("2x", ParseError::UnexpectedToken(Span::new(0, 2))),
+            ("1\u{a0}2 #", ParseError::UnexpectedToken(Span::new(5, 6))),
+            ("1\u{2009}x #", ParseError::UnexpectedToken(Span::new(6, 7))),
+        ];
+
+        for (input, expected) in cases {
+            let error = Rpn(input).tokenize().unwrap_err();
+            assert_eq!(error.span(), expected.span(), "{input}");
+            assert_eq!(error.to_string(), expected.to_string(), "{input}");
+        }
+    }
+}
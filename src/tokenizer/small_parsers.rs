@@ -4,16 +4,17 @@ use nom::{
     branch::alt,
     bytes::complete::{is_not, tag, tag_no_case, take_until},
     character::complete::{char, multispace0, one_of},
-    combinator::{complete, map_res, opt, recognize, value},
+    combinator::{complete, map_res, not, opt, recognize, value},
     error::{self, Error, ErrorKind, ParseError},
     multi::{many0, many1},
     sequence::{pair, preceded, terminated, tuple},
     Err, IResult, Needed,
 };
-use num::{bigint::ParseBigIntError, Num};
+use num::{bigint::ParseBigIntError, Integer, Num, Zero};
 use once_cell::sync::Lazy;
 
 use super::token::{Operation, Token};
+use crate::ast::Identifier;
 
 fn parse_eol_comment<'a, E: ParseError<&'a str> + 'a>(i: &'a str) -> IResult<&'a str, (), E> {
     trim(value(
@@ -79,9 +80,25 @@ static BINARY_EXPRESSION_MAPS: Lazy<Vec<HashMap<&'static str, &'static Token>>>
         ("<=", &Token::Binary(Operation::LessThanOrEqual)),
         (">=", &Token::Binary(Operation::GreaterThanOrEqual)),
         ("!=", &Token::Binary(Operation::NotEqual)),
+        ("−", &Token::Binary(Operation::Subtract)),
+        ("·", &Token::Binary(Operation::Multiply)),
+        ("⋅", &Token::Binary(Operation::Multiply)),
+        ("×", &Token::Binary(Operation::Multiply)),
+        ("÷", &Token::Binary(Operation::Divide)),
+        ("≤", &Token::Binary(Operation::LessThanOrEqual)),
+        ("≥", &Token::Binary(Operation::GreaterThanOrEqual)),
+        ("≠", &Token::Binary(Operation::NotEqual)),
     ])
 });
 
+/// Symbols which are read as a named constant, e.g. `2π` is `2 * pi`.
+static NAMED_CONSTANTS: [(char, &str); 2] = [('π', "pi"), ('∞', "infinity")];
+
+/// Radical signs and the function they are read as, e.g. `√x` is `sqrt(x)`.
+static RADICALS: [(char, &str); 2] = [('√', "sqrt"), ('∛', "cbrt")];
+
+static SUPERSCRIPT_DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+
 fn parse_and_map<'a, OutputType, FuncError>(
     name: &'a str,
     function: impl FnMut(&'a str) -> Result<OutputType, FuncError>,
@@ -96,7 +113,8 @@ fn parse_binary_expressions(input: &str) -> IResult<&str, Token> {
         let work_string = match input.len().cmp(&length) {
             Ordering::Less => continue,
             Ordering::Equal => input,
-            Ordering::Greater => &input[..length],
+            Ordering::Greater if input.is_char_boundary(length) => &input[..length],
+            Ordering::Greater => continue,
         };
 
         if let Some(&token) = map.get(work_string) {
@@ -162,53 +180,151 @@ fn parse_decimal(input: &str) -> IResult<&str, Token> {
     )(input)
 }
 
+/// A deliberate limit on the exponents of float literals: `1e1000000` is already a million digits,
+/// and the exact value of a larger one could take longer to compute than a user would wait for, or
+/// run out of memory. Literals above it are refused with [`ParseError::NumberTooLarge`].
+///
+/// [`ParseError::NumberTooLarge`]: super::parser::ParseError::NumberTooLarge
+pub(crate) const MAX_FLOAT_EXPONENT: u32 = 1_000_000;
+
+fn digits<'a>(allowed: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    recognize(many1(terminated(one_of(allowed), many0(char('_')))))
+}
+
+fn exponent<'a>(marker: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    preceded(
+        one_of(marker),
+        recognize(pair(opt(one_of("+-")), digits("0123456789"))),
+    )
+}
+
+#[derive(Debug)]
+enum FloatError {
+    /// Not a float literal, another parser can try.
+    Invalid,
+    /// A float literal whose exponent is above [`MAX_FLOAT_EXPONENT`].
+    TooLarge,
+}
+
+/// Runs `parser` and converts its output with `to_token`. A literal which is too large fails
+/// hard, so it isn't read as something else instead, like `1e1000001` as `1*e1000001`. The
+/// error then points after the literal.
+fn float<'a, O>(
+    mut parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+    mut to_token: impl FnMut(O) -> Result<Token, FloatError>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Token> {
+    move |input| {
+        let (rest, output) = parser(input)?;
+
+        match to_token(output) {
+            Ok(token) => Ok((rest, token)),
+            Err(FloatError::Invalid) => Err(Err::Error(Error::new(input, ErrorKind::MapRes))),
+            Err(FloatError::TooLarge) => Err(Err::Failure(Error::new(rest, ErrorKind::TooLarge))),
+        }
+    }
+}
+
+/// The exact value of the literal `integer.fraction` in base `radix`, multiplied by
+/// `exponent_base^exponent`.
+fn exact_float(
+    radix: u32,
+    integer: &str,
+    fraction: &str,
+    exponent_base: u32,
+    exponent: Option<&str>,
+) -> Result<Token, FloatError> {
+    let digits = str::replace(&format!("{integer}{fraction}"), "_", "");
+    let fraction_digits = str::replace(fraction, "_", "").len();
+    if digits.is_empty() {
+        return Err(FloatError::Invalid);
+    }
+
+    let mantissa = num::BigInt::from_str_radix(&digits, radix).map_err(|_| FloatError::Invalid)?;
+    if mantissa.is_zero() {
+        return Ok(Token::Number(num::BigRational::zero()));
+    }
+    let divisor = num::pow(num::BigInt::from(radix), fraction_digits);
+
+    // the exponent only has digits, so it can only fail to parse by being too large
+    let exponent = exponent.map_or(Ok(0), |exponent| {
+        str::replace(exponent, "_", "")
+            .parse::<i64>()
+            .map_err(|_| FloatError::TooLarge)
+    })?;
+    let magnitude = u32::try_from(exponent.unsigned_abs())
+        .ok()
+        .filter(|magnitude| *magnitude <= MAX_FLOAT_EXPONENT)
+        .ok_or(FloatError::TooLarge)?;
+    let scale = num::pow(num::BigInt::from(exponent_base), magnitude as usize);
+
+    Ok(Token::Number(if exponent >= 0 {
+        reduced(mantissa * scale, divisor)
+    } else {
+        reduced(mantissa, divisor * scale)
+    }))
+}
+
+/// `BigRational::new`, but fast when one side is a huge power: num's gcd takes about one step
+/// per bit of the larger number, so one Euclid step first brings both sides down to the size of
+/// the smaller one.
+fn reduced(numerator: num::BigInt, denominator: num::BigInt) -> num::BigRational {
+    let (smaller, larger) = if numerator.bits() < denominator.bits() {
+        (&numerator, &denominator)
+    } else {
+        (&denominator, &numerator)
+    };
+    let gcd = if smaller.is_zero() {
+        larger.clone()
+    } else {
+        smaller.gcd(&(larger % smaller))
+    };
+    num::BigRational::new_raw(&numerator / &gcd, &denominator / &gcd)
+}
+
 fn parse_float(input: &str) -> IResult<&str, Token> {
     alt((
         // Case one: .42
-        map_res(
-            recognize(tuple((
-                char('.'),
-                parse_decimal,
-                opt(tuple((one_of("eE"), opt(one_of("+-")), parse_decimal))),
-            ))),
-            |out: &str| -> Result<Token, std::num::ParseFloatError> {
-                let number = str::replace(out, "_", "").parse::<f64>()?;
-
-                let float =
-                    num::BigRational::from_float(number).expect("Can't read input to float");
-                Ok(Token::Number(float))
-            },
+        float(
+            tuple((char('.'), digits("0123456789"), opt(exponent("eE")))),
+            |(_, fraction, exponent)| exact_float(10, "", fraction, 10, exponent),
         ), // Case two: 42e42 and 42.42e42
-        map_res(
-            recognize(tuple((
-                parse_decimal,
-                opt(preceded(char('.'), parse_decimal)),
-                one_of("eE"),
-                opt(one_of("+-")),
-                parse_decimal,
-            ))),
-            |out: &str| -> Result<Token, std::num::ParseFloatError> {
-                let number = str::replace(out, "_", "").parse::<f64>()?;
-
-                let float =
-                    num::BigRational::from_float(number).expect("Can't read input to float");
-                Ok(Token::Number(float))
+        float(
+            tuple((
+                digits("0123456789"),
+                opt(preceded(char('.'), digits("0123456789"))),
+                exponent("eE"),
+            )),
+            |(integer, fraction, exponent)| {
+                exact_float(10, integer, fraction.unwrap_or(""), 10, Some(exponent))
             },
         ), // Case three: 42. and 42.42
-        map_res(
-            recognize(tuple((parse_decimal, char('.'), opt(parse_decimal)))),
-            |out: &str| -> Result<Token, std::num::ParseFloatError> {
-                let number = str::replace(out, "_", "").parse::<f64>()?;
-
-                let float =
-                    num::BigRational::from_float(number).expect("Can't read input to float");
-                Ok(Token::Number(float))
+        float(
+            tuple((digits("0123456789"), char('.'), opt(digits("0123456789")))),
+            |(integer, _, fraction)| exact_float(10, integer, fraction.unwrap_or(""), 10, None),
+        ), // Case four: 0x1.8, 0x.8p-1 and 0x1p3, the exponent is binary
+        float(
+            preceded(
+                alt((tag("0x"), tag("0X"))),
+                tuple((
+                    opt(digits("0123456789abcdefABCDEF")),
+                    opt(preceded(char('.'), opt(digits("0123456789abcdefABCDEF")))),
+                    opt(exponent("pP")),
+                )),
+            ),
+            |(integer, fraction, exponent)| {
+                // Without a point or an exponent it's an integer
+                if fraction.is_none() && exponent.is_none() {
+                    return Err(FloatError::Invalid);
+                }
+
+                let fraction = fraction.flatten().unwrap_or("");
+                exact_float(16, integer.unwrap_or(""), fraction, 2, exponent)
             },
         ),
     ))(input)
 }
 
-fn parse_number(input: &str) -> IResult<&str, Token> {
+pub(crate) fn parse_number(input: &str) -> IResult<&str, Token> {
     alt((
         parse_float,
         parse_hexadecimal,
@@ -218,16 +334,44 @@ fn parse_number(input: &str) -> IResult<&str, Token> {
     ))(input)
 }
 
-fn parse_idenifier(input: &str) -> IResult<&str, &str> {
+fn is_identifier_letter(character: char) -> bool {
+    (character.is_alphabetic() || character == '_')
+        && !NAMED_CONSTANTS
+            .iter()
+            .any(|(constant, _)| *constant == character)
+}
+
+pub(crate) fn parse_idenifier(input: &str) -> IResult<&str, &str> {
     let mut iter = input.chars();
     if let Some(first_char) = iter.next() {
         match first_char {
-            'a'..='z' | 'A'..='Z' | '_' => {
-                let num_of_chars = iter
-                    .take_while(|c| matches!(c, 'a'..='z' | 'A'..='Z' | '_' | '0'..='9'))
-                    .count();
+            c if is_identifier_letter(c) => {
+                let mut num_of_bytes = input
+                    .char_indices()
+                    .find(|(_, c)| !is_identifier_letter(*c) && !c.is_ascii_digit())
+                    .map_or(input.len(), |(index, _)| index);
+
+                // A braced subscript like `x_{max}`
+                if input[..num_of_bytes].ends_with('_') {
+                    if let Some(subscript) = input[num_of_bytes..].strip_prefix('{') {
+                        if let Some(length) = subscript.find('}') {
+                            let content = &subscript[..length];
+                            if !content.is_empty()
+                                && content
+                                    .chars()
+                                    .all(|c| is_identifier_letter(c) || c.is_ascii_digit())
+                            {
+                                num_of_bytes += length + 2;
+                            }
+                        }
+                    }
+                }
+
+                // Primes like `f'` or `y''`
+                num_of_bytes += input[num_of_bytes..].len()
+                    - input[num_of_bytes..].trim_start_matches('\'').len();
 
-                let (out, rest) = input.split_at(num_of_chars + 1);
+                let (out, rest) = input.split_at(num_of_bytes);
                 Ok((rest, out))
             }
             _ => IResult::Err(Err::Error(Error {
@@ -244,11 +388,11 @@ pub fn parse_unit(input: &str) -> IResult<&str, Token> {
     alt((
         map_res(
             preceded(parse_number, complete(parse_idenifier)),
-            |s| -> Result<Token, ()> { Ok(Token::Identifier(s.to_string())) },
+            |s| -> Result<Token, ()> { Ok(Token::Identifier(Identifier::parse(s))) },
         ),
         map_res(
             preceded(parse_right_parenthesis, complete(parse_idenifier)),
-            |s| -> Result<Token, ()> { Ok(Token::Identifier(s.to_string())) },
+            |s| -> Result<Token, ()> { Ok(Token::Identifier(Identifier::parse(s))) },
         ),
         map_res(
             preceded(
@@ -272,14 +416,76 @@ pub fn parse_unit(input: &str) -> IResult<&str, Token> {
                 ),
                 complete(parse_idenifier),
             ),
-            |s| -> Result<Token, ()> { Ok(Token::Identifier(s.to_string())) },
+            |s| -> Result<Token, ()> { Ok(Token::Identifier(Identifier::parse(s))) },
         ),
     ))(input)
 }
 
+fn parse_named_constant(input: &str) -> IResult<&str, Token> {
+    for (symbol, name) in NAMED_CONSTANTS {
+        if let Some(rest) = input.strip_prefix(symbol) {
+            return Ok((rest, Token::Identifier(Identifier::new(name))));
+        }
+    }
+
+    Err(Err::Error(Error {
+        input,
+        code: ErrorKind::Char,
+    }))
+}
+
+/// Parses a radical sign and returns the name of the matching function.
+pub(crate) fn parse_radical(input: &str) -> IResult<&str, &'static str> {
+    for (symbol, name) in RADICALS {
+        if let Some(rest) = input.strip_prefix(symbol) {
+            return Ok((rest, name));
+        }
+    }
+
+    Err(Err::Error(Error {
+        input,
+        code: ErrorKind::Char,
+    }))
+}
+
+/// Parses a superscript exponent like `²` or `⁻¹` into the tokens `^ 2` or `^ -1`.
+pub(crate) fn parse_superscript(input: &str) -> IResult<&str, Vec<Token>> {
+    let (rest, negative) = opt(char('⁻'))(input)?;
+
+    let digits = rest
+        .chars()
+        .map_while(|c| SUPERSCRIPT_DIGITS.iter().position(|digit| *digit == c))
+        .collect::<Vec<_>>();
+
+    if digits.is_empty() {
+        return Err(Err::Error(Error {
+            input,
+            code: ErrorKind::Digit,
+        }));
+    }
+
+    let length: usize = rest.chars().take(digits.len()).map(char::len_utf8).sum();
+    let number = digits
+        .into_iter()
+        .fold(num::BigInt::from(0), |acc, digit| acc * 10 + digit);
+
+    let mut tokens = vec![Token::Binary(Operation::Power)];
+    if negative.is_some() {
+        tokens.push(Token::Unary(Operation::Subtract));
+    }
+    tokens.push(Token::Number(number.into()));
+
+    Ok((&rest[length..], tokens))
+}
+
+/// The operand of a radical written without parenthesis, e.g. `2` in `√2`.
+pub(crate) fn parse_radicand(input: &str) -> IResult<&str, Token> {
+    alt((parse_number, parse_named_constant, parse_variable))(input)
+}
+
 fn parse_variable(input: &str) -> IResult<&str, Token> {
     map_res(complete(parse_idenifier), |s| -> Result<Token, ()> {
-        Ok(Token::Identifier(s.to_string()))
+        Ok(Token::Identifier(Identifier::parse(s)))
     })(input)
 }
 
@@ -291,7 +497,7 @@ fn parse_function(input: &str) -> IResult<&str, Token> {
         ),
         |s: &str| -> Result<Token, ()> {
             Ok(Token::Function {
-                name: s.to_string(),
+                name: Identifier::parse(s),
                 num_of_args: None,
             })
         },
@@ -306,11 +512,18 @@ fn parse_unary_sign(input: &str) -> IResult<&str, Token> {
     alt((
         parse_and_map("+", |_| Ok::<Token, ()>(Token::Unary(Operation::Add))),
         parse_and_map("-", |_| Ok::<Token, ()>(Token::Unary(Operation::Subtract))),
+        parse_and_map("−", |_| {
+            Ok::<Token, ()>(Token::Unary(Operation::Subtract))
+        }),
     ))(input)
 }
 
 fn parse_factorial(input: &str) -> IResult<&str, Token> {
-    parse_and_map("!", |_| Ok::<Token, ()>(Token::Unary(Operation::Factorial)))(input)
+    // `!=` is the not equal sign
+    terminated(
+        parse_and_map("!", |_| Ok::<Token, ()>(Token::Unary(Operation::Factorial))),
+        not(char('=')),
+    )(input)
 }
 
 fn parse_left_parenthesis(input: &str) -> IResult<&str, Token> {
@@ -330,6 +543,7 @@ pub(crate) fn parse_left_expression(input: &str) -> IResult<&str, Token> {
         parse_number,
         parse_function,
         parse_variable,
+        parse_named_constant,
         parse_unary_sign,
         parse_left_parenthesis,
     ))(input)
@@ -412,6 +626,14 @@ mod tests {
             ("<=", Token::Binary(Operation::LessThanOrEqual)),
             (">=", Token::Binary(Operation::GreaterThanOrEqual)),
             (">", Token::Binary(Operation::GreaterThan)),
+            ("−", Token::Binary(Operation::Subtract)),
+            ("·", Token::Binary(Operation::Multiply)),
+            ("⋅", Token::Binary(Operation::Multiply)),
+            ("×", Token::Binary(Operation::Multiply)),
+            ("÷", Token::Binary(Operation::Divide)),
+            ("≤", Token::Binary(Operation::LessThanOrEqual)),
+            ("≥", Token::Binary(Operation::GreaterThanOrEqual)),
+            ("≠", Token::Binary(Operation::NotEqual)),
         ];
 
         for case in cases {
@@ -425,26 +647,90 @@ mod tests {
         assert_eq!(Ok(("", "Abc")), parse_idenifier("Abc"));
         assert_eq!(Ok(("", "_abc")), parse_idenifier("_abc"));
         assert_eq!(Ok(("", "a_Bc")), parse_idenifier("a_Bc"));
+        assert_eq!(Ok(("", "ω")), parse_idenifier("ω"));
+        assert_eq!(Ok(("²", "α1")), parse_idenifier("α1²"));
+        assert_eq!(Ok(("π", "r")), parse_idenifier("rπ"));
+    }
+
+    #[test]
+    fn test_parse_superscript() {
+        assert_eq!(
+            Ok((
+                "",
+                vec![
+                    Token::Binary(Operation::Power),
+                    Token::Number(num::BigRational::from_integer(23.into()))
+                ]
+            )),
+            parse_superscript("²³")
+        );
+        assert_eq!(
+            Ok((
+                "x",
+                vec![
+                    Token::Binary(Operation::Power),
+                    Token::Unary(Operation::Subtract),
+                    Token::Number(num::BigRational::from_integer(1.into()))
+                ]
+            )),
+            parse_superscript("⁻¹x")
+        );
+        assert!(parse_superscript("⁻").is_err());
     }
 
     #[test]
     fn test_parse_variable() {
         assert_eq!(
-            Ok(("", Token::Identifier("abc".to_string()))),
+            Ok(("", Token::Identifier(Identifier::new("abc")))),
             parse_variable("abc")
         );
         assert_eq!(
-            Ok(("", Token::Identifier("Abc".to_string()))),
+            Ok(("", Token::Identifier(Identifier::new("Abc")))),
             parse_variable("Abc")
         );
         assert_eq!(
-            Ok(("", Token::Identifier("_abc".to_string()))),
+            Ok(("", Token::Identifier(Identifier::new("_abc")))),
             parse_variable("_abc")
         );
         assert_eq!(
-            Ok(("", Token::Identifier("a_Bc".to_string()))),
+            Ok((
+                "",
+                Token::Identifier(Identifier::new("a").with_subscript("Bc"))
+            )),
             parse_variable("a_Bc")
         );
+        assert_eq!(
+            Ok((
+                "+1",
+                Token::Identifier(Identifier::new("x").with_subscript("max"))
+            )),
+            parse_variable("x_{max}+1")
+        );
+        assert_eq!(
+            Ok(("{", Token::Identifier(Identifier::new("x_")))),
+            parse_variable("x_{")
+        );
+        assert_eq!(
+            Ok((
+                "",
+                Token::Identifier(Identifier::new("y").with_subscript("0").with_primes(2))
+            )),
+            parse_variable("y_0''")
+        );
+    }
+
+    #[test]
+    fn test_parse_function() {
+        assert_eq!(
+            Ok((
+                "x)",
+                Token::Function {
+                    name: Identifier::new("f").with_primes(1),
+                    num_of_args: None
+                }
+            )),
+            parse_function("f'(x)")
+        );
     }
 
     #[test]
@@ -493,37 +779,57 @@ mod tests {
 
     #[test]
     fn test_parse_float() {
+        let rational = |numerator: i64, denominator: i64| {
+            Token::Number(num::BigRational::new(numerator.into(), denominator.into()))
+        };
+
+        assert_eq!(Ok(("", rational(21, 50))), parse_float(".42"));
+        assert_eq!(Ok(("", rational(1, 10))), parse_float("0.1"));
+        assert_eq!(Ok(("", rational(10_000, 1))), parse_float("10e3"));
+        assert_eq!(Ok(("", rational(10_100, 1))), parse_float("10.1e3"));
+        assert_eq!(Ok(("", rational(3, 2000))), parse_float("1.5e-3"));
+        assert_eq!(Ok(("", rational(14_871, 50))), parse_float("297.42"));
         assert_eq!(
-            Ok((
-                "",
-                Token::Number(num::BigRational::from_float(0.42).expect("Can't parse float"))
-            )),
-            parse_float(".42")
+            Ok(("", rational(1_000_001, 1000))),
+            parse_float("1_000.001")
         );
+        assert_eq!(Ok(("", rational(42, 1))), parse_float("42."));
+
+        assert_eq!(Ok(("", rational(12, 1))), parse_float("0x1.8p3"));
+        assert_eq!(Ok(("", rational(1, 4))), parse_float("0x.8p-1"));
+        assert_eq!(Ok(("", rational(31, 2))), parse_float("0xF.8"));
+        assert_eq!(Ok(("", rational(1024, 1))), parse_float("0x1p10"));
+        assert!(parse_float("0x1A").is_err());
 
         assert_eq!(
             Ok((
                 "",
-                Token::Number(num::BigRational::from_float(10e3).expect("Can't parse float"))
+                Token::Number(num::BigRational::from_integer(num::pow(
+                    num::BigInt::from(10),
+                    999
+                )))
             )),
-            parse_float("10e3")
+            parse_float("1e999")
         );
-
         assert_eq!(
             Ok((
                 "",
-                Token::Number(num::BigRational::from_float(10.1e3).expect("Can't parse float"))
+                Token::Number(num::BigRational::new(
+                    num::BigInt::from(3),
+                    num::pow(num::BigInt::from(10), 200_000) * 2
+                ))
             )),
-            parse_float("10.1e3")
+            parse_float("1.5e-200000")
         );
-
+        assert_eq!(Ok(("", rational(0, 1))), parse_float("0e-1000000"));
         assert_eq!(
-            Ok((
-                "",
-                Token::Number(num::BigRational::from_float(297.42).expect("Can't parse float"))
-            )),
-            parse_float("297.42")
+            Err(Err::Failure(Error::new(" + 1", ErrorKind::TooLarge))),
+            parse_float("1e1000001 + 1")
         );
+        assert!(matches!(
+            parse_float("0x1p-99999999999999999999"),
+            Err(Err::Failure(_))
+        ));
     }
 
     /* #[test]
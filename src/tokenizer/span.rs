@@ -0,0 +1,73 @@
+use std::fmt::Display;
+
+/// Byte range `start..end` into the string that was tokenized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    /// An empty span, used for things that are missing at `position`.
+    pub fn point(position: usize) -> Self {
+        Span::new(position, position)
+    }
+
+    /// The smallest span containing both `self` and `other`.
+    pub fn merge(self, other: Span) -> Self {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// Prints the line of `input` containing `span` and underlines the span with carets.
+///
+/// ```text
+/// error: Unexpected token at 4..5
+///   |
+/// 1 | 1 + # 2
+///   |     ^
+/// ```
+pub fn render_diagnostic(input: &str, span: Span, message: &str) -> String {
+    let start = floor_char_boundary(input, span.start);
+    let end = floor_char_boundary(input, span.end.max(start));
+
+    let line_start = input[..start].rfind('\n').map_or(0, |pos| pos + 1);
+    let line_end = input[start..]
+        .find('\n')
+        .map_or(input.len(), |pos| start + pos);
+    let line = input[line_start..line_end].trim_end_matches('\r');
+    let line_number = input[..line_start].matches('\n').count() + 1;
+
+    let column = input[line_start..start].chars().count();
+    let width = input[start..end.min(line_end)].chars().count().max(1);
+
+    let gutter = " ".repeat(line_number.to_string().len());
+
+    format!(
+        "error: {message}\n{gutter} |\n{line_number} | {line}\n{gutter} | {}{}",
+        " ".repeat(column),
+        "^".repeat(width),
+    )
+}
+
+fn floor_char_boundary(input: &str, mut index: usize) -> usize {
+    index = index.min(input.len());
+    while !input.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
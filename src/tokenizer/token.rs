@@ -1,5 +1,7 @@
 use std::fmt::Display;
 
+use crate::ast::Identifier;
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub enum Operation {
     Add,
@@ -59,11 +61,13 @@ pub enum Token {
     RightParenthesis,
     Comma,
     Number(num::BigRational),
-    Identifier(String),
+    Identifier(Identifier),
     Function {
-        name: String,
+        name: Identifier,
         num_of_args: Option<usize>,
     },
+    /// Stands in for a missing operand when tokenizing with error recovery.
+    Placeholder,
 }
 
 #[derive(Clone, Copy)]
@@ -74,28 +78,30 @@ pub enum Associativity {
 }
 
 impl Token {
-    pub fn get_precedence_and_associativity(&self) -> (u32, Associativity) {
+    /// Returns `None` for tokens which are not operators or operations which can't be used in
+    /// this position (e.g. a binary factorial).
+    pub fn get_precedence_and_associativity(&self) -> Option<(u32, Associativity)> {
         match self {
             Token::Binary(operation) => match operation {
-                Operation::Add | Operation::Subtract => (1, Associativity::Left),
+                Operation::Add | Operation::Subtract => Some((1, Associativity::Left)),
                 Operation::Multiply | Operation::Divide | Operation::Mod => {
-                    (2, Associativity::Left)
+                    Some((2, Associativity::Left))
                 }
-                Operation::Power => (4, Associativity::Right),
+                Operation::Power => Some((4, Associativity::Right)),
                 Operation::Equal
                 | Operation::NotEqual
                 | Operation::LessThan
                 | Operation::LessThanOrEqual
                 | Operation::GreaterThanOrEqual
-                | Operation::GreaterThan => (5, Associativity::Left),
-                _ => unimplemented!(),
+                | Operation::GreaterThan => Some((5, Associativity::Left)),
+                Operation::Factorial => None,
             },
             Token::Unary(operation) => match operation {
-                Operation::Add | Operation::Subtract => (3, Associativity::NA),
-                Operation::Factorial => (5, Associativity::NA),
-                _ => unimplemented!(),
+                Operation::Add | Operation::Subtract => Some((3, Associativity::NA)),
+                Operation::Factorial => Some((5, Associativity::NA)),
+                _ => None,
             },
-            _ => (0, Associativity::NA),
+            _ => Some((0, Associativity::NA)),
         }
     }
 }